@@ -0,0 +1,112 @@
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion};
+use nalgebra_glm::{Vec2, Vec3};
+use sistema_solar::color::Color;
+use sistema_solar::fragment::Fragment;
+use sistema_solar::framebuffer::Viewport;
+use sistema_solar::headless;
+use sistema_solar::lighting::Light;
+use sistema_solar::material::Material;
+use sistema_solar::obj::Obj;
+use sistema_solar::shaders::{fragment_shader, vertex_shader};
+use sistema_solar::triangle::triangle;
+use sistema_solar::vertex::Vertex;
+use sistema_solar::CelestialBody;
+
+const WIDTH: usize = 320;
+const HEIGHT: usize = 240;
+
+fn sphere() -> Vec<Vertex> {
+    Obj::load(concat!(env!("CARGO_MANIFEST_DIR"), "/assets/sphere.obj"))
+        .expect("no se pudo cargar la esfera")
+        .get_vertex_array()
+}
+
+fn screen_vertex(x: f32, y: f32) -> Vertex {
+    let mut vertex = Vertex::new(Vec3::new(x, y, 0.0), Vec3::new(0.0, 0.0, 1.0), Vec2::zeros());
+    vertex.transformed_position = Vec3::new(x, y, 0.5);
+    vertex
+}
+
+fn triangle_benchmark(c: &mut Criterion) {
+    // Un triángulo grande (unos 20000 píxeles) y uno de pocos píxeles, como
+    // los de un planeta lejano
+    let cases = [
+        ("grande", [screen_vertex(10.0, 10.0), screen_vertex(230.0, 30.0), screen_vertex(60.0, 220.0)]),
+        ("chico", [screen_vertex(100.0, 100.0), screen_vertex(104.5, 101.0), screen_vertex(101.0, 105.5)]),
+    ];
+    let clip = Viewport::new(0, 0, WIDTH, HEIGHT);
+    let mut group = c.benchmark_group("triangle");
+    for (name, [a, b, c]) in &cases {
+        group.bench_function(*name, |bench| {
+            bench.iter(|| {
+                let mut count = 0usize;
+                triangle(a, b, c, &clip, |fragment| {
+                    count += 1;
+                    black_box(fragment);
+                });
+                count
+            })
+        });
+    }
+    group.finish();
+}
+
+fn vertex_shader_benchmark(c: &mut Criterion) {
+    let sphere = sphere();
+    let material = Material::for_body(CelestialBody::RockyPlanet);
+    let noise = material.noise.build();
+    let lights = [Light::point(Vec3::zeros(), Color::from_hex(0xFFFFFF), 1.0, 0.0)];
+    let uniforms = headless::camera_uniforms(WIDTH, HEIGHT, 0, &noise, &material, &lights);
+
+    c.bench_function("vertex_shader/esfera", |bench| {
+        bench.iter(|| {
+            for vertex in &sphere {
+                black_box(vertex_shader(vertex, &uniforms));
+            }
+        })
+    });
+}
+
+fn fragment_shader_benchmark(c: &mut Criterion) {
+    // Fragmentos sobre la superficie de la esfera, repartidos por todo el modelo
+    let fragments: Vec<Fragment> = sphere()
+        .iter()
+        .step_by(37)
+        .map(|vertex| {
+            let intensity = vertex.normal.z.max(0.0);
+            Fragment::new(0.0, 0.0, Color::new(100, 100, 100), 0.5, vertex.normal, intensity, vertex.position)
+        })
+        .collect();
+    let lights = [Light::point(Vec3::new(4.0, 2.0, 6.0), Color::from_hex(0xFFFFFF), 1.0, 0.0)];
+
+    let materials = std::iter::once(("Sol", Material::for_body(CelestialBody::Sun)))
+        .chain(headless::BODIES.iter().map(|&body| (body.default_name(), Material::for_body(body))))
+        .chain([("Nave", Material::spaceship()), ("Estacion", Material::space_station())]);
+
+    let mut group = c.benchmark_group("fragment_shader");
+    for (name, material) in materials {
+        let noise = material.noise.build();
+        let uniforms = headless::camera_uniforms(WIDTH, HEIGHT, 120, &noise, &material, &lights);
+        group.bench_with_input(BenchmarkId::from_parameter(name), &fragments, |bench, fragments| {
+            bench.iter(|| {
+                for fragment in fragments {
+                    black_box(fragment_shader(fragment, &uniforms));
+                }
+            })
+        });
+    }
+    group.finish();
+}
+
+fn frame_benchmark(c: &mut Criterion) {
+    let sphere = sphere();
+    let mut group = c.benchmark_group("cuadro");
+    group.sample_size(20);
+    group.bench_function("sin_ventana", |bench| {
+        bench.iter(|| black_box(headless::render_frame(&sphere, &headless::FrameOptions::new(WIDTH, HEIGHT, 120))))
+    });
+    group.finish();
+}
+
+criterion_group!(benches, triangle_benchmark, vertex_shader_benchmark, fragment_shader_benchmark, frame_benchmark);
+criterion_main!(benches);
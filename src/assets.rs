@@ -0,0 +1,183 @@
+use std::collections::HashMap;
+use std::rc::Rc;
+use std::time::{Duration, Instant, SystemTime};
+use crate::lod::LodChain;
+use crate::obj::{Obj, ObjError};
+use crate::sky::{self, SkySource};
+use crate::vertex::Vertex;
+
+// Cada cuánto se miran las fechas de los archivos
+const CHECK_INTERVAL: Duration = Duration::from_millis(500);
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct MeshId(usize);
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct TextureId(usize);
+
+struct MeshEntry {
+    path: String,
+    lod: LodChain,
+    // Fecha del archivo cuando se cargó; None si no estaba
+    modified: Option<SystemTime>,
+}
+
+struct TextureEntry {
+    path: String,
+    source: Rc<SkySource>,
+    modified: Option<SystemTime>,
+}
+
+// Lo que se volvió a cargar en `reload_changed`
+#[derive(Debug, Default)]
+pub struct Reloaded {
+    pub meshes: Vec<MeshId>,
+    pub textures: Vec<TextureId>,
+}
+
+// Modelos y texturas cargados, por ruta. Cada modelo se guarda con sus
+// niveles simplificados. Pedir dos veces la misma ruta
+// devuelve lo mismo sin volver a leer el archivo, y `reload_changed` vuelve
+// a leer los que cambiaron en disco, así un modelo exportado de nuevo desde
+// Blender se ve en el próximo cuadro.
+pub struct Assets {
+    meshes: Vec<MeshEntry>,
+    textures: Vec<TextureEntry>,
+    mesh_paths: HashMap<String, MeshId>,
+    texture_paths: HashMap<String, TextureId>,
+    last_check: Instant,
+}
+
+impl Assets {
+    pub fn new() -> Self {
+        Assets {
+            meshes: Vec::new(),
+            textures: Vec::new(),
+            mesh_paths: HashMap::new(),
+            texture_paths: HashMap::new(),
+            last_check: Instant::now(),
+        }
+    }
+
+    // El archivo tiene prioridad, así se puede cambiar sin recompilar. Si no
+    // está se usa `embedded` (la copia del binario) sin avisar; si está roto
+    // se avisa. Si tampoco sirve la copia, queda `fallback`.
+    pub fn load_mesh(&mut self, path: &str, embedded: &str, fallback: fn() -> Obj) -> MeshId {
+        if let Some(&id) = self.mesh_paths.get(path) {
+            return id;
+        }
+
+        let obj = match Obj::load(path) {
+            Ok(obj) => obj,
+            Err(err) => {
+                if !matches!(err, ObjError::Open(_)) {
+                    eprintln!("No se pudo cargar el modelo: {}", err);
+                }
+                Obj::parse(path, embedded).unwrap_or_else(|err| {
+                    eprintln!("No se pudo cargar el modelo incluido: {}", err);
+                    fallback()
+                })
+            }
+        };
+
+        let id = MeshId(self.meshes.len());
+        self.meshes.push(MeshEntry { path: path.to_string(), lod: prepare(path, obj), modified: modified(path) });
+        self.mesh_paths.insert(path.to_string(), id);
+        id
+    }
+
+    // El modelo completo
+    pub fn mesh(&self, id: MeshId) -> &[Vertex] {
+        self.meshes[id.0].lod.full()
+    }
+
+    pub fn lod(&self, id: MeshId) -> &LodChain {
+        &self.meshes[id.0].lod
+    }
+
+    // `path` como en `sky::load_background`
+    pub fn load_texture(&mut self, path: &str) -> Result<TextureId, String> {
+        if let Some(&id) = self.texture_paths.get(path) {
+            return Ok(id);
+        }
+
+        let source = Rc::new(sky::load_background(path)?);
+        let id = TextureId(self.textures.len());
+        self.textures.push(TextureEntry { path: path.to_string(), source, modified: modified(path) });
+        self.texture_paths.insert(path.to_string(), id);
+        Ok(id)
+    }
+
+    pub fn texture(&self, id: TextureId) -> Rc<SkySource> {
+        Rc::clone(&self.textures[id.0].source)
+    }
+
+    // Vuelve a leer los archivos cuya fecha cambió desde que se cargaron.
+    // Si la nueva versión no se puede leer (por ejemplo, porque todavía se
+    // está escribiendo) se avisa y se queda la anterior. Mira el disco a lo
+    // sumo cada CHECK_INTERVAL.
+    pub fn reload_changed(&mut self) -> Reloaded {
+        let mut reloaded = Reloaded::default();
+        if self.last_check.elapsed() < CHECK_INTERVAL {
+            return reloaded;
+        }
+        self.last_check = Instant::now();
+
+        for (index, entry) in self.meshes.iter_mut().enumerate() {
+            let current = modified(&entry.path);
+            if current.is_none() || current == entry.modified {
+                continue;
+            }
+            entry.modified = current;
+            match Obj::load(&entry.path) {
+                Ok(obj) => {
+                    entry.lod = prepare(&entry.path, obj);
+                    reloaded.meshes.push(MeshId(index));
+                }
+                Err(err) => eprintln!("No se pudo recargar el modelo: {}", err),
+            }
+        }
+
+        for (index, entry) in self.textures.iter_mut().enumerate() {
+            let current = modified(&entry.path);
+            if current.is_none() || current == entry.modified {
+                continue;
+            }
+            entry.modified = current;
+            match sky::load_background(&entry.path) {
+                Ok(source) => {
+                    entry.source = Rc::new(source);
+                    reloaded.textures.push(TextureId(index));
+                }
+                Err(err) => eprintln!("No se pudo recargar el fondo {}: {}", entry.path, err),
+            }
+        }
+
+        reloaded
+    }
+}
+
+// Distancia por debajo de la cual dos vértices iguales se juntan
+const WELD_EPSILON: f32 = 1e-5;
+
+// Valida el modelo antes de usarlo, avisa si hubo que descartar algo y arma
+// los niveles simplificados
+fn prepare(path: &str, mut obj: Obj) -> LodChain {
+    let validation = obj.validate(Some(WELD_EPSILON));
+    if validation.has_problems() {
+        eprintln!("{}: se descartaron partes del modelo ({})", path, validation);
+    }
+    LodChain::new(obj)
+}
+
+// Fecha de modificación del archivo; para un cubemap (`<prefijo>_*.<ext>`),
+// la de la cara más reciente
+fn modified(path: &str) -> Option<SystemTime> {
+    let date = |path: &str| std::fs::metadata(path).and_then(|metadata| metadata.modified()).ok();
+    match path.split_once("_*.") {
+        Some((prefix, extension)) => ["rt", "lf", "up", "dn", "bk", "ft"].iter()
+            .filter_map(|face| date(&format!("{}_{}.{}", prefix, face, extension)))
+            .max(),
+        None => date(path),
+    }
+}
@@ -0,0 +1,79 @@
+use std::fs;
+use nalgebra_glm::Vec3;
+use crate::tween::CameraPose;
+
+pub const SLOTS: usize = 5;
+
+// Poses de cámara guardadas por el usuario. Se escriben en un archivo de
+// texto con una línea por ranura:
+//   marcador <ranura> <ojo x y z> <centro x y z> <arriba x y z>
+pub struct Bookmarks {
+    path: String,
+    slots: [Option<CameraPose>; SLOTS],
+}
+
+impl Bookmarks {
+    pub fn new(path: &str) -> Self {
+        Bookmarks { path: path.to_string(), slots: [None; SLOTS] }
+    }
+
+    // Si el archivo no existe se empieza sin marcadores
+    pub fn load(path: &str) -> Result<Self, String> {
+        let mut bookmarks = Bookmarks::new(path);
+        let Ok(contents) = fs::read_to_string(path) else {
+            return Ok(bookmarks);
+        };
+
+        for (number, line) in contents.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let error = || format!("{}:{}: línea inválida '{}'", path, number + 1, line);
+
+            let mut parts = line.split_whitespace();
+            if parts.next() != Some("marcador") {
+                return Err(error());
+            }
+            let slot: usize = parts.next().and_then(|slot| slot.parse().ok()).ok_or_else(error)?;
+            let values: Vec<f32> = parts.map(|value| value.parse()).collect::<Result<_, _>>().map_err(|_| error())?;
+            if slot == 0 || slot > SLOTS || values.len() != 9 {
+                return Err(error());
+            }
+
+            bookmarks.slots[slot - 1] = Some(CameraPose {
+                eye: Vec3::new(values[0], values[1], values[2]),
+                center: Vec3::new(values[3], values[4], values[5]),
+                up: Vec3::new(values[6], values[7], values[8]),
+            });
+        }
+        Ok(bookmarks)
+    }
+
+    pub fn save(&self) -> Result<(), String> {
+        let mut contents = String::from("# Marcadores de cámara: ranura, ojo, centro, arriba\n");
+        for (slot, pose) in self.slots.iter().enumerate() {
+            if let Some(pose) = pose {
+                contents += &format!(
+                    "marcador {} {} {} {} {} {} {} {} {} {}\n",
+                    slot + 1,
+                    pose.eye.x, pose.eye.y, pose.eye.z,
+                    pose.center.x, pose.center.y, pose.center.z,
+                    pose.up.x, pose.up.y, pose.up.z,
+                );
+            }
+        }
+        fs::write(&self.path, contents).map_err(|err| format!("{}: {}", self.path, err))
+    }
+
+    // Las ranuras van de 0 a SLOTS - 1
+    pub fn get(&self, slot: usize) -> Option<CameraPose> {
+        self.slots.get(slot).copied().flatten()
+    }
+
+    pub fn set(&mut self, slot: usize, pose: CameraPose) {
+        if slot < SLOTS {
+            self.slots[slot] = Some(pose);
+        }
+    }
+}
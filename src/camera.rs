@@ -1,6 +1,30 @@
-use nalgebra_glm::{Vec3, rotate_vec3};
+use nalgebra_glm::Vec3;
 use std::f32::consts::PI;
 
+// Cómo se arma la vista: cámara libre que orbita, persecución detrás de la
+// nave, cabina desde la nariz de la nave o vuelo libre de la cámara sola
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum CameraMode {
+    Orbit,
+    Chase,
+    Cockpit,
+    FreeFly,
+}
+
+impl CameraMode {
+    pub fn next(self) -> Self {
+        match self {
+            CameraMode::Orbit => CameraMode::Chase,
+            CameraMode::Chase => CameraMode::Cockpit,
+            CameraMode::Cockpit => CameraMode::FreeFly,
+            CameraMode::FreeFly => CameraMode::Orbit,
+        }
+    }
+}
+
+// Radianes por cuadro al girar con las teclas, con la sensibilidad en 1
+pub const DEFAULT_ROTATION_SPEED: f32 = 0.03;
+
 pub struct Camera {
     pub eye: Vec3,
     pub center: Vec3,
@@ -23,7 +47,7 @@ impl Camera {
             pitch: 0.0,
             roll: 0.0,
             movement_speed: 0.5,
-            rotation_speed: 0.03,
+            rotation_speed: DEFAULT_ROTATION_SPEED,
             has_changed: true,
         }
     }
@@ -103,6 +127,66 @@ impl Camera {
         self.has_changed = true;
     }
 
+    // Desplaza la cámara sin cambiar hacia dónde mira
+    pub fn translate(&mut self, offset: Vec3) {
+        self.eye += offset;
+        self.center += offset;
+        self.has_changed = true;
+    }
+
+    // Gira la vista sobre el propio ojo, en radianes y en los ejes de la
+    // cámara. A diferencia de `orbit`, no hay límite de cabeceo ni un "arriba"
+    // fijo: el alabeo inclina `up`.
+    pub fn free_look(&mut self, delta_yaw: f32, delta_pitch: f32, delta_roll: f32) {
+        let distance = (self.center - self.eye).magnitude().max(1.0);
+        let mut forward = self.get_view_direction();
+        let right = self.get_right();
+        let mut up = right.cross(&forward).normalize();
+
+        forward = nalgebra_glm::rotate_vec3(&forward, delta_yaw, &up);
+        forward = nalgebra_glm::rotate_vec3(&forward, delta_pitch, &right);
+        up = nalgebra_glm::rotate_vec3(&up, delta_pitch, &right);
+        up = nalgebra_glm::rotate_vec3(&up, delta_roll, &forward);
+
+        // Reortogonaliza para que el error no se acumule
+        let right = forward.cross(&up).normalize();
+        self.up = right.cross(&forward).normalize();
+        self.center = self.eye + forward.normalize() * distance;
+        self.has_changed = true;
+    }
+
+    // Saca el ojo de una esfera empujándolo por la normal hasta su superficie.
+    // Si la cámara mira a algo dentro de la esfera (orbitando el planeta) solo
+    // se mueve el ojo; si no, se mueve la vista entera para no girarla.
+    pub fn push_out_of_sphere(&mut self, sphere_center: Vec3, radius: f32) -> bool {
+        let offset = self.eye - sphere_center;
+        let distance = offset.magnitude();
+        if distance >= radius {
+            return false;
+        }
+
+        let normal = if distance > 1e-5 { offset / distance } else { -self.get_view_direction() };
+        let correction = sphere_center + normal * radius - self.eye;
+        if (self.center - sphere_center).magnitude() >= radius {
+            self.center += correction;
+        }
+        self.eye += correction;
+        self.has_changed = true;
+        true
+    }
+
+    // Recalcula yaw y pitch desde la posición actual, para que la órbita siga
+    // desde donde quedó la cámara tras moverla por otro medio
+    pub fn sync_orbit_angles(&mut self) {
+        let radius_vector = self.eye - self.center;
+        let radius = radius_vector.magnitude();
+        if radius <= 0.0 {
+            return;
+        }
+        self.yaw = radius_vector.z.atan2(radius_vector.x);
+        self.pitch = (-radius_vector.y / radius).asin().clamp(-PI / 2.0 + 0.1, PI / 2.0 - 0.1);
+    }
+
     pub fn set_movement_speed(&mut self, speed: f32) {
         self.movement_speed = speed;
     }
@@ -0,0 +1,85 @@
+use nalgebra_glm::Vec3;
+use crate::tween::CameraPose;
+
+// Recorrido de cámara grabado por el usuario: poses clave unidas por una
+// curva Catmull-Rom, que pasa por cada una sin quiebres
+pub struct CameraPath {
+    keyframes: Vec<CameraPose>,
+}
+
+impl CameraPath {
+    pub fn new() -> Self {
+        CameraPath { keyframes: Vec::new() }
+    }
+
+    pub fn add(&mut self, pose: CameraPose) {
+        self.keyframes.push(pose);
+    }
+
+    pub fn clear(&mut self) {
+        self.keyframes.clear();
+    }
+
+    pub fn len(&self) -> usize {
+        self.keyframes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.keyframes.is_empty()
+    }
+
+    // Pose en `t` entre 0 (primera clave) y 1 (última). Cada tramo entre
+    // claves dura lo mismo.
+    pub fn sample(&self, t: f32) -> Option<CameraPose> {
+        let last = self.keyframes.len().checked_sub(1)?;
+        if last == 0 {
+            return Some(self.keyframes[0]);
+        }
+
+        let position = t.clamp(0.0, 1.0) * last as f32;
+        let segment = (position.floor() as usize).min(last - 1);
+        let local = position - segment as f32;
+
+        // En los extremos se repite la clave para que la curva no se salga
+        let key = |i: isize| self.keyframes[i.clamp(0, last as isize) as usize];
+        let i = segment as isize;
+        let (p0, p1, p2, p3) = (key(i - 1), key(i), key(i + 1), key(i + 2));
+
+        Some(CameraPose {
+            eye: catmull_rom(p0.eye, p1.eye, p2.eye, p3.eye, local),
+            center: catmull_rom(p0.center, p1.center, p2.center, p3.center, local),
+            up: catmull_rom(p0.up, p1.up, p2.up, p3.up, local).normalize(),
+        })
+    }
+}
+
+fn catmull_rom(p0: Vec3, p1: Vec3, p2: Vec3, p3: Vec3, t: f32) -> Vec3 {
+    let t2 = t * t;
+    let t3 = t2 * t;
+    (p1 * 2.0
+        + (p2 - p0) * t
+        + (p0 * 2.0 - p1 * 5.0 + p2 * 4.0 - p3) * t2
+        + (p1 * 3.0 - p0 - p2 * 3.0 + p3) * t3)
+        * 0.5
+}
+
+// Reproducción del recorrido en una cantidad fija de cuadros
+pub struct PathPlayback {
+    duration: u32,
+    elapsed: u32,
+}
+
+impl PathPlayback {
+    pub fn new(duration: u32) -> Self {
+        PathPlayback { duration: duration.max(1), elapsed: 0 }
+    }
+
+    // Avanza un cuadro; devuelve None cuando terminó
+    pub fn step(&mut self, path: &CameraPath) -> Option<CameraPose> {
+        if self.elapsed >= self.duration {
+            return None;
+        }
+        self.elapsed += 1;
+        path.sample(self.elapsed as f32 / self.duration as f32)
+    }
+}
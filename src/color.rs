@@ -1,14 +1,20 @@
 use std::fmt;
 
+// Color RGB en punto flotante, 1.0 = blanco de pantalla. Los valores pueden
+// pasar de 1.0 (HDR) y se comprimen al presentar con el tonemapping.
 #[derive(Debug, Clone, Copy)]
 pub struct Color {
-    r: u8,
-    g: u8,
-    b: u8,
+    r: f32,
+    g: f32,
+    b: f32,
 }
 
 impl Color {
     pub const fn new(r: u8, g: u8, b: u8) -> Self {
+        Color { r: r as f32 / 255.0, g: g as f32 / 255.0, b: b as f32 / 255.0 }
+    }
+
+    pub const fn from_rgb_f32(r: f32, g: f32, b: f32) -> Self {
         Color { r, g, b }
     }
 
@@ -16,20 +22,141 @@ impl Color {
         let r = ((hex >> 16) & 0xFF) as u8;
         let g = ((hex >> 8) & 0xFF) as u8;
         let b = (hex & 0xFF) as u8;
-        Color { r, g, b }
+        Color::new(r, g, b)
+    }
+
+    // `hue` en grados (0..360), saturación y valor en 0..1
+    pub fn from_hsv(hue: f32, saturation: f32, value: f32) -> Self {
+        let hue = hue.rem_euclid(360.0) / 60.0;
+        let chroma = value * saturation;
+        let x = chroma * (1.0 - (hue % 2.0 - 1.0).abs());
+        let m = value - chroma;
+
+        let (r, g, b) = match hue as u32 {
+            0 => (chroma, x, 0.0),
+            1 => (x, chroma, 0.0),
+            2 => (0.0, chroma, x),
+            3 => (0.0, x, chroma),
+            4 => (x, 0.0, chroma),
+            _ => (chroma, 0.0, x),
+        };
+        Color { r: r + m, g: g + m, b: b + m }
+    }
+
+    // Devuelve (tono en grados, saturación, valor)
+    pub fn to_hsv(self) -> (f32, f32, f32) {
+        let max = self.r.max(self.g).max(self.b);
+        let min = self.r.min(self.g).min(self.b);
+        let delta = max - min;
+
+        let hue = if delta <= 0.0 {
+            0.0
+        } else if max == self.r {
+            60.0 * ((self.g - self.b) / delta).rem_euclid(6.0)
+        } else if max == self.g {
+            60.0 * ((self.b - self.r) / delta + 2.0)
+        } else {
+            60.0 * ((self.r - self.g) / delta + 4.0)
+        };
+        let saturation = if max <= 0.0 { 0.0 } else { delta / max };
+        (hue, saturation, max)
+    }
+
+    // Color aproximado (sRGB) de un cuerpo negro a `kelvin` grados,
+    // con la aproximación de Tanner Helland entre 1000 K y 40000 K
+    pub fn from_temperature(kelvin: f32) -> Self {
+        let t = kelvin.clamp(1000.0, 40000.0) / 100.0;
+
+        let r = if t <= 66.0 {
+            255.0
+        } else {
+            329.698_73 * (t - 60.0).powf(-0.133_204_76)
+        };
+        let g = if t <= 66.0 {
+            99.470_8 * t.ln() - 161.119_57
+        } else {
+            288.122_16 * (t - 60.0).powf(-0.075_514_85)
+        };
+        let b = if t >= 66.0 {
+            255.0
+        } else if t <= 19.0 {
+            0.0
+        } else {
+            138.517_73 * (t - 10.0).ln() - 305.044_8
+        };
+
+        Color {
+            r: (r / 255.0).clamp(0.0, 1.0),
+            g: (g / 255.0).clamp(0.0, 1.0),
+            b: (b / 255.0).clamp(0.0, 1.0),
+        }
+    }
+
+    // Gira el tono manteniendo saturación y brillo
+    pub fn hue_shift(self, degrees: f32) -> Self {
+        let (hue, saturation, value) = self.to_hsv();
+        Color::from_hsv(hue + degrees, saturation, value)
     }
 
     pub const fn black() -> Self {
-        Color { r: 0, g: 0, b: 0 }
+        Color { r: 0.0, g: 0.0, b: 0.0 }
+    }
+
+    pub const fn white() -> Self {
+        Color { r: 1.0, g: 1.0, b: 1.0 }
+    }
+
+    pub fn r(&self) -> f32 {
+        self.r
+    }
+
+    pub fn g(&self) -> f32 {
+        self.g
+    }
+
+    pub fn b(&self) -> f32 {
+        self.b
+    }
+
+    // Recorta a 0..1 y empaqueta en 0xRRGGBB
+    pub fn to_hex(self) -> u32 {
+        let channel = |value: f32| (value.clamp(0.0, 1.0) * 255.0).round() as u32;
+        (channel(self.r) << 16) | (channel(self.g) << 8) | channel(self.b)
+    }
+
+    // sRGB (lo que ve la pantalla) a lineal, para hacer la iluminación y mezclas
+    pub fn to_linear(self) -> Color {
+        let decode = |c: f32| {
+            if c <= 0.04045 { c / 12.92 } else { ((c + 0.055) / 1.055).powf(2.4) }
+        };
+        Color { r: decode(self.r), g: decode(self.g), b: decode(self.b) }
     }
 
-    pub fn to_hex(&self) -> u32 {
-        ((self.r as u32) << 16) | ((self.g as u32) << 8) | (self.b as u32)
+    // Lineal a sRGB, al escribir el buffer de salida
+    pub fn to_srgb(self) -> Color {
+        let encode = |c: f32| {
+            let c = c.max(0.0);
+            if c <= 0.0031308 { c * 12.92 } else { 1.055 * c.powf(1.0 / 2.4) - 0.055 }
+        };
+        Color { r: encode(self.r), g: encode(self.g), b: encode(self.b) }
+    }
+
+    // Recorta cada canal a 0..1 (pierde el HDR)
+    pub fn clamp(self) -> Self {
+        Color {
+            r: self.r.clamp(0.0, 1.0),
+            g: self.g.clamp(0.0, 1.0),
+            b: self.b.clamp(0.0, 1.0),
+        }
+    }
+
+    pub fn luminance(&self) -> f32 {
+        0.2126 * self.r + 0.7152 * self.g + 0.0722 * self.b
     }
 
     pub const fn transparent() -> Self {
 
-        Color { r: 0, g: 0, b: 0 }
+        Color { r: 0.0, g: 0.0, b: 0.0 }
 
     }
 
@@ -37,75 +164,107 @@ impl Color {
     pub fn lerp(&self, other: &Color, t: f32) -> Self {
         let t = t.clamp(0.0, 1.0);
         Color {
-            r: (self.r as f32 + (other.r as f32 - self.r as f32) * t).round() as u8,
-            g: (self.g as f32 + (other.g as f32 - self.g as f32) * t).round() as u8,
-            b: (self.b as f32 + (other.b as f32 - self.b as f32) * t).round() as u8,
+            r: self.r + (other.r - self.r) * t,
+            g: self.g + (other.g - self.g) * t,
+            b: self.b + (other.b - self.b) * t,
         }
     }
 
     pub fn is_black(&self) -> bool {
-        self.r == 0 && self.g == 0 && self.b == 0 
+        self.r <= 0.0 && self.g <= 0.0 && self.b <= 0.0
     }
 
     pub fn blend_normal(&self, blend: &Color) -> Color {
         if blend.is_black() { *self } else { *blend }
       }
-    
+
     pub fn blend_multiply(&self, blend: &Color) -> Color {
-        Color::new(
-            ((self.r as f32 * blend.r as f32) / 255.0) as u8,
-            ((self.g as f32 * blend.g as f32) / 255.0) as u8,
-            ((self.b as f32 * blend.b as f32) / 255.0) as u8
-        )
+        Color {
+            r: self.r * blend.r,
+            g: self.g * blend.g,
+            b: self.b * blend.b,
+        }
     }
-    
+
     pub fn blend_add(&self, blend: &Color) -> Color {
-        Color::new(
-            (self.r as u16 + blend.r as u16).min(255) as u8,
-            (self.g as u16 + blend.g as u16).min(255) as u8,
-            (self.b as u16 + blend.b as u16).min(255) as u8
-        )
+        *self + *blend
     }
-    
-    pub fn blend_subtract(&self, blend: &Color) -> Color {
-        let r = (self.r as i16 - blend.r as i16).max(0).min(255) as u8;
-        let g = (self.g as i16 - blend.g as i16).max(0).min(255) as u8;
-        let b = (self.b as i16 - blend.b as i16).max(0).min(255) as u8;
 
-        Color::new(r, g, b)
+    pub fn blend_subtract(&self, blend: &Color) -> Color {
+        Color {
+            r: (self.r - blend.r).max(0.0),
+            g: (self.g - blend.g).max(0.0),
+            b: (self.b - blend.b).max(0.0),
+        }
     }
 }
 
-use std::ops::Add;
+use std::ops::{Add, AddAssign, Mul, MulAssign, Sub};
 
 impl Add for Color {
     type Output = Color;
 
     fn add(self, other: Color) -> Color {
         Color {
-            r: self.r.saturating_add(other.r),
-            g: self.g.saturating_add(other.g),
-            b: self.b.saturating_add(other.b),
+            r: self.r + other.r,
+            g: self.g + other.g,
+            b: self.b + other.b,
         }
     }
 }
 
-use std::ops::Mul;
+impl AddAssign for Color {
+    fn add_assign(&mut self, other: Color) {
+        *self = *self + other;
+    }
+}
+
+// Resta por canal sin bajar de 0
+impl Sub for Color {
+    type Output = Color;
+
+    fn sub(self, other: Color) -> Color {
+        self.blend_subtract(&other)
+    }
+}
 
 impl Mul<f32> for Color {
     type Output = Color;
 
     fn mul(self, scalar: f32) -> Color {
         Color {
-            r: (self.r as f32 * scalar).clamp(0.0, 255.0) as u8,
-            g: (self.g as f32 * scalar).clamp(0.0, 255.0) as u8,
-            b: (self.b as f32 * scalar).clamp(0.0, 255.0) as u8,
+            r: (self.r * scalar).max(0.0),
+            g: (self.g * scalar).max(0.0),
+            b: (self.b * scalar).max(0.0),
         }
     }
 }
 
+impl Mul<Color> for f32 {
+    type Output = Color;
+
+    fn mul(self, color: Color) -> Color {
+        color * self
+    }
+}
+
+// Producto por canal: tiñe un color con otro (luz de color sobre una superficie)
+impl Mul for Color {
+    type Output = Color;
+
+    fn mul(self, other: Color) -> Color {
+        self.blend_multiply(&other)
+    }
+}
+
+impl MulAssign<f32> for Color {
+    fn mul_assign(&mut self, scalar: f32) {
+        *self = *self * scalar;
+    }
+}
+
 impl fmt::Display for Color {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f, "Color(r: {}, g: {}, b: {})", self.r, self.g, self.b)
+        write!(f, "Color(r: {:.3}, g: {:.3}, b: {:.3})", self.r, self.g, self.b)
     }
 }
@@ -0,0 +1,66 @@
+use nalgebra_glm::Vec3;
+use std::fs;
+
+pub struct Constellation {
+    pub name: String,
+    // Direcciones unitarias hacia cada estrella
+    pub stars: Vec<Vec3>,
+    pub lines: Vec<(usize, usize)>,
+}
+
+// Ascensión recta (horas) y declinación (grados) a una dirección con y arriba
+fn direction(right_ascension: f32, declination: f32) -> Vec3 {
+    let ra = right_ascension / 24.0 * std::f32::consts::TAU;
+    let dec = declination.to_radians();
+    Vec3::new(dec.cos() * ra.cos(), dec.sin(), dec.cos() * ra.sin())
+}
+
+// Lee el archivo de constelaciones (ver assets/constellations.txt)
+pub fn load(filename: &str) -> Result<Vec<Constellation>, String> {
+    let source = fs::read_to_string(filename).map_err(|err| format!("{}: {}", filename, err))?;
+    let mut constellations: Vec<Constellation> = Vec::new();
+
+    for (number, line) in source.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        let error = |message: &str| format!("{}:{}: {}", filename, number + 1, message);
+        let (keyword, rest) = line.split_once(' ').unwrap_or((line, ""));
+
+        if keyword == "constelacion" {
+            constellations.push(Constellation {
+                name: rest.trim().to_string(),
+                stars: Vec::new(),
+                lines: Vec::new(),
+            });
+            continue;
+        }
+
+        let current = constellations.last_mut().ok_or_else(|| error("falta 'constelacion' antes"))?;
+        let values: Vec<&str> = rest.split_whitespace().collect();
+        if values.len() != 2 {
+            return Err(error("se esperaban dos valores"));
+        }
+
+        match keyword {
+            "estrella" => {
+                let ra: f32 = values[0].parse().map_err(|_| error("ascensión recta inválida"))?;
+                let dec: f32 = values[1].parse().map_err(|_| error("declinación inválida"))?;
+                current.stars.push(direction(ra, dec));
+            }
+            "linea" => {
+                let a: usize = values[0].parse().map_err(|_| error("índice inválido"))?;
+                let b: usize = values[1].parse().map_err(|_| error("índice inválido"))?;
+                if a >= current.stars.len() || b >= current.stars.len() {
+                    return Err(error("la línea usa una estrella que no existe"));
+                }
+                current.lines.push((a, b));
+            }
+            _ => return Err(error("palabra clave desconocida")),
+        }
+    }
+
+    Ok(constellations)
+}
@@ -0,0 +1,110 @@
+use nalgebra_glm::{Vec3, Vec4};
+use std::f32::consts::PI;
+use crate::framebuffer::Framebuffer;
+use crate::Uniforms;
+use crate::shaders::encode_depth;
+
+const PROMINENCES: usize = 5;
+
+// Halo de la corona y arcos de protuberancias alrededor del sol.
+// Se dibuja como un billboard en pantalla centrado en el sol.
+pub fn render_corona(framebuffer: &mut Framebuffer, uniforms: &Uniforms, center: Vec3, radius: f32) {
+    let time = uniforms.time as f32 * 0.01;
+
+    let view_center = uniforms.view_matrix * Vec4::new(center.x, center.y, center.z, 1.0);
+    if view_center.z > -radius {
+        return;
+    }
+
+    let (cx, cy, _) = project(uniforms, view_center);
+    let (ex, ey, _) = project(uniforms, view_center + Vec4::new(radius, 0.0, 0.0, 0.0));
+    let (_, _, depth) = project(uniforms, view_center + Vec4::new(0.0, 0.0, radius, 0.0));
+    let radius_px = ((ex - cx).powi(2) + (ey - cy).powi(2)).sqrt();
+
+    if radius_px < 1.0 {
+        return;
+    }
+
+    let corona_color = uniforms.palette(255, 180, 80);
+    let prominence_color = uniforms.palette(255, 90, 30);
+
+    let extent = radius_px * 2.2;
+    let min_x = (cx - extent).max(0.0) as usize;
+    let max_x = (cx + extent).min(framebuffer.width as f32 - 1.0).max(0.0) as usize;
+    let min_y = (cy - extent).max(0.0) as usize;
+    let max_y = (cy + extent).min(framebuffer.height as f32 - 1.0).max(0.0) as usize;
+
+    // Cada protuberancia tiene su ángulo base, ancho y altura animados con ruido
+    let prominences: Vec<(f32, f32, f32)> = (0..PROMINENCES).map(|i| {
+        let seed = i as f32 * 37.0;
+        let angle = i as f32 / PROMINENCES as f32 * 2.0 * PI
+            + uniforms.noise.get_noise_2d(seed, time * 2.0) * 0.6;
+        let width = 0.12 + uniforms.noise.get_noise_2d(seed + 100.0, time * 3.0).abs() * 0.12;
+        let height = 0.15 + uniforms.noise.get_noise_2d(seed + 200.0, time * 4.0).abs() * 0.35;
+        (angle, width, height)
+    }).collect();
+
+    for y in min_y..=max_y {
+        for x in min_x..=max_x {
+            let dx = x as f32 + 0.5 - cx;
+            let dy = y as f32 + 0.5 - cy;
+            let d = (dx * dx + dy * dy).sqrt() / radius_px;
+
+            if !(1.0..=2.2).contains(&d) {
+                continue;
+            }
+
+            let angle = dy.atan2(dx);
+
+            // Corona: caída exponencial con serpentinas que cambian con el tiempo
+            let streamers = uniforms.noise.get_noise_3d(
+                angle.cos() * 3.0,
+                angle.sin() * 3.0,
+                time * 1.5
+            ) * 0.5 + 0.5;
+            let halo = (-(d - 1.0) * 5.0).exp() * (0.5 + streamers * 0.5);
+            let mut color = corona_color * (halo * 0.8);
+
+            for &(base, width, height) in &prominences {
+                let offset = angle_difference(angle, base) / width;
+                if offset.abs() >= 1.0 {
+                    continue;
+                }
+
+                let arc = 1.0 + height * (1.0 - offset * offset).sqrt();
+                let thickness = 0.04 + 0.02 * (1.0 - offset.abs());
+                let distance = (d - arc).abs();
+
+                if distance < thickness {
+                    let flicker = uniforms.noise.get_noise_3d(
+                        angle * 20.0,
+                        d * 20.0,
+                        time * 5.0
+                    ) * 0.3 + 0.7;
+                    let strength = (1.0 - distance / thickness) * flicker;
+                    color += prominence_color * strength;
+                }
+            }
+
+            framebuffer.point_additive(x, y, depth, color);
+        }
+    }
+}
+
+fn project(uniforms: &Uniforms, view_position: Vec4) -> (f32, f32, f32) {
+    let clip = uniforms.projection_matrix * view_position;
+    let ndc = clip / clip.w;
+
+    let (screen_x, screen_y) = uniforms.ndc_to_screen(ndc.x, ndc.y);
+    (screen_x, screen_y, encode_depth(&clip, uniforms.depth_mode))
+}
+
+fn angle_difference(a: f32, b: f32) -> f32 {
+    let mut diff = (a - b) % (2.0 * PI);
+    if diff > PI {
+        diff -= 2.0 * PI;
+    } else if diff < -PI {
+        diff += 2.0 * PI;
+    }
+    diff
+}
@@ -0,0 +1,194 @@
+use std::ops::RangeInclusive;
+use crate::framebuffer::{Framebuffer, DepthTest, Viewport};
+use crate::text;
+use crate::Uniforms;
+
+// Panel de depuración de modo inmediato: cada cuadro se llama a `begin`, a
+// un `slider` por valor y a `end`. Cada control se dibuja y lee el ratón en
+// el momento, así que no guarda nada de los valores que muestra. Va arriba a
+// la derecha, encima de la escena.
+pub struct DebugUi {
+    pub open: bool,
+    // Ratón en píxeles del framebuffer y si el botón izquierdo está apretado
+    mouse: Option<(f32, f32)>,
+    down: bool,
+    // Control que se está arrastrando, por su orden en el cuadro
+    active: Option<usize>,
+    next_id: usize,
+    pixel_scale: usize,
+    left: usize,
+    top: usize,
+    cursor_y: usize,
+    // Lo que ocupó el panel en el último cuadro: (x, y, ancho, alto)
+    area: (usize, usize, usize, usize),
+}
+
+// Ancho del panel con escala 1
+const WIDTH: usize = 280;
+
+impl DebugUi {
+    pub fn new() -> Self {
+        DebugUi {
+            open: false,
+            mouse: None,
+            down: false,
+            active: None,
+            next_id: 0,
+            pixel_scale: 1,
+            left: 0,
+            top: 0,
+            cursor_y: 0,
+            area: (0, 0, 0, 0),
+        }
+    }
+
+    // Si el ratón es del panel: está encima o arrastrando un control. Así
+    // arrastrar un slider no gira también la cámara.
+    pub fn wants_mouse(&self) -> bool {
+        if !self.open {
+            return false;
+        }
+        let (x, y, width, height) = self.area;
+        let over = self.mouse.is_some_and(|(mx, my)| {
+            mx >= x as f32 && mx < (x + width) as f32 && my >= y as f32 && my < (y + height) as f32
+        });
+        over || self.active.is_some()
+    }
+
+    pub fn begin(&mut self, framebuffer: &Framebuffer, mouse: Option<(f32, f32)>, down: bool, pixel_scale: usize) {
+        self.mouse = mouse;
+        self.down = down;
+        if !down {
+            self.active = None;
+        }
+        self.next_id = 0;
+        self.pixel_scale = pixel_scale;
+        let width = WIDTH * pixel_scale;
+        self.left = framebuffer.width.saturating_sub(width + 10 * pixel_scale);
+        self.top = 10 * pixel_scale;
+        self.cursor_y = self.top;
+    }
+
+    pub fn end(&mut self) {
+        self.area = (self.left, self.top, WIDTH * self.pixel_scale, self.cursor_y - self.top);
+    }
+
+    fn scale(&self) -> usize {
+        self.pixel_scale.max(1)
+    }
+
+    // Oscurece una franja del panel del alto dado y devuelve su borde de arriba
+    fn row(&mut self, framebuffer: &mut Framebuffer, height: usize) -> usize {
+        let y0 = self.cursor_y;
+        let width = WIDTH * self.pixel_scale;
+        for y in y0..(y0 + height).min(framebuffer.height) {
+            for x in self.left..(self.left + width).min(framebuffer.width) {
+                framebuffer.color[y * framebuffer.width + x] *= 0.3;
+            }
+        }
+        self.cursor_y += height;
+        y0
+    }
+
+    // Un texto largo se corta en el borde del panel en vez de salirse
+    fn draw_label(&self, framebuffer: &mut Framebuffer, content: &str, x: usize, y: usize) {
+        let padding = 4 * self.pixel_scale;
+        let previous = framebuffer.scissor();
+        framebuffer.set_scissor(Some(Viewport::new(self.left, 0, WIDTH * self.pixel_scale - padding, framebuffer.height)));
+        framebuffer.set_depth_test(DepthTest::Always);
+        framebuffer.set_write_depth(false);
+        text::draw_text(content, x as isize, y as isize, self.scale(), |x, y| {
+            if x >= 0 && y >= 0 {
+                framebuffer.point(x as usize, y as usize, 0.0);
+            }
+        });
+        framebuffer.set_depth_test(DepthTest::Less);
+        framebuffer.set_write_depth(true);
+        framebuffer.set_scissor(previous);
+    }
+
+    // Título de una sección
+    pub fn heading(&mut self, framebuffer: &mut Framebuffer, uniforms: &Uniforms, content: &str) {
+        let padding = 4 * self.pixel_scale;
+        let y = self.row(framebuffer, text::text_height(self.scale()) + 2 * padding);
+        framebuffer.set_current_color(uniforms.palette(255, 220, 120));
+        self.draw_label(framebuffer, content, self.left + padding, y + padding);
+    }
+
+    // Barra horizontal con el valor dentro de `range`. Se arrastra con el
+    // botón izquierdo. Devuelve si el valor cambió en este cuadro.
+    pub fn slider(&mut self, framebuffer: &mut Framebuffer, uniforms: &Uniforms, label: &str, value: &mut f32, range: RangeInclusive<f32>) -> bool {
+        self.bar(framebuffer, uniforms, label, value, range, false)
+    }
+
+    // Igual, para enteros
+    pub fn slider_int(&mut self, framebuffer: &mut Framebuffer, uniforms: &Uniforms, label: &str, value: &mut i32, range: RangeInclusive<i32>) -> bool {
+        let mut current = *value as f32;
+        let range = *range.start() as f32..=*range.end() as f32;
+        let changed = self.bar(framebuffer, uniforms, label, &mut current, range, true);
+        *value = current as i32;
+        changed
+    }
+
+    fn bar(&mut self, framebuffer: &mut Framebuffer, uniforms: &Uniforms, label: &str, value: &mut f32, range: RangeInclusive<f32>, integer: bool) -> bool {
+        let (min, max) = range.into_inner();
+        let id = self.next_id;
+        self.next_id += 1;
+
+        let ps = self.pixel_scale;
+        let padding = 4 * ps;
+        let text_height = text::text_height(self.scale());
+        let track_height = 4 * ps;
+        let y = self.row(framebuffer, text_height + track_height + 3 * padding);
+        let track_left = self.left + padding;
+        let track_width = WIDTH * ps - 2 * padding;
+        let track_top = y + text_height + 2 * padding;
+
+        // Se engancha al apretar sobre la barra y la suelta al soltar el botón
+        let mut changed = false;
+        if let Some((mx, my)) = self.mouse {
+            let over = mx >= track_left as f32
+                && mx < (track_left + track_width) as f32
+                && my >= y as f32
+                && my < (track_top + track_height + padding) as f32;
+            if self.down && self.active.is_none() && over {
+                self.active = Some(id);
+            }
+            if self.active == Some(id) {
+                let t = ((mx - track_left as f32) / track_width as f32).clamp(0.0, 1.0);
+                let mut new_value = min + (max - min) * t;
+                if integer {
+                    new_value = new_value.round();
+                }
+                changed = new_value != *value;
+                *value = new_value;
+            }
+        }
+
+        framebuffer.set_current_color(uniforms.palette(200, 210, 230));
+        let shown = if integer { format!("{:.0}", value) } else { format_value(*value) };
+        self.draw_label(framebuffer, &format!("{} {}", label, shown), track_left, y + padding);
+
+        let fraction = if max > min { ((*value - min) / (max - min)).clamp(0.0, 1.0) } else { 0.0 };
+        let filled = (track_width as f32 * fraction) as usize;
+        let empty = uniforms.palette(60, 70, 90);
+        let fill = if self.active == Some(id) { uniforms.palette(255, 200, 80) } else { uniforms.palette(110, 170, 255) };
+        for py in track_top..(track_top + track_height).min(framebuffer.height) {
+            for px in track_left..(track_left + track_width).min(framebuffer.width) {
+                framebuffer.color[py * framebuffer.width + px] = if px - track_left < filled { fill } else { empty };
+            }
+        }
+        changed
+    }
+}
+
+// Pocas cifras significativas: las velocidades son muy chicas
+fn format_value(value: f32) -> String {
+    if value == 0.0 || value.abs() >= 100.0 {
+        format!("{:.0}", value)
+    } else if value.abs() >= 1.0 {
+        format!("{:.2}", value)
+    } else {
+        format!("{:.4}", value)
+    }
+}
@@ -0,0 +1,106 @@
+use nalgebra_glm::{Vec2, Vec3};
+use crate::color::Color;
+use crate::fragment::Fragment;
+use crate::framebuffer::Framebuffer;
+use crate::shaders::{fragment_shader, DebugView};
+use crate::Uniforms;
+
+// Píxel sin geometría diferida
+const EMPTY: u16 = u16::MAX;
+
+// Camino diferido: la geometría opaca solo deja en el G-buffer lo que hace
+// falta para sombrear (normal, posición en el modelo, color de los vértices,
+// profundidad y de qué material es) y después una pasada por la pantalla corre el shader una vez
+// por píxel, sin importar cuántas veces se tapó al dibujar.
+pub struct GBuffer {
+    normal: Vec<Vec3>,
+    position: Vec<Vec3>,
+    depth: Vec<f32>,
+    // Solo para las vistas de depuración
+    tex_coords: Vec<Vec2>,
+    barycentric: Vec<Vec3>,
+    // Color de los vértices, que el shader multiplica
+    color: Vec<Color>,
+    // Índice en la lista de materiales del cuadro; EMPTY si no hay nada
+    material: Vec<u16>,
+}
+
+impl GBuffer {
+    pub fn new() -> Self {
+        GBuffer {
+            normal: Vec::new(),
+            position: Vec::new(),
+            depth: Vec::new(),
+            tex_coords: Vec::new(),
+            barycentric: Vec::new(),
+            color: Vec::new(),
+            material: Vec::new(),
+        }
+    }
+
+    // Acompaña al framebuffer cuando cambia de tamaño
+    pub fn fit(&mut self, framebuffer: &Framebuffer) {
+        let size = framebuffer.width * framebuffer.height;
+        if self.material.len() != size {
+            self.normal = vec![Vec3::zeros(); size];
+            self.position = vec![Vec3::zeros(); size];
+            self.depth = vec![f32::INFINITY; size];
+            self.tex_coords = vec![Vec2::zeros(); size];
+            self.barycentric = vec![Vec3::zeros(); size];
+            self.color = vec![Color::white(); size];
+            self.material = vec![EMPTY; size];
+        }
+    }
+
+    pub fn write(&mut self, index: usize, fragment: &Fragment, material: u16) {
+        self.normal[index] = fragment.normal;
+        self.position[index] = fragment.vertex_position;
+        self.depth[index] = fragment.depth;
+        self.tex_coords[index] = fragment.tex_coords;
+        self.barycentric[index] = fragment.barycentric;
+        self.color[index] = fragment.color;
+        self.material[index] = material;
+    }
+
+    // Sombrea el viewport actual con los uniforms de cada material y deja el
+    // G-buffer vacío para la vista siguiente. Si algo que no pasa por el
+    // G-buffer (un planeta dibujado como punto) quedó adelante, la
+    // profundidad ya no coincide y el píxel se respeta.
+    pub fn resolve(&mut self, framebuffer: &mut Framebuffer, materials: &[Uniforms]) {
+        let light_dir = Vec3::new(0.0, 0.0, 1.0);
+        let clip = framebuffer.clip_rect();
+        for y in clip.y..clip.y + clip.height {
+            for x in clip.x..clip.x + clip.width {
+                let index = y * framebuffer.width + x;
+                let material = std::mem::replace(&mut self.material[index], EMPTY);
+                let Some(uniforms) = materials.get(material as usize) else {
+                    continue;
+                };
+                if framebuffer.zbuffer[index] != self.depth[index] {
+                    continue;
+                }
+
+                // El mismo fragmento que habría salido del rasterizador
+                let normal = self.normal[index];
+                let intensity = normal.dot(&light_dir).max(0.0);
+                let fragment = Fragment {
+                    tex_coords: self.tex_coords[index],
+                    barycentric: self.barycentric[index],
+                    ..Fragment::new(
+                        x as f32,
+                        y as f32,
+                        self.color[index],
+                        self.depth[index],
+                        normal,
+                        intensity,
+                        self.position[index],
+                    )
+                };
+                let color = fragment_shader(&fragment, uniforms);
+                framebuffer.color[index] = color;
+                framebuffer.count_write(index);
+                framebuffer.emission[index] = if uniforms.material.emissive && uniforms.debug_view == DebugView::Off { color } else { Color::black() };
+            }
+        }
+    }
+}
@@ -0,0 +1,177 @@
+use std::fs;
+use std::time::{SystemTime, UNIX_EPOCH};
+use nalgebra_glm::Vec3;
+use crate::CelestialBody;
+
+// Segundos Unix de la época J2000 (1 de enero de 2000, 12:00 TT)
+const J2000_UNIX: f64 = 946_728_000.0;
+const SECONDS_PER_DAY: f64 = 86_400.0;
+
+// Días de simulación que pasan en cada cuadro
+pub const DAYS_PER_FRAME: f32 = 1.0;
+
+// Elementos orbitales de un cuerpo en la época J2000, como vienen en la tabla
+#[derive(Clone)]
+pub struct OrbitalElements {
+    pub name: String,
+    pub body_type: CelestialBody,
+    pub semi_major_axis: f32,
+    pub eccentricity: f32,
+    pub period_days: f32,
+    pub radius_km: f32,
+    // Inclinación del eje, en grados
+    pub axial_tilt: f32,
+    // Longitud media y longitud del perihelio, en grados
+    pub mean_longitude: f32,
+    pub perihelion_longitude: f32,
+}
+
+impl OrbitalElements {
+    // Anomalía media (radianes) `days` días después de J2000
+    pub fn mean_anomaly(&self, days: f64) -> f32 {
+        if self.period_days <= 0.0 {
+            return 0.0;
+        }
+        let degrees = self.mean_longitude as f64 - self.perihelion_longitude as f64 + 360.0 * days / self.period_days as f64;
+        degrees.rem_euclid(360.0).to_radians() as f32
+    }
+}
+
+fn body_type(keyword: &str) -> Option<CelestialBody> {
+    Some(match keyword {
+        "sol" => CelestialBody::Sun,
+        "rocoso" => CelestialBody::RockyPlanet,
+        "gaseoso" => CelestialBody::GasGiant,
+        "tierra" => CelestialBody::CloudyPlanet,
+        "anillos" => CelestialBody::RingedPlanet,
+        "hielo" => CelestialBody::IcePlanet,
+        "colores" => CelestialBody::ColorPlanet,
+        "oceano" => CelestialBody::OceanPlanet,
+        "selva" => CelestialBody::NaturePlanet,
+        "aurora" => CelestialBody::AuroraPlanet,
+        "lava" => CelestialBody::LavaPlanet,
+        "desierto" => CelestialBody::DesertPlanet,
+        _ => return None,
+    })
+}
+
+// Lee la tabla de elementos orbitales (ver assets/planets.csv). La primera
+// fila que no es comentario es la cabecera y se salta. Tiene que haber al
+// menos un cuerpo.
+pub fn load(filename: &str) -> Result<Vec<OrbitalElements>, String> {
+    let source = fs::read_to_string(filename).map_err(|err| format!("{}: {}", filename, err))?;
+    let mut bodies = Vec::new();
+    let mut header = true;
+
+    for (number, line) in source.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        if header {
+            header = false;
+            continue;
+        }
+
+        let error = |message: &str| format!("{}:{}: {}", filename, number + 1, message);
+        let fields: Vec<&str> = line.split(',').map(str::trim).collect();
+        if fields.len() != 9 {
+            return Err(error("se esperaban 9 columnas"));
+        }
+        let number_at = |column: usize, what: &str| -> Result<f32, String> {
+            fields[column].parse().map_err(|_| error(&format!("{} inválido", what)))
+        };
+
+        bodies.push(OrbitalElements {
+            name: fields[0].to_string(),
+            body_type: body_type(fields[1]).ok_or_else(|| error("tipo de cuerpo desconocido"))?,
+            semi_major_axis: number_at(2, "semieje")?,
+            eccentricity: number_at(3, "excentricidad")?.clamp(0.0, 0.99),
+            period_days: number_at(4, "período")?,
+            radius_km: number_at(5, "radio")?,
+            axial_tilt: number_at(6, "inclinación")?,
+            mean_longitude: number_at(7, "longitud media")?,
+            perihelion_longitude: number_at(8, "longitud del perihelio")?,
+        });
+    }
+
+    if bodies.is_empty() {
+        return Err(format!("{}: la tabla no tiene ningún cuerpo", filename));
+    }
+    Ok(bodies)
+}
+
+// Días desde J2000 hasta `now`
+pub fn days_since_j2000(now: SystemTime) -> f64 {
+    let seconds = now.duration_since(UNIX_EPOCH).map_or(0.0, |elapsed| elapsed.as_secs_f64());
+    (seconds - J2000_UNIX) / SECONDS_PER_DAY
+}
+
+// Anomalía excéntrica a partir de la media (ecuación de Kepler, por Newton)
+pub fn eccentric_anomaly(mean_anomaly: f32, eccentricity: f32) -> f32 {
+    let mut e = if eccentricity > 0.8 { std::f32::consts::PI } else { mean_anomaly };
+    for _ in 0..8 {
+        let delta = (e - eccentricity * e.sin() - mean_anomaly) / (1.0 - eccentricity * e.cos());
+        e -= delta;
+        if delta.abs() < 1e-6 {
+            break;
+        }
+    }
+    e
+}
+
+// Punto de la elipse en el plano XZ con el sol en el foco. `perihelion` es
+// el ángulo hacia el perihelio; con excentricidad 0 es un círculo.
+pub fn orbit_point(semi_major_axis: f32, eccentricity: f32, perihelion: f32, eccentric_anomaly: f32) -> Vec3 {
+    let x = semi_major_axis * (eccentric_anomaly.cos() - eccentricity);
+    let z = semi_major_axis * (1.0 - eccentricity * eccentricity).sqrt() * eccentric_anomaly.sin();
+    let (sin_p, cos_p) = perihelion.sin_cos();
+    Vec3::new(x * cos_p - z * sin_p, 0.0, x * sin_p + z * cos_p)
+}
+
+pub const KM_PER_AU: f32 = 149_597_870.7;
+// Unidades de escena por UA en la escala real
+const UNITS_PER_AU: f32 = 25.0;
+
+// Cómo se pasan las distancias y los radios reales a la escena
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ScaleMode {
+    // Comprimido para que todo entre en pantalla: raíz de la distancia en UA
+    // y logaritmo del radio en km
+    Stylized,
+    // Logaritmo de las dos cosas: los planetas exteriores se acercan más
+    Logarithmic,
+    // La misma proporción para distancias y radios: casi todo queda en un punto
+    TrueRatio,
+}
+
+impl ScaleMode {
+    pub fn next(self) -> Self {
+        match self {
+            ScaleMode::Stylized => ScaleMode::Logarithmic,
+            ScaleMode::Logarithmic => ScaleMode::TrueRatio,
+            ScaleMode::TrueRatio => ScaleMode::Stylized,
+        }
+    }
+
+    // Radio de la órbita en la escena para un semieje en UA
+    pub fn distance(self, semi_major_axis: f32) -> f32 {
+        if semi_major_axis <= 0.0 {
+            return 0.0;
+        }
+        match self {
+            ScaleMode::Stylized => 3.0 + 10.0 * semi_major_axis.sqrt(),
+            ScaleMode::Logarithmic => 12.0 * (1.0 + semi_major_axis / 0.1).ln(),
+            ScaleMode::TrueRatio => semi_major_axis * UNITS_PER_AU,
+        }
+    }
+
+    // Escala del modelo para un radio en km
+    pub fn scale(self, radius_km: f32) -> f32 {
+        match self {
+            ScaleMode::Stylized => (0.8 + 0.45 * (radius_km / 2000.0).max(1.0).ln()).min(4.0),
+            ScaleMode::Logarithmic => (0.5 + 0.4 * (radius_km / 1000.0).max(1.0).ln()).max(0.3),
+            ScaleMode::TrueRatio => radius_km / KM_PER_AU * UNITS_PER_AU / crate::SURFACE_RADIUS,
+        }
+    }
+}
@@ -2,13 +2,18 @@
 use nalgebra_glm::{Vec2, Vec3};
 use crate::color::Color;
 
+#[derive(Clone, Copy)]
 pub struct Fragment {
     pub position: Vec2,
+    // Color de los vértices interpolado, en sRGB como viene del modelo
     pub color: Color,
     pub depth: f32,
     pub normal: Vec3,
     pub intensity: f32,
     pub vertex_position: Vec3,
+    pub tex_coords: Vec2,
+    // Peso de cada vértice del triángulo en este píxel
+    pub barycentric: Vec3,
 }
 
 impl Fragment {
@@ -19,7 +24,9 @@ impl Fragment {
             depth,
             normal,
             intensity,
-            vertex_position
+            vertex_position,
+            tex_coords: Vec2::zeros(),
+            barycentric: Vec3::zeros(),
         }
     }
 }
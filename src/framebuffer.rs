@@ -1,11 +1,158 @@
+use crate::color::Color;
 
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum DepthTest {
+    Less,
+    LEqual,
+    Equal,
+    Always,
+}
+
+impl DepthTest {
+    pub fn passes(self, depth: f32, stored: f32) -> bool {
+        match self {
+            DepthTest::Less => depth < stored,
+            DepthTest::LEqual => depth <= stored,
+            DepthTest::Equal => depth == stored,
+            DepthTest::Always => true,
+        }
+    }
+}
+
+// Comparación del stencil entre la referencia y lo guardado, los dos con la máscara
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum StencilFunc {
+    Always,
+    Never,
+    Equal,
+    NotEqual,
+}
+
+impl StencilFunc {
+    pub fn passes(self, reference: u8, stored: u8) -> bool {
+        match self {
+            StencilFunc::Always => true,
+            StencilFunc::Never => false,
+            StencilFunc::Equal => reference == stored,
+            StencilFunc::NotEqual => reference != stored,
+        }
+    }
+}
+
+// Qué le pasa al valor guardado en el stencil
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum StencilOp {
+    Keep,
+    Zero,
+    Replace,
+    Increment,
+    Decrement,
+}
+
+impl StencilOp {
+    fn apply(self, stored: u8, reference: u8) -> u8 {
+        match self {
+            StencilOp::Keep => stored,
+            StencilOp::Zero => 0,
+            StencilOp::Replace => reference,
+            StencilOp::Increment => stored.saturating_add(1),
+            StencilOp::Decrement => stored.saturating_sub(1),
+        }
+    }
+}
+
+// Configuración del stencil: la prueba y qué se escribe según falle el
+// stencil, pase el stencil pero no la profundidad, o pasen los dos
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct StencilState {
+    pub func: StencilFunc,
+    pub reference: u8,
+    pub mask: u8,
+    pub fail: StencilOp,
+    pub depth_fail: StencilOp,
+    pub pass: StencilOp,
+}
+
+impl StencilState {
+    // Solo prueba, no escribe nada
+    pub fn test(func: StencilFunc, reference: u8) -> Self {
+        StencilState {
+            func,
+            reference,
+            mask: 0xFF,
+            fail: StencilOp::Keep,
+            depth_fail: StencilOp::Keep,
+            pass: StencilOp::Keep,
+        }
+    }
+
+    // Marca con `reference` todo lo que se llega a dibujar
+    pub fn write(reference: u8) -> Self {
+        StencilState { pass: StencilOp::Replace, ..StencilState::test(StencilFunc::Always, reference) }
+    }
+}
+
+// Cómo se combina un color nuevo con el que ya está en el buffer
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum BlendMode {
+    Replace,
+    Alpha,
+    Additive,
+}
+
+// Rectángulo del framebuffer en el que se dibuja, en píxeles
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Viewport {
+    pub x: usize,
+    pub y: usize,
+    pub width: usize,
+    pub height: usize,
+}
+
+impl Viewport {
+    pub fn new(x: usize, y: usize, width: usize, height: usize) -> Self {
+        Viewport { x, y, width, height }
+    }
+
+    pub fn contains(&self, x: usize, y: usize) -> bool {
+        x >= self.x && y >= self.y && x < self.x + self.width && y < self.y + self.height
+    }
+
+    // Lo que tienen en común; sin solapamiento queda un rectángulo vacío
+    pub fn intersect(&self, other: &Viewport) -> Viewport {
+        let x = self.x.max(other.x);
+        let y = self.y.max(other.y);
+        let right = (self.x + self.width).min(other.x + other.width).max(x);
+        let bottom = (self.y + self.height).min(other.y + other.height).max(y);
+        Viewport::new(x, y, right - x, bottom - y)
+    }
+}
+
+// `color` guarda la imagen en HDR; `buffer` es la salida 0xRRGGBB que se
+// llena al hacer el tonemapping y se entrega a la ventana.
 pub struct Framebuffer {
     pub width: usize,
     pub height: usize,
+    pub color: Vec<Color>,
     pub buffer: Vec<u32>,
     pub zbuffer: Vec<f32>,
-    background_color: u32,
-    current_color: u32,
+    pub emission: Vec<Color>,
+    pub stencil: Vec<u8>,
+    background_color: Color,
+    current_color: Color,
+    depth_test: DepthTest,
+    write_depth: bool,
+    blend_mode: BlendMode,
+    // Sin stencil activo no se prueba ni se escribe
+    stencil_state: Option<StencilState>,
+    // Los puntos fuera de este rectángulo se descartan
+    viewport: Viewport,
+    // Recorte extra dentro del viewport (paneles, textos); no cambia la
+    // proyección, solo lo que llega a escribirse
+    scissor: Option<Viewport>,
+    // Cuántas veces se escribió el color de cada píxel en el cuadro; None
+    // mientras no se cuenta
+    overdraw: Option<Vec<u16>>,
 }
 
 impl Framebuffer {
@@ -13,38 +160,370 @@ impl Framebuffer {
         Framebuffer {
             width,
             height,
+            color: vec![Color::black(); width * height],
             buffer: vec![0; width * height],
             zbuffer: vec![f32::INFINITY; width * height],
-            background_color: 0x000000,
-            current_color: 0xFFFFFF
+            emission: vec![Color::black(); width * height],
+            stencil: vec![0; width * height],
+            background_color: Color::black(),
+            current_color: Color::from_hex(0xFFFFFF),
+            depth_test: DepthTest::Less,
+            write_depth: true,
+            blend_mode: BlendMode::Replace,
+            stencil_state: None,
+            viewport: Viewport::new(0, 0, width, height),
+            scissor: None,
+            overdraw: None,
         }
     }
 
     pub fn clear(&mut self) {
-        for pixel in self.buffer.iter_mut() {
+        for pixel in self.color.iter_mut() {
             *pixel = self.background_color;
         }
         for depth in self.zbuffer.iter_mut() {
             *depth = f32::INFINITY;
         }
+        for glow in self.emission.iter_mut() {
+            *glow = Color::black();
+        }
+        self.stencil.fill(0);
+        if let Some(overdraw) = &mut self.overdraw {
+            overdraw.fill(0);
+        }
+    }
+
+    // Limpia solo el viewport actual (y dentro del recorte, si hay): color de
+    // fondo, profundidad y brillo. Así una vista dentro de otra tiene su
+    // propio pedazo del zbuffer.
+    pub fn clear_viewport(&mut self) {
+        let viewport = self.clip_rect();
+        for y in viewport.y..viewport.y + viewport.height {
+            let row = y * self.width;
+            for index in row + viewport.x..row + viewport.x + viewport.width {
+                self.color[index] = self.background_color;
+                self.zbuffer[index] = f32::INFINITY;
+                self.emission[index] = Color::black();
+                self.stencil[index] = 0;
+            }
+        }
+    }
+
+    pub fn viewport(&self) -> Viewport {
+        self.viewport
+    }
+
+    // Se recorta a los bordes del framebuffer
+    pub fn set_viewport(&mut self, viewport: Viewport) {
+        let x = viewport.x.min(self.width);
+        let y = viewport.y.min(self.height);
+        self.viewport = Viewport::new(x, y, viewport.width.min(self.width - x), viewport.height.min(self.height - y));
+    }
+
+    pub fn reset_viewport(&mut self) {
+        self.viewport = Viewport::new(0, 0, self.width, self.height);
+    }
+
+    pub fn scissor(&self) -> Option<Viewport> {
+        self.scissor
+    }
+
+    pub fn set_scissor(&mut self, scissor: Option<Viewport>) {
+        self.scissor = scissor;
+    }
+
+    // Donde se puede escribir: el viewport recortado por el scissor
+    pub fn clip_rect(&self) -> Viewport {
+        match &self.scissor {
+            Some(scissor) => self.viewport.intersect(scissor),
+            None => self.viewport,
+        }
+    }
+
+    fn accepts(&self, x: usize, y: usize) -> bool {
+        self.viewport.contains(x, y) && self.scissor.is_none_or(|scissor| scissor.contains(x, y))
+    }
+
+    pub fn set_overdraw_counting(&mut self, enabled: bool) {
+        if enabled != self.overdraw.is_some() {
+            self.overdraw = enabled.then(|| vec![0; self.width * self.height]);
+        }
+    }
+
+    // Suma una escritura al píxel `index`; los puntos lo hacen solos, esto es
+    // para lo que escribe el color directamente
+    pub fn count_write(&mut self, index: usize) {
+        if let Some(overdraw) = &mut self.overdraw {
+            overdraw[index] = overdraw[index].saturating_add(1);
+        }
+    }
+
+    // Reemplaza el color por el mapa de calor de las escrituras y apaga el
+    // brillo. `to_color` convierte los colores de la escala al flujo actual.
+    // Devuelve el promedio y el máximo de escrituras por píxel.
+    pub fn show_overdraw(&mut self, to_color: impl Fn(Color) -> Color) -> Option<(f32, u16)> {
+        let overdraw = self.overdraw.as_ref()?;
+        let mut total = 0u64;
+        let mut max = 0;
+        for (index, &count) in overdraw.iter().enumerate() {
+            total += count as u64;
+            max = max.max(count);
+            self.color[index] = to_color(heat(count));
+            self.emission[index] = Color::black();
+        }
+        Some((total as f32 / overdraw.len().max(1) as f32, max))
+    }
+
+    pub fn set_stencil(&mut self, stencil_state: Option<StencilState>) {
+        self.stencil_state = stencil_state;
+    }
+
+    // Prueba del stencil y de profundidad para el píxel `index`, con lo que
+    // el stencil tenga que escribir en cada caso
+    fn passes(&mut self, index: usize, depth: f32) -> bool {
+        let depth_ok = self.depth_test.passes(depth, self.zbuffer[index]);
+        let Some(state) = self.stencil_state else {
+            return depth_ok;
+        };
+        let stored = self.stencil[index];
+        let (op, passed) = if !state.func.passes(state.reference & state.mask, stored & state.mask) {
+            (state.fail, false)
+        } else if depth_ok {
+            (state.pass, true)
+        } else {
+            (state.depth_fail, false)
+        };
+        self.stencil[index] = op.apply(stored, state.reference);
+        passed
     }
 
     pub fn point(&mut self, x: usize, y: usize, depth: f32) {
-        if x < self.width && y < self.height {
+        if self.accepts(x, y) {
+            let index = y * self.width + x;
+
+            if self.passes(index, depth) {
+                self.color[index] = self.current_color;
+                self.count_write(index);
+                if self.write_depth {
+                    self.zbuffer[index] = depth;
+                }
+                // Lo que tapa a un objeto emisivo también tapa su brillo
+                self.emission[index] = Color::black();
+            }
+        }
+    }
+
+    // Igual que point, pero además marca el píxel como fuente de luz
+    pub fn point_emissive(&mut self, x: usize, y: usize, depth: f32) {
+        if self.accepts(x, y) {
             let index = y * self.width + x;
 
-            if self.zbuffer[index] > depth {
-                self.buffer[index] = self.current_color;
+            if self.passes(index, depth) {
+                self.color[index] = self.current_color;
+                self.count_write(index);
+                if self.write_depth {
+                    self.zbuffer[index] = depth;
+                }
+                self.emission[index] = self.current_color;
+            }
+        }
+    }
+
+    // Solo profundidad, sin tocar el color (pre-pasada de profundidad y
+    // G-buffer). Devuelve si el punto pasó.
+    pub fn depth_point(&mut self, x: usize, y: usize, depth: f32) -> bool {
+        if self.accepts(x, y) {
+            let index = y * self.width + x;
+
+            if self.passes(index, depth) {
                 self.zbuffer[index] = depth;
+                return true;
             }
         }
+        false
     }
 
-    pub fn set_background_color(&mut self, color: u32) {
+    // Suma el color sobre lo que ya hay sin escribir profundidad (halos, brillos).
+    // Sólo respeta la función de comparación, nunca escribe en el zbuffer.
+    pub fn point_additive(&mut self, x: usize, y: usize, depth: f32, color: Color) {
+        if self.accepts(x, y) {
+            let index = y * self.width + x;
+
+            if self.passes(index, depth) {
+                self.color[index] = blend(self.color[index], color, 1.0, BlendMode::Additive);
+                self.count_write(index);
+            }
+        }
+    }
+
+    // Punto RGBA combinado según el modo de mezcla actual. Las capas
+    // translúcidas no escriben profundidad aunque write_depth esté activo.
+    pub fn point_rgba(&mut self, x: usize, y: usize, depth: f32, color: Color, alpha: f32) {
+        if self.accepts(x, y) {
+            let index = y * self.width + x;
+
+            if self.passes(index, depth) {
+                self.color[index] = blend(self.color[index], color, alpha, self.blend_mode);
+                self.count_write(index);
+                if self.write_depth && self.blend_mode == BlendMode::Replace {
+                    self.zbuffer[index] = depth;
+                    self.emission[index] = Color::black();
+                }
+            }
+        }
+    }
+
+    // Si un fragmento en (x, y) pasaría la prueba de profundidad (y la del
+    // stencil). Sirve para descartar lo tapado antes de correr el shader.
+    // Si el stencil escribe al fallar, se deja pasar para que `point` lo haga.
+    pub fn depth_passes(&self, x: usize, y: usize, depth: f32) -> bool {
+        if !self.accepts(x, y) {
+            return false;
+        }
+        let index = y * self.width + x;
+        match self.stencil_state {
+            Some(state) if state.fail != StencilOp::Keep || state.depth_fail != StencilOp::Keep => true,
+            Some(state) if !state.func.passes(state.reference & state.mask, self.stencil[index] & state.mask) => false,
+            _ => self.depth_test.passes(depth, self.zbuffer[index]),
+        }
+    }
+
+    pub fn depth_at(&self, x: usize, y: usize) -> f32 {
+        if x < self.width && y < self.height {
+            self.zbuffer[y * self.width + x]
+        } else {
+            f32::INFINITY
+        }
+    }
+
+    pub fn set_depth_test(&mut self, depth_test: DepthTest) {
+        self.depth_test = depth_test;
+    }
+
+    pub fn set_write_depth(&mut self, write_depth: bool) {
+        self.write_depth = write_depth;
+    }
+
+    pub fn set_blend_mode(&mut self, blend_mode: BlendMode) {
+        self.blend_mode = blend_mode;
+    }
+
+    // Después del post-proceso, vuelve a poner en `color` la imagen final de
+    // `buffer` para dibujar la interfaz encima sin que la toquen el
+    // tonemapping ni los efectos. Con `linear` se decodifica de sRGB.
+    pub fn begin_overlay(&mut self, linear: bool) {
+        let levels = overlay_levels(linear);
+        for (color, &pixel) in self.color.iter_mut().zip(&self.buffer) {
+            *color = Color::from_rgb_f32(levels[(pixel >> 16) as usize & 0xFF], levels[(pixel >> 8) as usize & 0xFF], levels[pixel as usize & 0xFF]);
+        }
+    }
+
+    // Pasa a `buffer` lo que dibujó la interfaz desde `begin_overlay`. Solo
+    // se codifican los píxeles que cambiaron.
+    pub fn end_overlay(&mut self, linear: bool) {
+        let levels = overlay_levels(linear);
+        for (color, pixel) in self.color.iter().zip(self.buffer.iter_mut()) {
+            let unchanged = color.r() == levels[(*pixel >> 16) as usize & 0xFF]
+                && color.g() == levels[(*pixel >> 8) as usize & 0xFF]
+                && color.b() == levels[*pixel as usize & 0xFF];
+            if !unchanged {
+                *pixel = if linear { color.to_srgb().to_hex() } else { color.to_hex() };
+            }
+        }
+    }
+
+    // Reduce la imagen promediando bloques de factor x factor píxeles (SSAA)
+    pub fn downsample_into(&self, factor: usize, output: &mut [u32]) {
+        let out_width = self.width / factor;
+        let out_height = self.height / factor;
+        let samples = (factor * factor) as u32;
+
+        for y in 0..out_height {
+            for x in 0..out_width {
+                let (mut r, mut g, mut b) = (0u32, 0u32, 0u32);
+                for sy in 0..factor {
+                    let row = (y * factor + sy) * self.width + x * factor;
+                    for pixel in &self.buffer[row..row + factor] {
+                        r += (pixel >> 16) & 0xFF;
+                        g += (pixel >> 8) & 0xFF;
+                        b += pixel & 0xFF;
+                    }
+                }
+                output[y * out_width + x] = ((r / samples) << 16) | ((g / samples) << 8) | (b / samples);
+            }
+        }
+    }
+
+    // Lleva la imagen a otra medida (la resolución dinámica renderiza más
+    // chico y se estira a la ventana). Bilineal mezcla los cuatro píxeles
+    // más cercanos; si no, se toma el más cercano.
+    pub fn resample_into(&self, output: &mut [u32], out_width: usize, out_height: usize, bilinear: bool) {
+        let scale_x = self.width as f32 / out_width as f32;
+        let scale_y = self.height as f32 / out_height as f32;
+        let channel = |pixel: u32, shift: u32| ((pixel >> shift) & 0xFF) as f32;
+
+        for y in 0..out_height {
+            // Centro del píxel de salida en la imagen de origen
+            let source_y = ((y as f32 + 0.5) * scale_y - 0.5).max(0.0);
+            for x in 0..out_width {
+                let source_x = ((x as f32 + 0.5) * scale_x - 0.5).max(0.0);
+                if !bilinear {
+                    let sx = ((source_x + 0.5) as usize).min(self.width - 1);
+                    let sy = ((source_y + 0.5) as usize).min(self.height - 1);
+                    output[y * out_width + x] = self.buffer[sy * self.width + sx];
+                    continue;
+                }
+
+                let (x0, y0) = ((source_x as usize).min(self.width - 1), (source_y as usize).min(self.height - 1));
+                let (x1, y1) = ((x0 + 1).min(self.width - 1), (y0 + 1).min(self.height - 1));
+                let (tx, ty) = (source_x - x0 as f32, source_y - y0 as f32);
+                let corners = [
+                    (self.buffer[y0 * self.width + x0], (1.0 - tx) * (1.0 - ty)),
+                    (self.buffer[y0 * self.width + x1], tx * (1.0 - ty)),
+                    (self.buffer[y1 * self.width + x0], (1.0 - tx) * ty),
+                    (self.buffer[y1 * self.width + x1], tx * ty),
+                ];
+                let mix = |shift: u32| {
+                    let value: f32 = corners.iter().map(|&(pixel, weight)| channel(pixel, shift) * weight).sum();
+                    (value.round() as u32).min(255)
+                };
+                output[y * out_width + x] = (mix(16) << 16) | (mix(8) << 8) | mix(0);
+            }
+        }
+    }
+
+    pub fn set_background_color(&mut self, color: Color) {
         self.background_color = color;
     }
 
-    pub fn set_current_color(&mut self, color: u32) {
+    pub fn set_current_color(&mut self, color: Color) {
         self.current_color = color;
     }
 }
+
+// Escala del mapa de sobredibujo: negro sin escrituras, azul con una, y de
+// ahí hacia el rojo; de ocho para arriba blanco
+fn heat(count: u16) -> Color {
+    match count {
+        0 => Color::black(),
+        8.. => Color::from_rgb_f32(1.0, 1.0, 1.0),
+        _ => Color::from_hsv(240.0 - (count - 1) as f32 * 40.0, 1.0, 1.0),
+    }
+}
+
+fn blend(dst: Color, src: Color, alpha: f32, mode: BlendMode) -> Color {
+    let alpha = alpha.clamp(0.0, 1.0);
+    match mode {
+        BlendMode::Replace => src,
+        BlendMode::Alpha => dst.lerp(&src, alpha),
+        BlendMode::Additive => dst + src * alpha,
+    }
+}
+
+// Valor en el espacio de trabajo de cada nivel de 8 bits de la salida
+fn overlay_levels(linear: bool) -> [f32; 256] {
+    std::array::from_fn(|level| {
+        let value = level as f32 / 255.0;
+        if linear { Color::from_rgb_f32(value, 0.0, 0.0).to_linear().r() } else { value }
+    })
+}
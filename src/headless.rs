@@ -0,0 +1,121 @@
+use nalgebra_glm::{Mat4, Vec3};
+use fastnoise_lite::FastNoiseLite;
+use crate::color::Color;
+use crate::framebuffer::{Framebuffer, Viewport};
+use crate::lighting::Light;
+use crate::material::Material;
+use crate::postprocess::{PostChain, PostContext};
+use crate::shaders::{DebugView, DepthMode};
+use crate::vertex::Vertex;
+use crate::{create_model_matrix, create_perspective_matrix, create_view_matrix, create_viewport_matrix, render_depth, render_opaque, CelestialBody, RenderContext, Uniforms};
+
+// Cuerpos que rodean al sol en el cuadro fijo, uno por shader
+pub const BODIES: [CelestialBody; 12] = [
+    CelestialBody::RockyPlanet,
+    CelestialBody::GasGiant,
+    CelestialBody::CloudyPlanet,
+    CelestialBody::RingedPlanet,
+    CelestialBody::IcePlanet,
+    CelestialBody::ColorPlanet,
+    CelestialBody::Moon,
+    CelestialBody::OceanPlanet,
+    CelestialBody::NaturePlanet,
+    CelestialBody::AuroraPlanet,
+    CelestialBody::LavaPlanet,
+    CelestialBody::DesertPlanet,
+];
+
+const EYE: Vec3 = Vec3::new(0.0, 0.0, 10.5);
+const RING_RADIUS: f32 = 4.5;
+
+// Uniforms de la cámara fija del cuadro sin ventana, con el sol en el
+// origen como única luz. `model_matrix` queda en la identidad.
+pub fn camera_uniforms<'a>(width: usize, height: usize, time: u32, noise: &'a FastNoiseLite, material: &'a Material, lights: &'a [Light]) -> Uniforms<'a> {
+    Uniforms {
+        model_matrix: Mat4::identity(),
+        view_matrix: create_view_matrix(EYE, Vec3::zeros(), Vec3::new(0.0, 1.0, 0.0)),
+        projection_matrix: create_perspective_matrix(width as f32, height as f32),
+        viewport_matrix: create_viewport_matrix(&Viewport::new(0, 0, width, height)),
+        time,
+        noise,
+        material,
+        light_position: Vec3::zeros(),
+        lights,
+        pbr_materials: true,
+        camera_position: EYE,
+        depth_mode: DepthMode::Logarithmic,
+        linear_workflow: true,
+        debug_view: DebugView::Off,
+    }
+}
+
+// Qué cuadro sale: tamaño, instante y qué camino del pipeline se usa
+#[derive(Clone, Copy, Debug)]
+pub struct FrameOptions {
+    pub width: usize,
+    pub height: usize,
+    pub time: u32,
+    pub depth_prepass: bool,
+    pub deferred: bool,
+    pub debug_view: DebugView,
+}
+
+impl FrameOptions {
+    pub fn new(width: usize, height: usize, time: u32) -> Self {
+        FrameOptions { width, height, time, depth_prepass: false, deferred: false, debug_view: DebugView::Off }
+    }
+}
+
+// Dibuja sin ventana un cuadro que siempre sale igual para las mismas
+// opciones: el sol en el centro y un planeta de cada tipo a su alrededor,
+// con la cadena de post-proceso por defecto. La imagen final queda en `buffer`.
+pub fn render_frame(sphere: &[Vertex], options: &FrameOptions) -> Framebuffer {
+    let (width, height, time) = (options.width, options.height, options.time);
+    let mut framebuffer = Framebuffer::new(width, height);
+    let mut context = RenderContext::new(options.depth_prepass, options.deferred);
+    let lights = [Light::point(Vec3::zeros(), Color::from_hex(0xFFFFFF), 1.0, 0.0)];
+
+    let placements: Vec<(Material, Vec3, f32)> = std::iter::once((Material::for_body(CelestialBody::Sun), Vec3::zeros(), 2.5))
+        .chain(BODIES.iter().enumerate().map(|(index, &body)| {
+            let angle = index as f32 / BODIES.len() as f32 * std::f32::consts::TAU;
+            (Material::for_body(body), Vec3::new(angle.cos(), angle.sin(), 0.0) * RING_RADIUS, 1.2)
+        }))
+        .collect();
+    let noises: Vec<FastNoiseLite> = placements.iter().map(|(material, _, _)| material.noise.build()).collect();
+    let bodies: Vec<Uniforms> = placements.iter().zip(&noises)
+        .map(|((material, position, scale), noise)| Uniforms {
+            model_matrix: create_model_matrix(*position, *scale, Vec3::new(0.3, time as f32 * 0.01, 0.0)),
+            debug_view: options.debug_view,
+            ..camera_uniforms(width, height, time, noise, material, &lights)
+        })
+        .collect();
+
+    framebuffer.clear();
+    if options.depth_prepass {
+        for uniforms in &bodies {
+            render_depth(&mut framebuffer, &mut context, uniforms, sphere);
+        }
+    }
+    let mut materials = Vec::new();
+    if options.deferred {
+        context.gbuffer.fit(&framebuffer);
+    }
+    for uniforms in &bodies {
+        render_opaque(&mut framebuffer, &mut context, &mut materials, uniforms, sphere);
+    }
+    if options.deferred {
+        context.gbuffer.resolve(&mut framebuffer, &materials);
+    }
+
+    let mut post_chain = PostChain::default();
+    post_chain.run(&mut framebuffer, &PostContext { scale: 1, time, encode_srgb: true });
+    framebuffer
+}
+
+// La imagen final del framebuffer en 8 bits, para guardarla o compararla
+pub fn to_image(framebuffer: &Framebuffer) -> image::RgbImage {
+    image::RgbImage::from_fn(framebuffer.width as u32, framebuffer.height as u32, |x, y| {
+        let pixel = framebuffer.buffer[y as usize * framebuffer.width + x as usize];
+        image::Rgb([(pixel >> 16) as u8, (pixel >> 8) as u8, pixel as u8])
+    })
+}
@@ -0,0 +1,151 @@
+use minifb::{Key, KeyRepeat, Window};
+use crate::camera::CameraMode;
+
+// Lo que hace cada tecla que se aprieta una vez
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Action {
+    Help,
+    Menu,
+    DebugUi,
+    DebugView,
+    Overdraw,
+    Profiler,
+    Fullscreen,
+    NextCameraMode,
+    ToggleFlight,
+    Tour,
+    FollowPlanet,
+    SplitScreen,
+    PathKey,
+    PathPlay,
+    PathShorter,
+    PathLonger,
+    Minimap,
+    Inset,
+    Constellations,
+    SatelliteTrails,
+    Lagrange,
+    NextSky,
+    DepthMode,
+    ScaleMode,
+    Supersampling,
+    Tonemapping,
+    LinearWorkflow,
+    Bloom,
+    CockpitEffects,
+    Dither,
+    Fxaa,
+    Grain,
+    Bounce,
+    Warp,
+}
+
+// En qué modos de cámara vale una tecla
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Context {
+    Always,
+    Orbit,
+    Flight,
+    FreeFly,
+}
+
+impl Context {
+    pub fn applies(self, mode: CameraMode) -> bool {
+        match self {
+            Context::Always => true,
+            Context::Orbit => mode == CameraMode::Orbit,
+            Context::Flight => matches!(mode, CameraMode::Chase | CameraMode::Cockpit),
+            Context::FreeFly => mode == CameraMode::FreeFly,
+        }
+    }
+}
+
+// Una fila de la tabla. Las que no tienen acción son teclas que se mantienen
+// o combinaciones que se leen aparte; están para que salgan en la ayuda.
+pub struct Binding {
+    pub action: Option<Action>,
+    pub keys: &'static [Key],
+    pub label: &'static str,
+    pub description: &'static str,
+    pub context: Context,
+}
+
+const fn bind(action: Action, keys: &'static [Key], label: &'static str, description: &'static str, context: Context) -> Binding {
+    Binding { action: Some(action), keys, label, description, context }
+}
+
+const fn info(label: &'static str, description: &'static str, context: Context) -> Binding {
+    Binding { action: None, keys: &[], label, description, context }
+}
+
+pub const BINDINGS: &[Binding] = &[
+    bind(Action::Help, &[Key::H], "H", "Mostrar u ocultar esta ayuda", Context::Always),
+    bind(Action::Menu, &[Key::Escape], "Esc", "Menú de opciones", Context::Always),
+
+    info("W A S D", "Mover la cámara", Context::Orbit),
+    info("Q / E", "Subir / bajar la cámara", Context::Orbit),
+    info("Flechas", "Orbitar alrededor del objetivo", Context::Orbit),
+    info("Z / X", "Acercar / alejar", Context::Orbit),
+    info("Shift", "Mover más rápido", Context::Orbit),
+
+    info("W / S", "Empuje adelante / atrás", Context::Flight),
+    info("A / D", "Guiñada", Context::Flight),
+    info("Arriba / Abajo", "Cabeceo", Context::Flight),
+    info("Q / E", "Alabeo", Context::Flight),
+    info("Shift", "Doble empuje", Context::Flight),
+    bind(Action::Bounce, &[Key::B], "B", "Rebote o deslizamiento al chocar", Context::Flight),
+    bind(Action::Warp, &[Key::Y], "Y", "Salto warp al planeta seleccionado", Context::Flight),
+
+    info("W A S D", "Avanzar y desplazarse", Context::FreeFly),
+    info("Espacio / Ctrl", "Subir / bajar", Context::FreeFly),
+    info("Flechas", "Girar la vista", Context::FreeFly),
+    info("Q / E", "Alabeo", Context::FreeFly),
+    info("1 / 2 / 3", "Velocidad lenta, normal, rápida", Context::FreeFly),
+
+    bind(Action::NextCameraMode, &[Key::C], "C", "Cambiar modo de cámara", Context::Always),
+    bind(Action::ToggleFlight, &[Key::F], "F", "Entrar o salir del vuelo", Context::Always),
+    info("1-9", "Seleccionar un planeta", Context::Always),
+    bind(Action::FollowPlanet, &[Key::G], "G", "Seguir al planeta seleccionado", Context::Always),
+    bind(Action::SplitScreen, &[Key::V], "V", "Pantalla dividida", Context::Always),
+    bind(Action::Tour, &[Key::T], "T", "Recorrido automático", Context::Always),
+    bind(Action::PathKey, &[Key::P], "P / Shift+P", "Agregar clave al camino / borrarlo", Context::Always),
+    bind(Action::PathPlay, &[Key::O], "O", "Reproducir el camino", Context::Always),
+    bind(Action::PathShorter, &[Key::LeftBracket], "Corchete izq.", "Acortar el camino", Context::Always),
+    bind(Action::PathLonger, &[Key::RightBracket], "Corchete der.", "Alargar el camino", Context::Always),
+    info("Ctrl / Alt + 1..5", "Guardar / ir a un marcador", Context::Always),
+
+    bind(Action::Minimap, &[Key::M], "M", "Minimapa", Context::Always),
+    bind(Action::Inset, &[Key::I], "I", "Primer plano del planeta", Context::Always),
+    bind(Action::Constellations, &[Key::N], "N", "Constelaciones", Context::Always),
+    bind(Action::SatelliteTrails, &[Key::J], "J", "Trazas de los satélites", Context::Always),
+    bind(Action::Lagrange, &[Key::U], "U", "Puntos de Lagrange", Context::Always),
+    bind(Action::NextSky, &[Key::K], "K", "Cambiar el fondo del cielo", Context::Always),
+    bind(Action::DepthMode, &[Key::L], "L", "Profundidad logarítmica o estándar", Context::Always),
+    bind(Action::ScaleMode, &[Key::R], "R", "Escala del sistema real", Context::Always),
+    bind(Action::Supersampling, &[Key::F2], "F2", "Supersampling 2x", Context::Always),
+    bind(Action::Tonemapping, &[Key::F3], "F3", "Tonemapping", Context::Always),
+    bind(Action::LinearWorkflow, &[Key::F4], "F4", "Sombreado lineal (sRGB)", Context::Always),
+    bind(Action::Bloom, &[Key::F5], "F5", "Bloom", Context::Always),
+    bind(Action::CockpitEffects, &[Key::F6], "F6", "Efectos de cabina", Context::Always),
+    bind(Action::Dither, &[Key::F7], "F7", "Dithering", Context::Always),
+    bind(Action::Fxaa, &[Key::F8], "F8", "FXAA", Context::Always),
+    bind(Action::Grain, &[Key::F9], "F9", "Grano de película", Context::Always),
+    bind(Action::DebugUi, &[Key::F1], "F1", "Panel de depuración", Context::Always),
+    bind(Action::DebugView, &[Key::F10], "F10", "Normales, profundidad, UV o bordes", Context::Always),
+    bind(Action::Overdraw, &[Key::F12], "F12", "Mapa de sobredibujo", Context::Always),
+    bind(Action::Profiler, &[Key::Tab], "Tab", "Tiempos por etapa del cuadro", Context::Always),
+    bind(Action::Fullscreen, &[Key::F11], "F11", "Pantalla completa", Context::Always),
+];
+
+// Si se apretó en este cuadro alguna tecla de la acción (sin repetición)
+pub fn pressed(window: &Window, action: Action) -> bool {
+    BINDINGS.iter()
+        .filter(|binding| binding.action == Some(action))
+        .flat_map(|binding| binding.keys)
+        .any(|key| window.is_key_pressed(*key, KeyRepeat::No))
+}
+
+// Las filas de la tabla que valen en el modo de cámara actual
+pub fn active(mode: CameraMode) -> impl Iterator<Item = &'static Binding> {
+    BINDINGS.iter().filter(move |binding| binding.context.applies(mode))
+}
@@ -0,0 +1,75 @@
+use nalgebra_glm::{Vec3, Vec4};
+use crate::framebuffer::Framebuffer;
+use crate::color::Color;
+use crate::shaders::encode_depth;
+use crate::text;
+use crate::Uniforms;
+
+const LABELS: [&str; 5] = ["L1", "L2", "L3", "L4", "L5"];
+
+// Los cinco puntos de Lagrange de un par sol-planeta en este cuadro
+pub struct LagrangePoints {
+    pub points: [Vec3; 5],
+    pub color: Color,
+}
+
+impl LagrangePoints {
+    // `mass_ratio` es la masa del planeta sobre la del sol. L1 y L2 quedan a
+    // la distancia de la esfera de Hill, L3 del otro lado del sol y L4 y L5
+    // 60° adelante y atrás en la órbita (aproximaciones de masa pequeña).
+    pub fn new(sun: Vec3, planet: Vec3, mass_ratio: f32, color: Color) -> Self {
+        let offset = planet - sun;
+        let distance = offset.magnitude();
+        if distance < 1e-4 {
+            return LagrangePoints { points: [planet; 5], color };
+        }
+        let axis = offset / distance;
+        let hill = distance * (mass_ratio / 3.0).cbrt();
+        let up = Vec3::new(0.0, 1.0, 0.0);
+
+        let points = [
+            planet - axis * hill,
+            planet + axis * hill,
+            sun - axis * distance * (1.0 + 5.0 * mass_ratio / 12.0),
+            sun + nalgebra_glm::rotate_vec3(&offset, std::f32::consts::FRAC_PI_3, &up),
+            sun + nalgebra_glm::rotate_vec3(&offset, -std::f32::consts::FRAC_PI_3, &up),
+        ];
+        LagrangePoints { points, color }
+    }
+
+    // Un rombo con su etiqueta por punto, tapado por los cuerpos de delante
+    pub fn render(&self, framebuffer: &mut Framebuffer, uniforms: &Uniforms) {
+        let scale = (uniforms.viewport_height() as usize / 450).max(1);
+        let size = 3 * scale as isize;
+        framebuffer.set_current_color(self.color);
+
+        for (point, label) in self.points.iter().zip(LABELS) {
+            let clip = uniforms.projection_matrix * uniforms.view_matrix * Vec4::new(point.x, point.y, point.z, 1.0);
+            if clip.w <= 0.0 {
+                continue;
+            }
+            let ndc = clip / clip.w;
+            if ndc.x.abs() > 1.0 || ndc.y.abs() > 1.0 {
+                continue;
+            }
+            let (x, y) = uniforms.ndc_to_screen(ndc.x, ndc.y);
+            let (cx, cy) = (x as isize, y as isize);
+            let depth = encode_depth(&clip, uniforms.depth_mode);
+            let mut plot = |x: isize, y: isize| {
+                if x >= 0 && y >= 0 {
+                    framebuffer.point(x as usize, y as usize, depth);
+                }
+            };
+
+            // Contorno de rombo
+            for i in 0..=size {
+                let j = size - i;
+                plot(cx + i, cy + j);
+                plot(cx - i, cy + j);
+                plot(cx + i, cy - j);
+                plot(cx - i, cy - j);
+            }
+            text::draw_text(label, cx + size + 2 * scale as isize, cy - size, scale, plot);
+        }
+    }
+}
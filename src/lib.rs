@@ -0,0 +1,411 @@
+// Los tipos se arman con `new()`; no hace falta además un Default
+#![allow(clippy::new_without_default)]
+
+// El pipeline y los módulos del simulador, aparte de la ventana: el binario
+// arma la escena y lee la entrada, y los benchmarks usan lo mismo sin abrir
+// una ventana.
+
+use nalgebra_glm::{Vec3, Mat4, look_at, perspective};
+use std::f32::consts::PI;
+use fastnoise_lite::FastNoiseLite;
+
+pub mod framebuffer;
+pub mod triangle;
+pub mod vertex;
+pub mod obj;
+pub mod color;
+pub mod fragment;
+pub mod shaders;
+pub mod camera;
+pub mod postprocess;
+pub mod corona;
+pub mod noise_utils;
+pub mod render_queue;
+pub mod text;
+pub mod constellation;
+pub mod sky;
+pub mod meteors;
+pub mod particles;
+pub mod tween;
+pub mod tour;
+pub mod bookmarks;
+pub mod camera_path;
+pub mod minimap;
+pub mod trails;
+pub mod spatial;
+pub mod missions;
+pub mod satellites;
+pub mod lagrange;
+pub mod ephemeris;
+pub mod units;
+pub mod panel;
+pub mod picking;
+pub mod keybindings;
+pub mod settings;
+pub mod debug_ui;
+pub mod pacing;
+pub mod redraw;
+pub mod deferred;
+pub mod lighting;
+pub mod material;
+pub mod profiler;
+pub mod seed;
+pub mod assets;
+pub mod lod;
+pub mod headless;
+
+use framebuffer::{Framebuffer, DepthTest, Viewport};
+use vertex::Vertex;
+use triangle::triangle;
+use shaders::{vertex_shader, fragment_shader, DepthMode, DebugView};
+use fragment::Fragment;
+use color::Color;
+use render_queue::BlendShader;
+use material::Material;
+
+pub const NEAR_PLANE: f32 = 0.1;
+pub const FAR_PLANE: f32 = 1000.0;
+// Radio de la esfera del modelo con escala 1, contando la capa de atmósfera
+pub const BODY_RADIUS: f32 = 0.57;
+// Radio del suelo del modelo con escala 1, donde se posa la nave
+pub const SURFACE_RADIUS: f32 = 0.53;
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum CelestialBody {
+    Sun,
+    RockyPlanet,
+    GasGiant,
+    CloudyPlanet,
+    RingedPlanet,
+    IcePlanet,
+    ColorPlanet,
+    Moon,
+    OceanPlanet,    
+    NaturePlanet,   
+    AuroraPlanet, 
+    LavaPlanet,
+    DesertPlanet,
+}
+
+impl CelestialBody {
+    // Qué es, para el panel de información
+    pub fn description(self) -> &'static str {
+        match self {
+            CelestialBody::Sun => "Estrella",
+            CelestialBody::RockyPlanet => "Planeta rocoso",
+            CelestialBody::GasGiant => "Gigante gaseoso",
+            CelestialBody::CloudyPlanet => "Planeta con nubes",
+            CelestialBody::RingedPlanet => "Planeta con anillos",
+            CelestialBody::IcePlanet => "Planeta helado",
+            CelestialBody::ColorPlanet => "Planeta de colores",
+            CelestialBody::Moon => "Luna",
+            CelestialBody::OceanPlanet => "Planeta oceánico",
+            CelestialBody::NaturePlanet => "Planeta selvático",
+            CelestialBody::AuroraPlanet => "Planeta con auroras",
+            CelestialBody::LavaPlanet => "Planeta de lava",
+            CelestialBody::DesertPlanet => "Planeta desértico",
+        }
+    }
+
+    // Nombre por defecto de un cuerpo de este tipo, para las misiones
+    pub fn default_name(self) -> &'static str {
+        match self {
+            CelestialBody::Sun => "Sol",
+            CelestialBody::RockyPlanet => "Roca",
+            CelestialBody::GasGiant => "Gigante",
+            CelestialBody::CloudyPlanet => "Tierra",
+            CelestialBody::RingedPlanet => "Anillos",
+            CelestialBody::IcePlanet => "Hielo",
+            CelestialBody::ColorPlanet => "Colores",
+            CelestialBody::Moon => "Luna",
+            CelestialBody::OceanPlanet => "Oceano",
+            CelestialBody::NaturePlanet => "Selva",
+            CelestialBody::AuroraPlanet => "Aurora",
+            CelestialBody::LavaPlanet => "Lava",
+            CelestialBody::DesertPlanet => "Desierto",
+        }
+    }
+
+}
+
+#[derive(Clone, Copy)]
+pub struct Uniforms<'a> {
+    pub model_matrix: Mat4,
+    pub view_matrix: Mat4,
+    pub projection_matrix: Mat4,
+    pub viewport_matrix: Mat4,
+    pub time: u32,
+    pub noise: &'a FastNoiseLite,
+    // Shader, tinte y ruido del objeto que se está dibujando
+    pub material: &'a Material,
+    // Posición del sol, para la atmósfera y el terminador
+    pub light_position: Vec3,
+    // Todas las luces que se suman en cada fragmento, el sol primero
+    pub lights: &'a [lighting::Light],
+    // La nave y la estación con la BRDF metálico/rugosidad
+    pub pbr_materials: bool,
+    pub camera_position: Vec3,
+    pub depth_mode: DepthMode,
+    pub linear_workflow: bool,
+    // Con algo distinto de Off los cuerpos muestran normales, profundidad, UV o bordes
+    pub debug_view: DebugView,
+}
+
+impl Uniforms<'_> {
+    // Píxel de un punto en NDC dentro del viewport de `viewport_matrix`
+    pub fn ndc_to_screen(&self, ndc_x: f32, ndc_y: f32) -> (f32, f32) {
+        let m = &self.viewport_matrix;
+        (m[(0, 0)] * ndc_x + m[(0, 3)], m[(1, 1)] * ndc_y + m[(1, 3)])
+    }
+
+    pub fn viewport_height(&self) -> f32 {
+        -2.0 * self.viewport_matrix[(1, 1)]
+    }
+
+    // Colores de paleta escritos en sRGB; en el flujo lineal se convierten
+    // antes de iluminarlos y mezclarlos
+    pub fn palette(&self, r: u8, g: u8, b: u8) -> Color {
+        self.srgb(Color::new(r, g, b))
+    }
+
+    pub fn srgb(&self, color: Color) -> Color {
+        if self.linear_workflow { color.to_linear() } else { color }
+    }
+}
+
+pub fn create_model_matrix(translation: Vec3, scale: f32, rotation: Vec3) -> Mat4 {
+    let (sin_x, cos_x) = rotation.x.sin_cos();
+    let (sin_y, cos_y) = rotation.y.sin_cos();
+    let (sin_z, cos_z) = rotation.z.sin_cos();
+
+    let rotation_matrix_x = Mat4::new(
+        1.0,  0.0,    0.0,   0.0,
+        0.0,  cos_x, -sin_x, 0.0,
+        0.0,  sin_x,  cos_x, 0.0,
+        0.0,  0.0,    0.0,   1.0,
+    );
+
+    let rotation_matrix_y = Mat4::new(
+        cos_y,  0.0,  sin_y, 0.0,
+        0.0,    1.0,  0.0,   0.0,
+        -sin_y, 0.0,  cos_y, 0.0,
+        0.0,    0.0,  0.0,   1.0,
+    );
+
+    let rotation_matrix_z = Mat4::new(
+        cos_z, -sin_z, 0.0, 0.0,
+        sin_z,  cos_z, 0.0, 0.0,
+        0.0,    0.0,  1.0, 0.0,
+        0.0,    0.0,  0.0, 1.0,
+    );
+
+    let rotation_matrix = rotation_matrix_z * rotation_matrix_y * rotation_matrix_x;
+
+    let transform_matrix = Mat4::new(
+        scale, 0.0,   0.0,   translation.x,
+        0.0,   scale, 0.0,   translation.y,
+        0.0,   0.0,   scale, translation.z,
+        0.0,   0.0,   0.0,   1.0,
+    );
+
+    transform_matrix * rotation_matrix
+}
+
+pub fn create_view_matrix(eye: Vec3, center: Vec3, up: Vec3) -> Mat4 {
+    look_at(&eye, &center, &up)
+}
+
+pub fn create_perspective_matrix(window_width: f32, window_height: f32) -> Mat4 {
+    let fov = 45.0 * PI / 180.0;
+    let aspect_ratio = window_width / window_height;
+
+    perspective(fov, aspect_ratio, NEAR_PLANE, FAR_PLANE)
+}
+
+pub fn create_viewport_matrix(viewport: &Viewport) -> Mat4 {
+    let width = viewport.width as f32;
+    let height = viewport.height as f32;
+    Mat4::new(
+        width / 2.0, 0.0, 0.0, viewport.x as f32 + width / 2.0,
+        0.0, -height / 2.0, 0.0, viewport.y as f32 + height / 2.0,
+        0.0, 0.0, 1.0, 0.0,
+        0.0, 0.0, 0.0, 1.0
+    )
+}
+
+// Lo que se reusa de un objeto a otro y de un cuadro a otro, para no pedir
+// memoria nueva en cada `render`. Los fragmentos no se guardan: se sombrean
+// apenas salen del triángulo.
+pub struct RenderContext {
+    pub vertices: Vec<Vertex>,
+    // Ver `render_opaque`
+    pub depth_prepass: bool,
+    pub deferred: bool,
+    pub gbuffer: deferred::GBuffer,
+    pub profiler: profiler::Profiler,
+}
+
+impl RenderContext {
+    pub fn new(depth_prepass: bool, deferred: bool) -> Self {
+        RenderContext {
+            vertices: Vec::new(),
+            depth_prepass,
+            deferred,
+            gbuffer: deferred::GBuffer::new(),
+            profiler: profiler::Profiler::new(),
+        }
+    }
+}
+
+// Pasa cada fragmento del modelo a `shade` en cuanto sale del triángulo;
+// `clip` es lo que deja escribir el framebuffer. Con el perfil activo lo que
+// tarda `shade` cuenta como fragmentos y el resto del recorrido como rasterizado.
+fn rasterize(vertices: &mut Vec<Vertex>, profiler: &mut profiler::Profiler, uniforms: &Uniforms, vertex_array: &[Vertex], clip: Viewport, mut shade: impl FnMut(&Fragment)) {
+    profiler.time(profiler::Stage::Vertex, || {
+        vertices.clear();
+        vertices.extend(vertex_array.iter().map(|vertex| vertex_shader(vertex, uniforms)));
+    });
+
+    if !profiler.measuring() {
+        for tri in vertices.chunks_exact(3) {
+            triangle(&tri[0], &tri[1], &tri[2], &clip, |fragment| shade(&fragment));
+        }
+        return;
+    }
+    let start = std::time::Instant::now();
+    let mut shading = std::time::Duration::ZERO;
+    for tri in vertices.chunks_exact(3) {
+        triangle(&tri[0], &tri[1], &tri[2], &clip, |fragment| {
+            let shade_start = std::time::Instant::now();
+            shade(&fragment);
+            shading += shade_start.elapsed();
+        });
+    }
+    profiler.add(profiler::Stage::Fragment, shading);
+    profiler.add(profiler::Stage::Raster, start.elapsed().saturating_sub(shading));
+}
+
+pub fn render(framebuffer: &mut Framebuffer, context: &mut RenderContext, uniforms: &Uniforms, vertex_array: &[Vertex]) {
+    let emissive = uniforms.material.emissive && uniforms.debug_view == DebugView::Off;
+    rasterize(&mut context.vertices, &mut context.profiler, uniforms, vertex_array, framebuffer.clip_rect(), |fragment| {
+        let x = fragment.position.x as usize;
+        let y = fragment.position.y as usize;
+
+        // Lo opaco se prueba contra el zbuffer antes de sombrear: lo tapado no
+        // paga el ruido del shader
+        if x < framebuffer.width && y < framebuffer.height && framebuffer.depth_passes(x, y, fragment.depth) {
+            let shaded_color = fragment_shader(fragment, uniforms);
+            framebuffer.set_current_color(shaded_color);
+            if emissive {
+                framebuffer.point_emissive(x, y, fragment.depth);
+            } else {
+                framebuffer.point(x, y, fragment.depth);
+            }
+        }
+    });
+}
+
+// Solo escribe la profundidad del modelo, sin sombrear
+pub fn render_depth(framebuffer: &mut Framebuffer, context: &mut RenderContext, uniforms: &Uniforms, vertex_array: &[Vertex]) {
+    rasterize(&mut context.vertices, &mut context.profiler, uniforms, vertex_array, framebuffer.clip_rect(), |fragment| {
+        let x = fragment.position.x as usize;
+        let y = fragment.position.y as usize;
+
+        if x < framebuffer.width && y < framebuffer.height {
+            framebuffer.depth_point(x, y, fragment.depth);
+        }
+    });
+}
+
+// Solo llena el G-buffer con lo que pasa la prueba de profundidad
+pub fn render_gbuffer(framebuffer: &mut Framebuffer, context: &mut RenderContext, uniforms: &Uniforms, vertex_array: &[Vertex], material: u16) {
+    let RenderContext { vertices, gbuffer, profiler, .. } = context;
+    rasterize(vertices, profiler, uniforms, vertex_array, framebuffer.clip_rect(), |fragment| {
+        let x = fragment.position.x as usize;
+        let y = fragment.position.y as usize;
+
+        if x < framebuffer.width && y < framebuffer.height && framebuffer.depth_point(x, y, fragment.depth) {
+            gbuffer.write(y * framebuffer.width + x, fragment, material);
+        }
+    });
+}
+
+// Geometría opaca de la escena. Con la pre-pasada el zbuffer ya tiene lo
+// más cercano y solo se sombrea el fragmento que coincide con él. En el
+// camino diferido se guardan sus uniforms en `materials` y se sombrea
+// después, en `GBuffer::resolve`.
+pub fn render_opaque<'a>(framebuffer: &mut Framebuffer, context: &mut RenderContext, materials: &mut Vec<Uniforms<'a>>, uniforms: &Uniforms<'a>, vertex_array: &[Vertex]) {
+    if context.depth_prepass {
+        framebuffer.set_depth_test(DepthTest::Equal);
+    }
+    if context.deferred {
+        let material = materials.len() as u16;
+        materials.push(*uniforms);
+        render_gbuffer(framebuffer, context, uniforms, vertex_array, material);
+    } else {
+        render(framebuffer, context, uniforms, vertex_array);
+    }
+    framebuffer.set_depth_test(DepthTest::Less);
+}
+
+// Capa translúcida (auroras, atmósferas, halos): se mezcla sobre la imagen
+// con el modo de mezcla actual del framebuffer y no escribe profundidad
+pub fn render_blended(framebuffer: &mut Framebuffer, context: &mut RenderContext, uniforms: &Uniforms, vertex_array: &[Vertex], shader: BlendShader) {
+    // Las capas translúcidas taparían la vista de depuración
+    if uniforms.debug_view != DebugView::Off {
+        return;
+    }
+    rasterize(&mut context.vertices, &mut context.profiler, uniforms, vertex_array, framebuffer.clip_rect(), |fragment| {
+        let x = fragment.position.x as usize;
+        let y = fragment.position.y as usize;
+
+        if x < framebuffer.width && y < framebuffer.height {
+            let (color, alpha) = shader(fragment, uniforms);
+            if alpha > 0.0 {
+                framebuffer.point_rgba(x, y, fragment.depth, color, alpha);
+            }
+        }
+    });
+}
+
+
+pub fn draw_line(framebuffer: &mut Framebuffer, start: (isize, isize, f32), end: (isize, isize, f32)) {
+    line_pixels(start, end, &mut |x, y, depth| {
+        if x >= 0 && y >= 0 {
+            framebuffer.point(x as usize, y as usize, depth);
+        }
+    });
+}
+
+// Bresenham con la profundidad interpolada a lo largo del tramo
+pub fn line_pixels(start: (isize, isize, f32), end: (isize, isize, f32), plot: &mut impl FnMut(isize, isize, f32)) {
+    let (mut x0, mut y0, z0) = start;
+    let (x1, y1, z1) = end;
+
+    let dx = (x1 - x0).abs();
+    let dy = -(y1 - y0).abs();
+    let mut err = dx + dy;
+    let mut e2;
+
+    let sx = if x0 < x1 { 1 } else { -1 };
+    let sy = if y0 < y1 { 1 } else { -1 };
+
+    let steps = dx.max(-dy).max(1) as f32;
+    let mut step = 0.0;
+
+    loop {
+        plot(x0, y0, z0 + (z1 - z0) * (step / steps));
+
+        if x0 == x1 && y0 == y1 { break; }
+        e2 = 2 * err;
+        if e2 >= dy {
+            err += dy;
+            x0 += sx;
+        }
+        if e2 <= dx {
+            err += dx;
+            y0 += sy;
+        }
+        step += 1.0;
+    }
+}
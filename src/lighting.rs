@@ -0,0 +1,130 @@
+use nalgebra_glm::Vec3;
+use std::f32::consts::PI;
+use crate::color::Color;
+
+// Luz que queda en el lado de noche para que no sea negro del todo
+const AMBIENT: f32 = 0.04;
+
+#[derive(Clone, Copy, Debug)]
+pub enum LightKind {
+    // Ilumina en todas direcciones
+    Point,
+    // Cono alrededor de `direction`; `cos_cutoff` es el coseno del borde
+    Spot { direction: Vec3, cos_cutoff: f32 },
+}
+
+// Fuente de luz de la escena. Con `range` en 0 la luz no se atenúa (el sol
+// alcanza a todo el sistema); si no, se apaga del todo a esa distancia.
+#[derive(Clone, Copy, Debug)]
+pub struct Light {
+    pub position: Vec3,
+    pub color: Color,
+    pub intensity: f32,
+    pub range: f32,
+    pub kind: LightKind,
+}
+
+impl Light {
+    pub fn point(position: Vec3, color: Color, intensity: f32, range: f32) -> Self {
+        Light { position, color, intensity, range, kind: LightKind::Point }
+    }
+
+    pub fn spot(position: Vec3, direction: Vec3, color: Color, intensity: f32, range: f32, cos_cutoff: f32) -> Self {
+        Light { position, color, intensity, range, kind: LightKind::Spot { direction: direction.normalize(), cos_cutoff } }
+    }
+
+    // Dirección hacia la luz y cuánto llega de ella al punto, sin contar
+    // hacia dónde mira la superficie
+    fn incoming(&self, position: Vec3) -> Option<(Vec3, f32)> {
+        let to_light = self.position - position;
+        let distance = to_light.magnitude();
+        if distance < 1e-5 {
+            return None;
+        }
+        let direction = to_light / distance;
+        let mut amount = self.intensity;
+
+        // Cae suave hasta cero en el alcance, sin un borde marcado
+        if self.range > 0.0 {
+            let falloff = (1.0 - (distance / self.range).powi(2)).max(0.0);
+            amount *= falloff * falloff;
+        }
+        if let LightKind::Spot { direction: axis, cos_cutoff } = self.kind {
+            let cosine = (-direction).dot(&axis);
+            let t = ((cosine - cos_cutoff) / (1.0 - cos_cutoff).max(1e-5)).clamp(0.0, 1.0);
+            amount *= t * t * (3.0 - 2.0 * t);
+        }
+        Some((direction, amount))
+    }
+
+    // Cuánto llega de esta luz a un punto con esta normal
+    fn amount(&self, position: Vec3, normal: Vec3) -> f32 {
+        self.incoming(position)
+            .map_or(0.0, |(direction, amount)| amount * normal.dot(&direction).max(0.0))
+    }
+}
+
+// Suma de todas las luces en un punto del mundo. Devuelve la intensidad total
+// (lo que los shaders usan como `fragment.intensity`) y el tinte promedio de
+// las luces, que con luces blancas es blanco.
+pub fn illuminate(lights: &[Light], position: Vec3, normal: Vec3) -> (f32, Color) {
+    let mut total = AMBIENT;
+    let mut tint = Color::from_rgb_f32(AMBIENT, AMBIENT, AMBIENT);
+    for light in lights {
+        let amount = light.amount(position, normal);
+        total += amount;
+        tint += light.color * amount;
+    }
+    (total, tint * (1.0 / total))
+}
+
+// Superficie para la BRDF de los objetos fabricados: qué tan metálica es y
+// qué tan rugosa, los dos en 0..1
+#[derive(Clone, Copy, Debug)]
+pub struct SurfaceMaterial {
+    pub metallic: f32,
+    pub roughness: f32,
+}
+
+impl SurfaceMaterial {
+    pub const fn new(metallic: f32, roughness: f32) -> Self {
+        SurfaceMaterial { metallic, roughness }
+    }
+}
+
+// BRDF de microfacetas (GGX con Smith y Fresnel de Schlick) sumada sobre las
+// luces. El difuso no se divide por pi, así un objeto mate queda tan claro
+// como con la iluminación de los planetas.
+pub fn shade_pbr(lights: &[Light], position: Vec3, normal: Vec3, to_camera: Vec3, albedo: Color, material: SurfaceMaterial) -> Color {
+    let white = Color::from_rgb_f32(1.0, 1.0, 1.0);
+    let dielectric = Color::from_rgb_f32(0.04, 0.04, 0.04);
+    let f0 = dielectric.lerp(&albedo, material.metallic);
+    let roughness = material.roughness.clamp(0.05, 1.0);
+    let alpha = roughness * roughness;
+    let alpha2 = alpha * alpha;
+    let k = (roughness + 1.0).powi(2) / 8.0;
+    let n_dot_v = normal.dot(&to_camera).max(1e-4);
+
+    let mut color = albedo * AMBIENT * (1.0 - material.metallic);
+    for light in lights {
+        let Some((to_light, radiance)) = light.incoming(position) else {
+            continue;
+        };
+        let n_dot_l = normal.dot(&to_light);
+        if n_dot_l <= 0.0 {
+            continue;
+        }
+        let half = (to_light + to_camera).normalize();
+        let n_dot_h = normal.dot(&half).max(0.0);
+        let h_dot_v = half.dot(&to_camera).max(0.0);
+
+        let distribution = alpha2 / (PI * (n_dot_h * n_dot_h * (alpha2 - 1.0) + 1.0).powi(2));
+        let geometry = n_dot_v / (n_dot_v * (1.0 - k) + k) * n_dot_l / (n_dot_l * (1.0 - k) + k);
+        let fresnel = f0.lerp(&white, (1.0 - h_dot_v).powi(5));
+
+        let specular = fresnel * (distribution * geometry / (4.0 * n_dot_v * n_dot_l + 1e-4) * PI);
+        let diffuse = (white - fresnel) * (1.0 - material.metallic) * albedo;
+        color += (diffuse + specular) * light.color * (radiance * n_dot_l);
+    }
+    color
+}
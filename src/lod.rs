@@ -0,0 +1,160 @@
+use std::cell::OnceCell;
+use nalgebra_glm::{Vec3, Vec4};
+use crate::obj::Obj;
+use crate::picking;
+use crate::vertex::Vertex;
+use crate::Uniforms;
+
+// Parte de los triángulos de cada nivel simplificado
+const RATIOS: [f32; 3] = [0.5, 0.25, 0.1];
+// Radio en pantalla (en píxeles de 900 de alto) desde el que se usa cada
+// nivel: el completo arriba de 80, el primero simplificado entre 30 y 80...
+const MIN_PIXELS: [f32; 3] = [80.0, 30.0, 12.0];
+// Radio en pantalla desde el que se usa cada nivel subdividido, en los
+// primeros planos: con una subdivisión arriba de 450 (el cuerpo ya llena la
+// pantalla), con dos arriba de 1200
+const CLOSE_UP_PIXELS: [f32; 2] = [450.0, 1200.0];
+
+// Un modelo con versiones de cada vez menos triángulos, para dibujar con
+// menos detalle lo que se ve chico, y versiones subdivididas para cuando se
+// lo mira de muy cerca
+pub struct LodChain {
+    // El 0 es el modelo completo
+    levels: Vec<Vec<Vertex>>,
+    // El modelo del que salen los subdivididos, que se arman la primera vez
+    // que se piden: solo hacen falta para el cuerpo que se mira de cerca
+    source: Obj,
+    // Una y dos subdivisiones de Loop
+    refined: OnceCell<[Vec<Vertex>; 2]>,
+    // Esfera que lo envuelve, en coordenadas del modelo
+    center: Vec3,
+    radius: f32,
+}
+
+impl LodChain {
+    // Arma los niveles simplificando `obj`
+    pub fn new(obj: Obj) -> Self {
+        let levels = std::iter::once(obj.get_vertex_array())
+            .chain(RATIOS.iter().map(|&ratio| obj.decimate(ratio).get_vertex_array()))
+            .collect::<Vec<Vec<Vertex>>>();
+        let full = &levels[0];
+        let (min, max) = full.iter().fold(
+            (Vec3::repeat(f32::INFINITY), Vec3::repeat(f32::NEG_INFINITY)),
+            |(min, max), vertex| (min.inf(&vertex.position), max.sup(&vertex.position)),
+        );
+        let center = if full.is_empty() { Vec3::zeros() } else { (min + max) * 0.5 };
+        let radius = full.iter().map(|vertex| (vertex.position - center).norm()).fold(0.0, f32::max);
+        LodChain { levels, source: obj, refined: OnceCell::new(), center, radius }
+    }
+
+    pub fn full(&self) -> &[Vertex] {
+        &self.levels[0]
+    }
+
+    // Radio en pantalla (llevado a 900 de alto) con la `model_matrix` de
+    // `uniforms`; None si el centro queda detrás de la cámara, que puede
+    // estar adentro
+    fn screen_radius(&self, uniforms: &Uniforms) -> Option<f32> {
+        let model = uniforms.model_matrix;
+        let center = model * Vec4::new(self.center.x, self.center.y, self.center.z, 1.0);
+        let scale = (0..3).map(|column| model.fixed_view::<3, 1>(0, column).norm()).fold(0.0, f32::max);
+        let (_, _, _, pixels) = picking::project_sphere(uniforms, center.xyz(), self.radius * scale)?;
+        Some(pixels * 900.0 / uniforms.viewport_height())
+    }
+
+    // Nivel que corresponde al tamaño en pantalla; de muy cerca, el completo
+    pub fn level_for(&self, uniforms: &Uniforms) -> usize {
+        let Some(pixels) = self.screen_radius(uniforms) else {
+            return 0;
+        };
+        let level = MIN_PIXELS.iter().take_while(|&&min| pixels < min).count();
+        level.min(self.levels.len() - 1)
+    }
+
+    pub fn select(&self, uniforms: &Uniforms) -> &[Vertex] {
+        &self.levels[self.level_for(uniforms)]
+    }
+
+    fn refined(&self) -> &[Vec<Vertex>; 2] {
+        self.refined.get_or_init(|| {
+            let once = self.source.subdivide();
+            [once.get_vertex_array(), once.subdivide().get_vertex_array()]
+        })
+    }
+
+    // Como `select`, pero si llena la pantalla pasa a los subdivididos, que
+    // tienen cuatro y dieciséis veces los triángulos: es para el cuerpo que
+    // se está mirando, no para todos
+    pub fn select_close_up(&self, uniforms: &Uniforms) -> &[Vertex] {
+        let pixels = self.screen_radius(uniforms).unwrap_or(f32::INFINITY);
+        match CLOSE_UP_PIXELS.iter().take_while(|&&min| pixels >= min).count() {
+            0 => self.select(uniforms),
+            level => &self.refined()[level - 1],
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use fastnoise_lite::FastNoiseLite;
+    use crate::headless;
+    use crate::material::Material;
+    use crate::{create_model_matrix, CelestialBody};
+
+    // La esfera en el origen con la cámara fija del cuadro sin ventana, de
+    // 900 de alto, con `scale`
+    fn level_at(chain: &LodChain, scale: f32) -> (usize, f32) {
+        let noise = FastNoiseLite::new();
+        let material = Material::for_body(CelestialBody::Moon);
+        let uniforms = Uniforms {
+            model_matrix: create_model_matrix(Vec3::zeros(), scale, Vec3::zeros()),
+            ..headless::camera_uniforms(1200, 900, 0, &noise, &material, &[])
+        };
+        (chain.level_for(&uniforms), chain.screen_radius(&uniforms).unwrap_or(0.0))
+    }
+
+    #[test]
+    fn level_for_follows_the_screen_radius() {
+        let chain = LodChain::new(Obj::sphere(16, 32));
+        assert_eq!(chain.levels.len(), RATIOS.len() + 1);
+
+        // El radio en pantalla crece en proporción a la escala
+        let (_, unit) = level_at(&chain, 1.0);
+        for (pixels, level) in [(200.0, 0), (50.0, 1), (20.0, 2), (5.0, 3)] {
+            let (chosen, measured) = level_at(&chain, pixels / unit);
+            assert!((measured - pixels).abs() < 0.01, "{} píxeles en vez de {}", measured, pixels);
+            assert_eq!(chosen, level, "a {} píxeles", pixels);
+        }
+    }
+
+    #[test]
+    fn refined_levels_are_built_on_first_close_up() {
+        let chain = LodChain::new(Obj::sphere(16, 32));
+        assert!(chain.refined.get().is_none());
+
+        let (_, unit) = level_at(&chain, 1.0);
+        let noise = FastNoiseLite::new();
+        let material = Material::for_body(CelestialBody::Moon);
+        let close_up = |pixels: f32| Uniforms {
+            model_matrix: create_model_matrix(Vec3::zeros(), pixels / unit, Vec3::zeros()),
+            ..headless::camera_uniforms(1200, 900, 0, &noise, &material, &[])
+        };
+        assert_eq!(chain.select_close_up(&close_up(100.0)).len(), chain.full().len());
+        assert!(chain.refined.get().is_none());
+        assert_eq!(chain.select_close_up(&close_up(600.0)).len(), chain.full().len() * 4);
+        assert_eq!(chain.select_close_up(&close_up(1500.0)).len(), chain.full().len() * 16);
+    }
+
+    #[test]
+    fn level_for_is_full_when_the_center_is_behind_the_camera() {
+        let chain = LodChain::new(Obj::sphere(16, 32));
+        let noise = FastNoiseLite::new();
+        let material = Material::for_body(CelestialBody::Moon);
+        let uniforms = Uniforms {
+            model_matrix: create_model_matrix(Vec3::new(0.0, 0.0, 20.0), 0.01, Vec3::zeros()),
+            ..headless::camera_uniforms(1200, 900, 0, &noise, &material, &[])
+        };
+        assert_eq!(chain.level_for(&uniforms), 0);
+    }
+}
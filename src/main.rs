@@ -1,152 +1,436 @@
-use nalgebra_glm::{Vec3, Vec4, Mat4, look_at, perspective};
-use minifb::{Key, Window, WindowOptions};
-use std::time::Duration;
+use nalgebra_glm::{Vec3, Vec4, Mat4};
+use minifb::{Key, MouseButton, MouseMode, Window, WindowOptions};
 use std::f32::consts::PI;
+use std::rc::Rc;
 use rand::Rng;
-
-mod framebuffer;
-mod triangle;
-mod vertex;
-mod obj;
-mod color;
-mod fragment;
-mod shaders;
-mod camera;
-
-use framebuffer::Framebuffer;
+use rand::rngs::StdRng;
+
+use sistema_solar::{
+    framebuffer, vertex, obj, color, shaders, camera, postprocess, corona, noise_utils, render_queue,
+    text, constellation, sky, meteors, particles, tween, tour, bookmarks, camera_path, minimap, trails,
+    spatial, missions, satellites, lagrange, ephemeris, units, panel, picking, keybindings, settings,
+    debug_ui, pacing, redraw, lighting, material, profiler, seed, assets, lod,
+};
+use sistema_solar::{
+    NEAR_PLANE, BODY_RADIUS, SURFACE_RADIUS, CelestialBody, Uniforms, RenderContext,
+    create_model_matrix, create_view_matrix, create_perspective_matrix, create_viewport_matrix,
+    render, render_depth, render_opaque, render_blended, draw_line, line_pixels,
+};
+
+use framebuffer::{Framebuffer, DepthTest, BlendMode, Viewport, StencilFunc, StencilState};
 use vertex::Vertex;
 use obj::Obj;
-use camera::Camera;
-use triangle::triangle;
-use shaders::{vertex_shader, fragment_shader};
-use fastnoise_lite::{FastNoiseLite, NoiseType};
-
-#[derive(Clone, Copy)]
-pub enum CelestialBody {
-    Sun,
-    RockyPlanet,
-    GasGiant,
-    CloudyPlanet,
-    RingedPlanet,
-    IcePlanet,
-    ColorPlanet,
-    Moon,
-    OceanPlanet,    
-    NaturePlanet,   
-    AuroraPlanet, 
-    Spaceship
-}
-
-pub struct Uniforms {
-    model_matrix: Mat4,
-    view_matrix: Mat4,
-    projection_matrix: Mat4,
-    viewport_matrix: Mat4,
-    time: u32,
-    noise: FastNoiseLite,
-    current_body: CelestialBody,  
+use camera::{Camera, CameraMode};
+use shaders::{aurora_glow_shader, atmosphere_haze_shader, outline_shader, encode_depth, DepthMode, DebugView};
+use color::Color;
+use postprocess::{PostChain, PostContext, ToneMapping, WarpStreaks};
+use render_queue::RenderQueue;
+use noise_utils::NoiseConfig;
+use material::Material;
+use fastnoise_lite::FastNoiseLite;
+use constellation::Constellation;
+use keybindings::Action;
+use lod::LodChain;
+use seed::{Seed, Stream};
+
+// Todo lo que se dibuja en un cuadro; es lo mismo para cada vista
+struct Scene<'a> {
+    planets: &'a [Planet],
+    moon: &'a Moon,
+    earth_position: Vec3,
+    station: &'a SpaceStation,
+    station_model: &'a LodChain,
+    satellites: &'a satellites::SatelliteSystem,
+    // Puntos de Lagrange a marcar; vacío si están ocultos
+    lagrange: &'a [lagrange::LagrangePoints],
+    skybox: &'a Skybox,
+    // Planeta al que se le dibuja el contorno
+    selected: Option<usize>,
+    show_constellations: bool,
+    meteors: &'a meteors::MeteorShower,
+    exhaust: &'a particles::ParticleSystem,
+    // Cada modelo con sus niveles de detalle; se usa el que corresponde al
+    // tamaño en pantalla
+    sphere: &'a LodChain,
+    ship: &'a LodChain,
+    ship_model_matrix: Mat4,
+    ship_material: &'a Material,
+    // Esferas de los cuerpos, para descartar los que quedan fuera de cada vista
+    bodies: &'a spatial::SpatialGrid,
+    // Trayectoria prevista de la nave en vuelo y si termina en un choque
+    predicted_path: Option<(Vec<Vec3>, bool)>,
 }
 
-fn create_noise() -> FastNoiseLite {
-    let mut noise = FastNoiseLite::with_seed(1337);
-    noise.set_noise_type(Some(NoiseType::OpenSimplex2));
-    noise
+fn planet_uniforms<'a>(planet: &'a Planet, frame_uniforms: &Uniforms<'a>) -> Uniforms<'a> {
+    Uniforms {
+        model_matrix: create_model_matrix(planet.position, planet.scale, planet.rotation),
+        noise: &planet.noise,
+        material: &planet.material,
+        ..*frame_uniforms
+    }
 }
 
-fn create_model_matrix(translation: Vec3, scale: f32, rotation: Vec3) -> Mat4 {
-    let (sin_x, cos_x) = rotation.x.sin_cos();
-    let (sin_y, cos_y) = rotation.y.sin_cos();
-    let (sin_z, cos_z) = rotation.z.sin_cos();
+// Nivel de detalle de la esfera de un planeta; el seleccionado se subdivide
+// si se lo mira de muy cerca
+fn planet_mesh<'a>(scene: &Scene<'a>, index: usize, uniforms: &Uniforms) -> &'a [Vertex] {
+    if scene.selected == Some(index) {
+        scene.sphere.select_close_up(uniforms)
+    } else {
+        scene.sphere.select(uniforms)
+    }
+}
 
-    let rotation_matrix_x = Mat4::new(
-        1.0,  0.0,    0.0,   0.0,
-        0.0,  cos_x, -sin_x, 0.0,
-        0.0,  sin_x,  cos_x, 0.0,
-        0.0,  0.0,    0.0,   1.0,
-    );
+// Con la escala real casi todos los planetas quedan más chicos que un
+// píxel: se dibujan como un punto de su color para no perderlos. Devuelve
+// dónde va el punto y su profundidad.
+fn point_sized(frame_uniforms: &Uniforms, planet: &Planet) -> Option<(f32, f32, f32)> {
+    picking::project_sphere(frame_uniforms, planet.position, planet.scale * BODY_RADIUS)
+        .filter(|&(_, _, _, radius)| radius < 1.5)
+        .map(|(x, y, depth, _)| (x, y, depth))
+}
 
-    let rotation_matrix_y = Mat4::new(
-        cos_y,  0.0,  sin_y, 0.0,
-        0.0,    1.0,  0.0,   0.0,
-        -sin_y, 0.0,  cos_y, 0.0,
-        0.0,    0.0,  0.0,   1.0,
-    );
+// Dibuja la escena con la cámara y el viewport de `frame_uniforms`. El
+// framebuffer ya debe tener ese viewport puesto.
+// `layers` guarda el fondo y las órbitas para el cuadro siguiente; con la
+// pantalla dividida no se pasa y todo se dibuja de nuevo
+fn render_scene(framebuffer: &mut Framebuffer, context: &mut RenderContext, frame_uniforms: &Uniforms, scene: &Scene, draw_ship: bool, mut layers: Option<&mut redraw::StaticLayers>) {
+    // 1. Renderiza el skybox primero: siempre al fondo y sin escribir profundidad
+    framebuffer.set_depth_test(DepthTest::Always);
+    framebuffer.set_write_depth(false);
+    match layers.as_deref_mut() {
+        Some(layers) if layers.restore_background(framebuffer) => {}
+        Some(layers) => {
+            scene.skybox.render_background(framebuffer, frame_uniforms);
+            layers.store_background(framebuffer);
+        }
+        None => scene.skybox.render_background(framebuffer, frame_uniforms),
+    }
+    scene.skybox.render_stars(framebuffer, frame_uniforms);
+    if scene.show_constellations {
+        scene.skybox.render_constellations(framebuffer, frame_uniforms);
+    }
+    framebuffer.set_depth_test(DepthTest::Less);
+    framebuffer.set_write_depth(true);
+
+    // Lo que queda fuera del frustum ni se rasteriza
+    let frustum = spatial::Frustum::from_matrix(&(frame_uniforms.projection_matrix * frame_uniforms.view_matrix));
+    let visible = scene.bodies.query_frustum(&frustum);
+
+    let moon_uniforms = Uniforms {
+        model_matrix: create_model_matrix(scene.moon.position, scene.moon.scale, scene.moon.rotation),
+        noise: &scene.moon.noise,
+        material: &scene.moon.material,
+        ..*frame_uniforms
+    };
+    let station_uniforms = Uniforms {
+        model_matrix: create_model_matrix(scene.station.position, scene.station.scale, scene.station.rotation),
+        noise: &scene.station.noise,
+        material: &scene.station.material,
+        ..*frame_uniforms
+    };
+    let spacecraft_uniforms = Uniforms {
+        model_matrix: scene.ship_model_matrix,
+        noise: frame_uniforms.noise,
+        material: scene.ship_material,
+        ..*frame_uniforms
+    };
+
+    // Pre-pasada: la profundidad de todo lo opaco va primero, así después
+    // cada píxel se sombrea una sola vez, con lo que haya quedado adelante
+    if context.depth_prepass {
+        for (index, planet) in scene.planets.iter().enumerate() {
+            if visible[index] && point_sized(frame_uniforms, planet).is_none() {
+                let uniforms = planet_uniforms(planet, frame_uniforms);
+                render_depth(framebuffer, context, &uniforms, planet_mesh(scene, index, &uniforms));
+            }
+        }
+        if visible[scene.planets.len()] {
+            render_depth(framebuffer, context, &moon_uniforms, scene.sphere.select(&moon_uniforms));
+        }
+        render_depth(framebuffer, context, &station_uniforms, scene.station_model.select(&station_uniforms));
+        if draw_ship {
+            render_depth(framebuffer, context, &spacecraft_uniforms, scene.ship.select(&spacecraft_uniforms));
+        }
+    }
 
-    let rotation_matrix_z = Mat4::new(
-        cos_z, -sin_z, 0.0, 0.0,
-        sin_z,  cos_z, 0.0, 0.0,
-        0.0,    0.0,  1.0, 0.0,
-        0.0,    0.0,  0.0, 1.0,
-    );
+    // Uniforms de cada objeto opaco, para sombrear el G-buffer
+    let mut materials = Vec::new();
+    if context.deferred {
+        context.gbuffer.fit(framebuffer);
+    }
 
-    let rotation_matrix = rotation_matrix_z * rotation_matrix_y * rotation_matrix_x;
+    // 2. Renderiza planetas; las capas translúcidas van a la cola
+    let mut render_queue = RenderQueue::new();
+    for (index, planet) in scene.planets.iter().enumerate() {
+        if !visible[index] {
+            continue;
+        }
+        if let Some((x, y, depth)) = point_sized(frame_uniforms, planet) {
+            let (r, g, b) = planet.material.map_color;
+            framebuffer.set_current_color(frame_uniforms.palette(r, g, b));
+            for (dx, dy) in [(0.0, 0.0), (1.0, 0.0), (0.0, 1.0), (1.0, 1.0)] {
+                if x + dx >= 0.0 && y + dy >= 0.0 {
+                    framebuffer.point((x + dx) as usize, (y + dy) as usize, depth);
+                }
+            }
+            continue;
+        }
+        let uniforms = planet_uniforms(planet, frame_uniforms);
+        // Las capas de encima usan el mismo nivel, así coinciden con el borde
+        let sphere = planet_mesh(scene, index, &uniforms);
 
-    let transform_matrix = Mat4::new(
-        scale, 0.0,   0.0,   translation.x,
-        0.0,   scale, 0.0,   translation.y,
-        0.0,   0.0,   scale, translation.z,
-        0.0,   0.0,   0.0,   1.0,
-    );
+        // El seleccionado queda marcado en el stencil para el contorno
+        if scene.selected == Some(index) {
+            framebuffer.set_stencil(Some(StencilState::write(1)));
+        }
+        render_opaque(framebuffer, context, &mut materials, &uniforms, sphere);
+        framebuffer.set_stencil(None);
+
+        // Las auroras sobresalen un poco del borde del planeta
+        if matches!(planet.body_type, CelestialBody::AuroraPlanet) {
+            let shell_uniforms = Uniforms {
+                model_matrix: create_model_matrix(planet.position, planet.scale * 1.08, planet.rotation),
+                ..uniforms
+            };
+            render_queue.push_transparent(shell_uniforms, sphere, aurora_glow_shader, BlendMode::Additive, planet.position);
+        }
 
-    transform_matrix * rotation_matrix
-}
+        // Capa de atmósfera translúcida sobre el planeta con nubes
+        if matches!(planet.body_type, CelestialBody::CloudyPlanet) {
+            let shell_uniforms = Uniforms {
+                model_matrix: create_model_matrix(planet.position, planet.scale * 1.04, planet.rotation),
+                ..uniforms
+            };
+            render_queue.push_transparent(shell_uniforms, sphere, atmosphere_haze_shader, BlendMode::Alpha, planet.position);
+        }
+    }
 
-fn create_view_matrix(eye: Vec3, center: Vec3, up: Vec3) -> Mat4 {
-    look_at(&eye, &center, &up)
-}
+    // 3. Renderiza la luna
+    if visible[scene.planets.len()] {
+        render_opaque(framebuffer, context, &mut materials, &moon_uniforms, scene.sphere.select(&moon_uniforms));
+    }
 
-fn create_perspective_matrix(window_width: f32, window_height: f32) -> Mat4 {
-    let fov = 45.0 * PI / 180.0;
-    let aspect_ratio = window_width / window_height;
-    let near = 0.1;
-    let far = 1000.0;
+    // La estación es chica y no está en la grilla: se dibuja siempre
+    render_opaque(framebuffer, context, &mut materials, &station_uniforms, scene.station_model.select(&station_uniforms));
 
-    perspective(fov, aspect_ratio, near, far)
-}
+    // La nave va con el resto de lo opaco, antes de lo que se dibuja encima.
+    // Desde la cabina no se dibuja: la cámara está dentro.
+    if draw_ship {
+        render_opaque(framebuffer, context, &mut materials, &spacecraft_uniforms, scene.ship.select(&spacecraft_uniforms));
+    }
 
-fn create_viewport_matrix(width: f32, height: f32) -> Mat4 {
-    Mat4::new(
-        width / 2.0, 0.0, 0.0, width / 2.0,
-        0.0, -height / 2.0, 0.0, height / 2.0,
-        0.0, 0.0, 1.0, 0.0,
-        0.0, 0.0, 0.0, 1.0
-    )
-}
+    // En el camino diferido recién acá se sombrea lo opaco
+    if context.deferred {
+        let RenderContext { gbuffer, profiler, .. } = context;
+        profiler.time(profiler::Stage::Fragment, || gbuffer.resolve(framebuffer, &materials));
+    }
+
+    let planet_positions: Vec<Vec3> = scene.planets.iter().map(|planet| planet.position).collect();
+    framebuffer.set_write_depth(false);
+    scene.satellites.render(framebuffer, frame_uniforms, &planet_positions);
+    framebuffer.set_write_depth(true);
+
+    // 4. Órbitas: con su profundidad real, tapadas por los cuerpos pero sin taparlos
+    framebuffer.set_write_depth(false);
+    framebuffer.set_current_color(frame_uniforms.palette(0x40, 0x40, 0x40));
+    let shapes: Vec<(f32, f32, f32)> = scene.planets.iter()
+        .filter(|planet| planet.orbit_radius > 0.0)
+        .map(|planet| (planet.orbit_radius, planet.eccentricity, planet.perihelion))
+        .collect();
+    let cached = layers.as_deref().and_then(|layers| layers.orbits(&shapes)).map(<[_]>::to_vec);
+    let pixels = cached.unwrap_or_else(|| {
+        let mut pixels = Vec::new();
+        for &(radius, eccentricity, perihelion) in &shapes {
+            orbit_pixels(radius, eccentricity, perihelion, Vec3::new(0.0, 0.0, 0.0), frame_uniforms, &mut |x, y, depth| {
+                if x >= 0 && y >= 0 {
+                    pixels.push((x as usize, y as usize, depth));
+                }
+            });
+        }
+        pixels
+    });
+    for &(x, y, depth) in &pixels {
+        framebuffer.point(x, y, depth);
+    }
+    if let Some(layers) = layers {
+        layers.store_orbits(shapes, pixels);
+    }
+    framebuffer.set_current_color(frame_uniforms.palette(0x30, 0x30, 0x30));
+    // Alrededor del baricentro: la luna por fuera y, si pesa, el planeta por dentro
+    let share = scene.moon.parent_share();
+    draw_orbit(framebuffer, scene.moon.orbit_radius * (1.0 - share), 0.0, 0.0, scene.earth_position, frame_uniforms);
+    if share > 0.0 {
+        draw_orbit(framebuffer, scene.moon.orbit_radius * share, 0.0, 0.0, scene.earth_position, frame_uniforms);
+    }
+
+    // Trayectoria prevista: cian que se apaga con la distancia, roja al final si choca
+    if let Some((path, impact)) = &scene.predicted_path {
+        let path_color = frame_uniforms.palette(80, 220, 255);
+        let impact_color = frame_uniforms.palette(255, 60, 40);
+        trails::render_polyline(framebuffer, frame_uniforms, path, |t| {
+            if *impact && t > 0.9 { impact_color * 2.0 } else { path_color * (1.0 - t * 0.8) }
+        });
+    }
 
-fn render(framebuffer: &mut Framebuffer, uniforms: &Uniforms, vertex_array: &[Vertex]) {
-    let mut transformed_vertices = Vec::with_capacity(vertex_array.len());
-    for vertex in vertex_array {
-        let transformed = vertex_shader(vertex, uniforms);
-        transformed_vertices.push(transformed);
+    // Estelas con las posiciones recientes de cada cuerpo
+    for planet in scene.planets {
+        if planet.orbit_radius > 0.0 {
+            let (r, g, b) = planet.material.map_color;
+            planet.trail.render(framebuffer, frame_uniforms, planet.position, frame_uniforms.palette(r, g, b) * 0.5);
+        }
+    }
+    let (r, g, b) = scene.moon.material.map_color;
+    scene.moon.trail.render(framebuffer, frame_uniforms, scene.moon.position, frame_uniforms.palette(r, g, b) * 0.4);
+
+    for points in scene.lagrange {
+        points.render(framebuffer, frame_uniforms);
+    }
+
+    // Estrellas fugaces en la esfera del cielo; los planetas ya dibujados las tapan
+    scene.meteors.render(framebuffer, frame_uniforms);
+    framebuffer.set_write_depth(true);
+
+    // 5. Corona y protuberancias del sol, después de los cuerpos que la puedan tapar
+    if let Some(sun) = scene.planets.iter().find(|p| matches!(p.body_type, CelestialBody::Sun)) {
+        let sun_uniforms = Uniforms {
+            noise: &sun.noise,
+            material: &sun.material,
+            ..*frame_uniforms
+        };
+        corona::render_corona(framebuffer, &sun_uniforms, sun.position, sun.scale * 0.5);
     }
 
-    let mut triangles = Vec::new();
-    for i in (0..transformed_vertices.len()).step_by(3) {
-        if i + 2 < transformed_vertices.len() {
-            triangles.push([
-                transformed_vertices[i].clone(),
-                transformed_vertices[i + 1].clone(),
-                transformed_vertices[i + 2].clone(),
-            ]);
+    // Escape de los motores, después de la nave para que ella lo tape
+    scene.exhaust.render(framebuffer, frame_uniforms);
+
+    // Contorno del seleccionado: una esfera algo más grande de color liso que
+    // solo llega a los píxeles que el planeta no marcó en el stencil
+    if let Some(index) = scene.selected {
+        let planet = &scene.planets[index];
+        if visible[index] && point_sized(frame_uniforms, planet).is_none() {
+            let outline_uniforms = Uniforms {
+                model_matrix: create_model_matrix(planet.position, planet.scale * 1.06, planet.rotation),
+                ..planet_uniforms(planet, frame_uniforms)
+            };
+            framebuffer.set_stencil(Some(StencilState::test(StencilFunc::NotEqual, 1)));
+            framebuffer.set_write_depth(false);
+            render_blended(framebuffer, context, &outline_uniforms, scene.sphere.select_close_up(&outline_uniforms), outline_shader);
+            framebuffer.set_write_depth(true);
+            framebuffer.set_stencil(None);
         }
     }
 
-    let mut fragments = Vec::new();
-    for tri in &triangles {
-        fragments.extend(triangle(&tri[0], &tri[1], &tri[2]));
+    // Translúcidos de atrás hacia adelante, sobre toda la geometría opaca
+    for draw in render_queue.drain_back_to_front() {
+        framebuffer.set_blend_mode(draw.blend_mode);
+        render_blended(framebuffer, context, &draw.uniforms, draw.vertices, draw.shader);
     }
+    framebuffer.set_blend_mode(BlendMode::Replace);
+}
 
-    for fragment in fragments {
-        let x = fragment.position.x as usize;
-        let y = fragment.position.y as usize;
+// Líneas de texto centradas arriba, encima de todo
+fn render_readout(framebuffer: &mut Framebuffer, frame_uniforms: &Uniforms, lines: &[String], pixel_scale: usize) {
+    let scale = 2 * pixel_scale;
+    let line = (text::text_height(scale) + 4 * pixel_scale) as isize;
+    let mut y = 10 * pixel_scale as isize;
+
+    framebuffer.set_depth_test(DepthTest::Always);
+    framebuffer.set_write_depth(false);
+    framebuffer.set_current_color(frame_uniforms.palette(220, 230, 255));
+    for text_line in lines {
+        let x = framebuffer.width as isize / 2 - text::text_width(text_line, scale) as isize / 2;
+        text::draw_text(text_line, x, y, scale, |x, y| {
+            if x >= 0 && y >= 0 {
+                framebuffer.point(x as usize, y as usize, 0.0);
+            }
+        });
+        y += line;
+    }
+    framebuffer.set_depth_test(DepthTest::Less);
+    framebuffer.set_write_depth(true);
+}
 
-        if x < framebuffer.width && y < framebuffer.height {
-            let shaded_color = fragment_shader(&fragment, &uniforms);
-            let color = shaded_color.to_hex();
-            framebuffer.set_current_color(color);
-            framebuffer.point(x, y, fragment.depth);
+// Combustible y estado de acoplamiento abajo a la izquierda
+fn render_flight_status(framebuffer: &mut Framebuffer, frame_uniforms: &Uniforms, spacecraft: &Spacecraft, pixel_scale: usize) {
+    let scale = 2 * pixel_scale;
+    let margin = 10 * pixel_scale as isize;
+    let line = (text::text_height(scale) + 4 * pixel_scale) as isize;
+    let mut y = framebuffer.height as isize - margin - line;
+
+    framebuffer.set_depth_test(DepthTest::Always);
+    framebuffer.set_write_depth(false);
+
+    let fuel = format!("Combustible {}%", (spacecraft.fuel * 100.0).round() as u32);
+    let (r, g, b) = if spacecraft.fuel < 0.2 { (255, 80, 60) } else { (200, 220, 255) };
+    framebuffer.set_current_color(frame_uniforms.palette(r, g, b));
+    text::draw_text(&fuel, margin, y, scale, |x, y| {
+        if x >= 0 && y >= 0 {
+            framebuffer.point(x as usize, y as usize, 0.0);
         }
+    });
+
+    if spacecraft.docked {
+        y -= line;
+        framebuffer.set_current_color(frame_uniforms.palette(120, 255, 140));
+        text::draw_text("Acoplada: recargando (W para salir)", margin, y, scale, |x, y| {
+            if x >= 0 && y >= 0 {
+                framebuffer.point(x as usize, y as usize, 0.0);
+            }
+        });
     }
+
+    framebuffer.set_depth_test(DepthTest::Less);
+    framebuffer.set_write_depth(true);
+}
+
+// Vista pequeña en la esquina inferior derecha con un primer plano del
+// planeta, desde un punto fijo del lado iluminado. Usa su propio pedazo
+// del zbuffer para no mezclarse con la escena de atrás.
+fn render_inset(framebuffer: &mut Framebuffer, context: &mut RenderContext, frame_uniforms: &Uniforms, planet: &Planet, vertex_array: &[Vertex], pixel_scale: usize) {
+    let width = framebuffer.width / 4;
+    let height = width * 3 / 4;
+    let margin = 10 * pixel_scale;
+    let border = pixel_scale;
+    if width + margin + 2 * border > framebuffer.width || height + margin + 2 * border > framebuffer.height {
+        return;
+    }
+    let inset = Viewport::new(
+        framebuffer.width - width - margin,
+        framebuffer.height - height - margin,
+        width,
+        height,
+    );
+
+    // Marco alrededor de la vista
+    framebuffer.set_viewport(Viewport::new(inset.x - border, inset.y - border, width + 2 * border, height + 2 * border));
+    framebuffer.set_background_color(frame_uniforms.palette(0x80, 0x80, 0x90));
+    framebuffer.clear_viewport();
+
+    framebuffer.set_viewport(inset);
+    framebuffer.set_background_color(frame_uniforms.palette(0x02, 0x02, 0x08));
+    framebuffer.clear_viewport();
+
+    // Entre el sol y el planeta, algo girado para ver el terminador
+    let to_sun = frame_uniforms.light_position - planet.position;
+    let to_sun = if to_sun.magnitude() > 0.001 { to_sun.normalize() } else { Vec3::new(0.0, 0.0, 1.0) };
+    let direction = (nalgebra_glm::rotate_vec3(&to_sun, 0.6, &Vec3::new(0.0, 1.0, 0.0)) + Vec3::new(0.0, 0.3, 0.0)).normalize();
+    let eye = planet.position + direction * (planet.scale * BODY_RADIUS * 3.5);
+
+    let uniforms = Uniforms {
+        model_matrix: create_model_matrix(planet.position, planet.scale, planet.rotation),
+        view_matrix: create_view_matrix(eye, planet.position, Vec3::new(0.0, 1.0, 0.0)),
+        projection_matrix: create_perspective_matrix(width as f32, height as f32),
+        viewport_matrix: create_viewport_matrix(&inset),
+        noise: &planet.noise,
+        material: &planet.material,
+        camera_position: eye,
+        ..*frame_uniforms
+    };
+    framebuffer.set_depth_test(DepthTest::Less);
+    framebuffer.set_write_depth(true);
+    render(framebuffer, context, &uniforms, vertex_array);
+
+    framebuffer.reset_viewport();
 }
 
 fn handle_input(window: &Window, camera: &mut Camera) {
@@ -154,13 +438,13 @@ fn handle_input(window: &Window, camera: &mut Camera) {
 
     // Movimiento orbital con flechas
     if window.is_key_down(Key::Left) {
-        camera.orbit(-1.0 * speed, 0.0);
+        camera.orbit(-speed, 0.0);
     }
     if window.is_key_down(Key::Right) {
         camera.orbit(1.0 * speed, 0.0);
     }
     if window.is_key_down(Key::Up) {
-        camera.orbit(0.0, -1.0 * speed);
+        camera.orbit(0.0, -speed);
     }
     if window.is_key_down(Key::Down) {
         camera.orbit(0.0, 1.0 * speed);
@@ -197,6 +481,57 @@ fn handle_input(window: &Window, camera: &mut Camera) {
     }
 }
 
+// Velocidades del vuelo libre, en unidades por cuadro (teclas 1, 2 y 3)
+const FREE_FLY_SPEEDS: [f32; 3] = [0.05, 0.25, 1.0];
+
+fn handle_free_fly(window: &Window, camera: &mut Camera, speed: f32, mouse_delta: (f32, f32)) {
+    let axis = |positive: Key, negative: Key| {
+        let mut value = 0.0;
+        if window.is_key_down(positive) {
+            value += 1.0;
+        }
+        if window.is_key_down(negative) {
+            value -= 1.0;
+        }
+        value
+    };
+
+    let forward = camera.get_view_direction();
+    let right = camera.get_right();
+    let up = camera.up;
+    camera.translate(
+        (forward * axis(Key::W, Key::S) + right * axis(Key::D, Key::A) + up * axis(Key::Space, Key::LeftCtrl)) * speed,
+    );
+
+    let turn = camera.rotation_speed;
+    camera.free_look(
+        (axis(Key::Left, Key::Right) - mouse_delta.0 * 0.1) * turn,
+        (axis(Key::Up, Key::Down) - mouse_delta.1 * 0.1) * turn,
+        axis(Key::E, Key::Q) * turn,
+    );
+}
+
+fn read_flight_input(window: &Window, mouse_delta: (f32, f32)) -> FlightInput {
+    let axis = |positive: Key, negative: Key| {
+        let mut value = 0.0;
+        if window.is_key_down(positive) {
+            value += 1.0;
+        }
+        if window.is_key_down(negative) {
+            value -= 1.0;
+        }
+        value
+    };
+    let boost = if window.is_key_down(Key::LeftShift) { 2.0 } else { 1.0 };
+
+    FlightInput {
+        thrust: axis(Key::W, Key::S) * boost,
+        pitch: axis(Key::Up, Key::Down) - mouse_delta.1 * 0.15,
+        yaw: axis(Key::A, Key::D) - mouse_delta.0 * 0.15,
+        roll: axis(Key::E, Key::Q),
+    }
+}
+
 struct Moon {
     position: Vec3,
     rotation: Vec3,
@@ -205,10 +540,18 @@ struct Moon {
     orbit_speed: f32,
     orbit_angle: f32,
     parent_position: Vec3,
+    // Masa de la luna sobre la del planeta. Con 0 el planeta queda quieto en
+    // el centro; si no, los dos giran alrededor del baricentro común.
+    mass_ratio: f32,
+    material: Material,
+    noise: FastNoiseLite,
+    trail: trails::Trail,
 }
 
 impl Moon {
     fn new(orbit_radius: f32, orbit_speed: f32) -> Self {
+        let material = Material::for_body(CelestialBody::Moon);
+
         Moon {
             position: Vec3::new(0.0, 0.0, 0.0),
             rotation: Vec3::new(0.0, 0.0, 0.0),
@@ -217,34 +560,111 @@ impl Moon {
             orbit_speed,
             orbit_angle: 0.0,
             parent_position: Vec3::new(0.0, 0.0, 0.0),
+            mass_ratio: 0.0,
+            noise: material.noise.build(),
+            material,
+            trail: trails::Trail::new(160, 2),
         }
     }
 
-    fn update(&mut self, parent_pos: Vec3) {
-        self.rotation.y += 0.01;
-        self.orbit_angle += self.orbit_speed;
-        self.parent_position = parent_pos;
+    fn with_mass_ratio(mut self, mass_ratio: f32) -> Self {
+        self.mass_ratio = mass_ratio.max(0.0);
+        self
+    }
+
+    // Parte de la distancia planeta-luna que queda del lado del planeta
+    fn parent_share(&self) -> f32 {
+        self.mass_ratio / (1.0 + self.mass_ratio)
+    }
+
+    // `barycenter` es el punto de la órbita del planeta. Devuelve cuánto hay
+    // que correr al planeta para que el baricentro quede ahí.
+    fn update(&mut self, barycenter: Vec3, time_scale: f32) -> Vec3 {
+        self.rotation.y += 0.01 * time_scale;
+        self.orbit_angle += self.orbit_speed * time_scale;
+        self.parent_position = barycenter;
         
-        let relative_x = self.orbit_angle.cos() * self.orbit_radius;
-        let relative_z = self.orbit_angle.sin() * self.orbit_radius;
+        let relative = Vec3::new(self.orbit_angle.cos(), 0.0, self.orbit_angle.sin()) * self.orbit_radius;
+        let share = self.parent_share();
         
-        self.position = Vec3::new(
-            parent_pos.x + relative_x,
-            parent_pos.y,
-            parent_pos.z + relative_z
+        self.position = barycenter + relative * (1.0 - share);
+        self.trail.record(self.position);
+        -relative * share
+    }
+}
+
+// Estación en órbita alrededor de un planeta. Gira sobre su eje Y y la
+// nave se acopla a ella entrando despacio en el radio de acoplamiento.
+struct SpaceStation {
+    position: Vec3,
+    rotation: Vec3,
+    scale: f32,
+    orbit_radius: f32,
+    orbit_speed: f32,
+    orbit_angle: f32,
+    // Planeta alrededor del cual orbita, por tipo
+    parent: CelestialBody,
+    docking_radius: f32,
+    material: Material,
+    noise: FastNoiseLite,
+}
+
+impl SpaceStation {
+    fn new(parent: CelestialBody, orbit_radius: f32, orbit_speed: f32) -> Self {
+        let material = Material::space_station();
+
+        SpaceStation {
+            position: Vec3::new(0.0, 0.0, 0.0),
+            rotation: Vec3::new(0.0, 0.0, 0.0),
+            scale: 0.6,
+            orbit_radius,
+            orbit_speed,
+            orbit_angle: 0.0,
+            parent,
+            docking_radius: 1.2,
+            noise: material.noise.build(),
+            material,
+        }
+    }
+
+    fn update(&mut self, parent_pos: Vec3, time_scale: f32) {
+        self.rotation.y += 0.02 * time_scale;
+        self.orbit_angle += self.orbit_speed * time_scale;
+        // Un poco inclinada respecto del plano de las órbitas
+        self.position = parent_pos + Vec3::new(
+            self.orbit_angle.cos() * self.orbit_radius,
+            self.orbit_angle.sin() * self.orbit_radius * 0.2,
+            self.orbit_angle.sin() * self.orbit_radius,
         );
     }
+
+    // Dónde queda la nave acoplada: encima del núcleo
+    fn docking_port(&self) -> Vec3 {
+        self.position + Vec3::new(0.0, self.scale * 0.7, 0.0)
+    }
 }
 
 struct Planet {
+    name: String,
     position: Vec3,
     rotation: Vec3,
     scale: f32,
     body_type: CelestialBody,
     orbit_radius: f32,
     orbit_speed: f32,
+    // Radianes por cuadro que gira sobre su eje
+    rotation_speed: f32,
+    // Con excentricidad `orbit_radius` es el semieje mayor y `orbit_angle`
+    // la anomalía media; `perihelion` es el ángulo hacia el perihelio
     orbit_angle: f32,
+    eccentricity: f32,
+    perihelion: f32,
     original_scale: f32,
+    // Elementos reales, si el planeta salió de la tabla; permiten cambiar la escala
+    elements: Option<ephemeris::OrbitalElements>,
+    material: Material,
+    noise: FastNoiseLite,
+    trail: trails::Trail,
 }
 
 impl Planet {
@@ -257,11 +677,13 @@ impl Planet {
             CelestialBody::RockyPlanet => 1.5, 
             CelestialBody::OceanPlanet => 1.7,
             CelestialBody::CloudyPlanet => 2.8, 
+            CelestialBody::DesertPlanet => 1.6,
             _ => 1.2,                         
         };
-        
+        let material = Material::for_body(body_type);
 
         Planet {
+            name: body_type.default_name().to_string(),
             position: Vec3::new(orbit_radius, 0.0, 0.0),
             rotation: Vec3::new(0.0, 0.0, 0.0),
             scale,
@@ -270,137 +692,461 @@ impl Planet {
             orbit_radius,
             orbit_speed,
             orbit_angle: 0.0,
+            rotation_speed: 0.01,
+            eccentricity: 0.0,
+            perihelion: 0.0,
+            elements: None,
+            noise: material.noise.build(),
+            material,
+            trail: trails::Trail::new(160, 3),
         }
     }
 
-    fn update(&mut self) {
-        self.rotation.y += 0.01;
-        self.orbit_angle += self.orbit_speed;
-        self.position.x = self.orbit_angle.cos() * self.orbit_radius;
-        self.position.z = self.orbit_angle.sin() * self.orbit_radius;
+    fn with_name(mut self, name: &str) -> Self {
+        self.name = name.to_string();
+        self
     }
+
+    // Planeta real a partir de sus elementos orbitales, en la posición que
+    // tiene `days` días después de J2000
+    fn from_elements(elements: &ephemeris::OrbitalElements, days: f64) -> Self {
+        let orbit_speed = if elements.period_days > 0.0 {
+            std::f32::consts::TAU / elements.period_days * ephemeris::DAYS_PER_FRAME
+        } else {
+            0.0
+        };
+        let mut planet = Planet::new(0.0, elements.body_type, orbit_speed).with_name(&elements.name);
+        planet.eccentricity = elements.eccentricity;
+        planet.perihelion = elements.perihelion_longitude.to_radians();
+        planet.orbit_angle = elements.mean_anomaly(days);
+        planet.rotation.z = elements.axial_tilt.to_radians();
+        planet.elements = Some(elements.clone());
+        planet.apply_scale_mode(ephemeris::ScaleMode::Stylized);
+        planet
+    }
+
+    // Recalcula la órbita y el tamaño con otra escala; mantiene el zoom de
+    // la selección. Sin elementos reales no cambia nada.
+    fn apply_scale_mode(&mut self, mode: ephemeris::ScaleMode) {
+        let Some(elements) = &self.elements else {
+            return;
+        };
+        let zoom = if self.original_scale > 0.0 { self.scale / self.original_scale } else { 1.0 };
+        self.orbit_radius = mode.distance(elements.semi_major_axis);
+        self.original_scale = mode.scale(elements.radius_km);
+        self.scale = self.original_scale * zoom;
+        // Se borra la estela para que no quede un trazo entre las dos órbitas
+        self.trail.clear();
+    }
+
+    fn with_material(mut self, material: Material) -> Self {
+        self.noise = material.noise.build();
+        self.material = material;
+        self
+    }
+
+    // La estela se graba aparte, después de que una luna pesada corra al planeta
+    fn update(&mut self, time_scale: f32) {
+        self.rotation.y += self.rotation_speed * time_scale;
+        self.orbit_angle += self.orbit_speed * time_scale;
+        let anomaly = ephemeris::eccentric_anomaly(self.orbit_angle, self.eccentricity);
+        self.position = ephemeris::orbit_point(self.orbit_radius, self.eccentricity, self.perihelion, anomaly);
+    }
+}
+
+// Elipse con foco en `center`; con excentricidad 0 es el círculo de radio `radius`
+fn draw_orbit(framebuffer: &mut Framebuffer, radius: f32, eccentricity: f32, perihelion: f32, center: Vec3, uniforms: &Uniforms) {
+    orbit_pixels(radius, eccentricity, perihelion, center, uniforms, &mut |x, y, depth| {
+        if x >= 0 && y >= 0 {
+            framebuffer.point(x as usize, y as usize, depth);
+        }
+    });
 }
 
-fn draw_orbit(framebuffer: &mut Framebuffer, radius: f32, uniforms: &Uniforms, depth: f32) {
+// Los píxeles de la elipse en pantalla, con su profundidad
+fn orbit_pixels(radius: f32, eccentricity: f32, perihelion: f32, center: Vec3, uniforms: &Uniforms, plot: &mut impl FnMut(isize, isize, f32)) {
     let segments = 100;
     let mut last_point = None;
     
     for i in 0..=segments {
         let angle = (i as f32 / segments as f32) * 2.0 * PI;
-        let x = angle.cos() * radius;
-        let z = angle.sin() * radius;
-        let point = Vec3::new(x, 0.0, z);
+        let point = center + ephemeris::orbit_point(radius, eccentricity, perihelion, angle);
         
         let world_pos = uniforms.view_matrix * Vec4::new(point.x, point.y, point.z, 1.0);
-        let mut transformed = uniforms.projection_matrix * world_pos;
-        transformed /= transformed.w;
+        let clip = uniforms.projection_matrix * world_pos;
+
+        // Los puntos detrás de la cámara cortan la línea
+        if clip.w <= 0.0 {
+            last_point = None;
+            continue;
+        }
+        let transformed = clip / clip.w;
         
-        let screen_x = ((transformed.x + 1.0) * framebuffer.width as f32 / 2.0) as usize;
-        let screen_y = ((1.0 - transformed.y) * framebuffer.height as f32 / 2.0) as usize;
+        let (screen_x, screen_y) = uniforms.ndc_to_screen(transformed.x, transformed.y);
+        let (screen_x, screen_y) = (screen_x as isize, screen_y as isize);
+        let depth = encode_depth(&clip, uniforms.depth_mode);
         
-        if let Some((last_x, last_y)) = last_point {
-            draw_line(framebuffer, last_x, last_y, screen_x, screen_y, 0.99999);
+        if let Some((last_x, last_y, last_depth)) = last_point {
+            line_pixels((last_x, last_y, last_depth), (screen_x, screen_y, depth), plot);
         }
         
-        last_point = Some((screen_x, screen_y));
+        last_point = Some((screen_x, screen_y, depth));
     }
 }
 
-fn draw_line(framebuffer: &mut Framebuffer, x0: usize, y0: usize, x1: usize, y1: usize, depth: f32) {
-    let mut x0 = x0 as isize;
-    let mut y0 = y0 as isize;
-    let x1 = x1 as isize;
-    let y1 = y1 as isize;
-
-    let dx = (x1 - x0).abs();
-    let dy = -(y1 - y0).abs();
-    let mut err = dx + dy;
-    let mut e2;
+// Framebuffer interno: la medida de la ventana por el supersampling y por la
+// escala de la resolución dinámica
+fn internal_framebuffer(width: usize, height: usize, render_scale: usize, dynamic_scale: f32) -> Framebuffer {
+    let scale = render_scale as f32 * dynamic_scale;
+    let scaled = |size: usize| ((size as f32 * scale).round() as usize).max(1);
+    Framebuffer::new(scaled(width), scaled(height))
+}
 
-    let sx = if x0 < x1 { 1 } else { -1 };
-    let sy = if y0 < y1 { 1 } else { -1 };
+// Ventana normal de `windowed` píxeles, o sin bordes y del tamaño de la
+// pantalla en las opciones (minifb no sabe cuánto mide el monitor)
+fn open_window(windowed: (usize, usize), settings: &settings::Settings) -> Window {
+    let (width, height, options, position) = if settings.fullscreen {
+        let options = WindowOptions { borderless: true, title: false, topmost: true, ..WindowOptions::default() };
+        (settings.screen_width, settings.screen_height, options, (0, 0))
+    } else {
+        (windowed.0, windowed.1, WindowOptions { resize: true, ..WindowOptions::default() }, (200, 100))
+    };
+    let mut window = Window::new("Sistema Solar", width, height, options).unwrap();
+    window.set_position(position.0, position.1);
+    window
+}
 
-    loop {
-        framebuffer.point(x0 as usize, y0 as usize, depth);
+// Mueve la semilla del ruido de un cuerpo según la global y lo rehace
+fn reseed_noise(material: &mut Material, noise: &mut FastNoiseLite, seed: Seed) {
+    material.noise.seed = seed.noise(material.noise.seed);
+    *noise = material.noise.build();
+}
 
-        if x0 == x1 && y0 == y1 { break; }
-        e2 = 2 * err;
-        if e2 >= dy {
-            err += dy;
-            x0 += sx;
-        }
-        if e2 <= dx {
-            err += dx;
-            y0 += sy;
-        }
-    }
+// Cubo que reemplaza a la nave o la estación si no hay modelo, con un
+// degradé horneado en los vértices (oscuro abajo) para que se lea el volumen
+fn placeholder_model(half_size: f32) -> Obj {
+    let mut cube = Obj::cube(half_size);
+    let shade = Color::from_rgb_f32(0.35, 0.35, 0.4);
+    cube.paint(|position, _| shade.lerp(&Color::white(), (position.y / half_size + 1.0) * 0.5));
+    cube
 }
+
 fn main() {
     let window_width = 1200;
     let window_height = 900;
-    let framebuffer_width = 1000;
-    let framebuffer_height = 900;
-    let frame_delay = Duration::from_millis(16);
-
-    let mut framebuffer = Framebuffer::new(framebuffer_width, framebuffer_height);
-    let mut window = Window::new(
-        "Sistema Solar",
-        window_width,
-        window_height,
-        WindowOptions::default(),
-    )
-    .unwrap();
-
-    window.set_position(200, 100);
-    framebuffer.set_background_color(0x000015);
+    // Siguen al tamaño de la ventana (ver el inicio del ciclo principal)
+    let mut framebuffer_width = window_width;
+    let mut framebuffer_height = window_height;
+    let background_color = 0x000015;
+
+    // Opciones del menú (Esc), guardadas entre ejecuciones
+    let mut settings = settings::Settings::load("settings.txt").unwrap_or_else(|err| {
+        eprintln!("No se pudieron leer las opciones: {}", err);
+        settings::Settings::new("settings.txt")
+    });
+    let mut menu = settings::SettingsMenu::new();
+    let mut debug_ui = debug_ui::DebugUi::new();
+    let mut limiter = pacing::FrameLimiter::new(settings.target_fps);
+    let mut resolution = pacing::DynamicResolution::new();
+    let mut static_layers = redraw::StaticLayers::new();
+    let mut render_context = RenderContext::new(settings.depth_prepass, settings.deferred);
+    // Con `--perfil archivo.csv` cada cuadro deja sus tiempos por etapa
+    let args: Vec<String> = std::env::args().collect();
+    // Todo el azar sale de esta semilla: `--semilla <n>` o la del archivo de opciones
+    let seed = seed::from_args(&args).unwrap_or_else(|err| {
+        eprintln!("No se pudo leer la semilla: {}", err);
+        None
+    }).unwrap_or(Seed(settings.seed));
+    if let Some(filename) = args.iter().position(|arg| arg == "--perfil").and_then(|i| args.get(i + 1)) {
+        if let Err(err) = render_context.profiler.record_to(filename) {
+            eprintln!("No se pudo crear el perfil: {}", err);
+        }
+    }
+
+    // Escala interna de render: con 2 se renderiza al doble y se promedia (SSAA)
+    let mut render_scale = settings.render_scale;
+    let mut present_buffer = vec![0u32; framebuffer_width * framebuffer_height];
+
+    let mut framebuffer = internal_framebuffer(framebuffer_width, framebuffer_height, render_scale, resolution.scale());
+    let mut window = open_window((window_width, window_height), &settings);
+    let mut fullscreen = settings.fullscreen;
 
     let mut camera = Camera::new(
         Vec3::new(0.0, 15.0, 30.0),
         Vec3::new(0.0, 0.0, 0.0),
         Vec3::new(0.0, 1.0, 0.0)
     );
+    camera.set_rotation_speed(camera::DEFAULT_ROTATION_SPEED * settings.sensitivity);
 
     // Carga los modelos 3D
-    let obj = Obj::load("assets/sphere.obj").expect("Failed to load obj");
-    let spacecraft_obj = Obj::load("assets/nave.obj").expect("Failed to load spacecraft");
-    let vertex_arrays = obj.get_vertex_array();
-    let spacecraft_vertex_arrays = spacecraft_obj.get_vertex_array();
+    let mut assets = assets::Assets::new();
+    let sphere_mesh = assets.load_mesh("assets/sphere.obj", obj::EMBEDDED_SPHERE, || Obj::sphere(24, 48));
+    let spacecraft_mesh = assets.load_mesh("assets/nave.obj", obj::EMBEDDED_SPACESHIP, || placeholder_model(4.0));
+    let station_mesh = assets.load_mesh("assets/estacion.obj", obj::EMBEDDED_STATION, || placeholder_model(1.0));
     
     // Inicializa la nave
     let mut spacecraft = Spacecraft::new();
+    // Las misiones que piden volver se refieren al punto de partida
+    let home = spacecraft.position;
+    let mut mission_tracker = missions::MissionTracker::new(
+        missions::load("assets/missions.txt").unwrap_or_else(|err| {
+            eprintln!("No se pudieron cargar las misiones: {}", err);
+            Vec::new()
+        }),
+    );
     
-    let mut planets = vec![
+    // Con `--real` el sistema sale de la tabla de elementos orbitales, en
+    // las posiciones de hoy; si no, el sistema estilizado de siempre
+    let real_system = std::env::args().any(|arg| arg == "--real");
+    let real_planets: Option<Vec<Planet>> = if real_system {
+        match ephemeris::load("assets/planets.csv") {
+            Ok(table) => {
+                let days = ephemeris::days_since_j2000(std::time::SystemTime::now());
+                Some(table.iter().map(|elements| Planet::from_elements(elements, days)).collect())
+            }
+            Err(err) => {
+                eprintln!("No se pudo cargar el sistema real: {}", err);
+                None
+            }
+        }
+    } else {
+        None
+    };
+    let mut planets = real_planets.unwrap_or_else(|| vec![
         Planet::new(0.0, CelestialBody::Sun, 0.0),        
         Planet::new(5.0, CelestialBody::RockyPlanet, 0.03), 
         Planet::new(7.0, CelestialBody::ColorPlanet, 0.025), 
         Planet::new(9.0, CelestialBody::CloudyPlanet, 0.02), 
-        Planet::new(11.0, CelestialBody::RockyPlanet, 0.018), 
+        Planet::new(11.0, CelestialBody::RockyPlanet, 0.018)
+            .with_material(Material {
+                map_color: (190, 95, 60),
+                ..Material::for_body(CelestialBody::RockyPlanet)
+                    .with_noise(NoiseConfig { seed: 77, ..NoiseConfig::for_body(CelestialBody::RockyPlanet) })
+                    .with_tint(Color::new(255, 170, 140))
+            })
+            .with_name("Oxido"), 
         Planet::new(14.0, CelestialBody::GasGiant, 0.012),    
         Planet::new(18.0, CelestialBody::RingedPlanet, 0.009),
         Planet::new(21.0, CelestialBody::IcePlanet, 0.007),    
         Planet::new(24.0, CelestialBody::NaturePlanet, 0.005),    
         Planet::new(26.0, CelestialBody::AuroraPlanet, 0.015),    
         Planet::new(30.0, CelestialBody::OceanPlanet, 0.010),  
-    ];
-    let mut moon = Moon::new(1.5, 0.05);
-    let skybox = Skybox::new(4000, 100.0); 
+        Planet::new(3.2, CelestialBody::LavaPlanet, 0.04),
+        Planet::new(34.0, CelestialBody::DesertPlanet, 0.006),
+    ]);
+    let mut moon = Moon::new(1.5, 0.05).with_mass_ratio(0.15);
+    let mut station = SpaceStation::new(CelestialBody::GasGiant, 4.5, 0.01);
+    for planet in &mut planets {
+        reseed_noise(&mut planet.material, &mut planet.noise, seed);
+    }
+    reseed_noise(&mut moon.material, &mut moon.noise, seed);
+    reseed_noise(&mut station.material, &mut station.noise, seed);
+
+    // Satélites en órbitas bajas e inclinadas de algunos planetas
+    let mut satellites = satellites::SatelliteSystem::new();
+    for (i, planet) in planets.iter().enumerate() {
+        let count = match planet.body_type {
+            CelestialBody::CloudyPlanet => 3,
+            CelestialBody::GasGiant | CelestialBody::OceanPlanet => 2,
+            _ => 0,
+        };
+        let low_orbit = planet.scale * BODY_RADIUS * 1.3;
+        for k in 0..count {
+            let k = k as f32;
+            satellites.add(i, low_orbit + k * 0.08, 0.4 + k * 0.5, k * 2.1, 0.05 - k * 0.01);
+        }
+    }
+    let constellations = constellation::load("assets/constellations.txt").unwrap_or_else(|err| {
+        eprintln!("No se pudieron cargar las constelaciones: {}", err);
+        Vec::new()
+    });
+    let mut skybox = Skybox::new(settings.star_count, 100.0, constellations, seed);
+    // 0 es el cielo procedural; los demás son imágenes de assets/skybox
+    let sky_backgrounds = sky::find_backgrounds("assets/skybox");
+    let mut sky_background_index = 0;
+    // Fondo elegido con K, para ponerlo de nuevo si se recarga
+    let mut sky_texture: Option<assets::TextureId> = None;
+    let mut meteor_shower = meteors::MeteorShower::new(100.0, seed.rng(Stream::Meteors));
+    let mut last_mouse: Option<(f32, f32)> = None;
+    let mut exhaust = particles::ParticleSystem::new(2000);
+    let mut exhaust_rng = seed.rng(Stream::Exhaust);
+    let mut camera_mode = CameraMode::Orbit;
+    let mut free_fly_speed = FREE_FLY_SPEEDS[1];
+    let mut tour: Option<tour::Tour> = None;
+    let mut bookmarks = bookmarks::Bookmarks::load("camera_bookmarks.txt").unwrap_or_else(|err| {
+        eprintln!("No se pudieron leer los marcadores: {}", err);
+        bookmarks::Bookmarks::new("camera_bookmarks.txt")
+    });
+    // Transición animada hacia un marcador: (tween, destino)
+    let mut transition: Option<(tween::CameraTween, tween::CameraPose)> = None;
+    let mut camera_path = camera_path::CameraPath::new();
+    let mut path_playback: Option<camera_path::PathPlayback> = None;
+    // Duración de la reproducción del camino, en cuadros
+    let mut path_duration = 600u32;
+    let mut show_constellations = false;
+    let mut show_help = false;
+    let mut show_inset = true;
+    let mut show_lagrange = false;
+    let mut show_minimap = false;
+    let mut split_screen = false;
+    let scene_noise = NoiseConfig::default().build();
+    // Lo que no es de ningún objeto (la interfaz, los puntos) usa el del sol
+    let scene_material = Material::for_body(CelestialBody::Sun);
+    let ship_material = Material::spaceship();
     let mut time = 0u32;
+    // El sol está en el origen y es la única fuente de luz
+    let light_position = Vec3::new(0.0, 0.0, 0.0);
+    let mut light_intensity = 1.0;
+    let mut depth_mode = DepthMode::Logarithmic;
+    let mut debug_view = DebugView::Off;
+    let mut show_overdraw = false;
+    let mut post_chain = PostChain::default();
+    settings.apply_post(&mut post_chain);
+    let mut linear_workflow = true;
     let mut selected_planet: Option<usize> = None;
+    let mut follow_planet = false;
+    let mut followed_planet: Option<usize> = None;
     let zoom_scale = 3.0; 
     let moon_zoom_scale = 2.0;
+    // Escala de distancias del sistema real, y cuánto se achica la luna con ella
+    let mut scale_mode = ephemeris::ScaleMode::Stylized;
+    let mut moon_size = 1.0;
+    let mut units = if planets.iter().any(|planet| planet.elements.is_some()) {
+        units::Units::for_scale(scale_mode)
+    } else {
+        units::Units::stylized()
+    };
+    // Para la velocidad de la cámara, que no guarda la suya
+    let mut last_eye = camera.eye;
+    let mut hover = picking::Hover::new();
+
+    while window.is_open() {
+        render_context.profiler.begin_frame();
+
+        // Modelos y fondos que cambiaron en disco
+        let reloaded = assets.reload_changed();
+        if let Some(id) = sky_texture.filter(|id| reloaded.textures.contains(id)) {
+            skybox.set_custom_background(Some(assets.texture(id)));
+            static_layers.invalidate();
+        }
+
+        // F11 (o el menú) pasa de ventana a pantalla completa: se abre otra
+        // ventana y el framebuffer se ajusta abajo con el tamaño nuevo
+        if keybindings::pressed(&window, Action::Fullscreen) {
+            settings.fullscreen = !settings.fullscreen;
+            if let Err(err) = settings.save() {
+                eprintln!("No se pudieron guardar las opciones: {}", err);
+            }
+        }
+        if settings.fullscreen != fullscreen {
+            fullscreen = settings.fullscreen;
+            window = open_window((window_width, window_height), &settings);
+            last_mouse = None;
+        }
+
+        // Si la ventana cambió de tamaño se rehace el framebuffer a la medida
+        // nueva; minimizada mide 0 y se deja como estaba
+        let (width, height) = window.get_size();
+        if width > 0 && height > 0 && (width, height) != (framebuffer_width, framebuffer_height) {
+            framebuffer_width = width;
+            framebuffer_height = height;
+            framebuffer = internal_framebuffer(framebuffer_width, framebuffer_height, render_scale, resolution.scale());
+            present_buffer = vec![0u32; framebuffer_width * framebuffer_height];
+        }
+
+        // Esc abre o cierra el menú de opciones; mientras está abierto el
+        // tiempo se detiene y las flechas y Enter son del menú
+        if keybindings::pressed(&window, Action::Menu) {
+            menu.open = !menu.open;
+        }
+        if menu.open {
+            match menu.handle(&window, &mut settings) {
+                settings::MenuEvent::Quit => break,
+                settings::MenuEvent::Changed => {
+                    if !settings.dynamic_resolution {
+                        resolution.reset();
+                    }
+                    if settings.render_scale != render_scale || !settings.dynamic_resolution {
+                        render_scale = settings.render_scale;
+                        framebuffer = internal_framebuffer(framebuffer_width, framebuffer_height, render_scale, resolution.scale());
+                    }
+                    settings.apply_post(&mut post_chain);
+                    skybox.set_star_count(settings.star_count);
+                    render_context.depth_prepass = settings.depth_prepass;
+                    render_context.deferred = settings.deferred;
+                    camera.set_rotation_speed(camera::DEFAULT_ROTATION_SPEED * settings.sensitivity);
+                    if settings.target_fps != limiter.target() {
+                        limiter.set_target(settings.target_fps);
+                    }
+                    if let Err(err) = settings.save() {
+                        eprintln!("No se pudieron guardar las opciones: {}", err);
+                    }
+                }
+                settings::MenuEvent::None => {}
+            }
+        }
+        let time_scale = if menu.open { 0.0 } else { settings.time_scale };
+
+        // Ctrl+1..5 guarda la pose de la cámara; Alt+1..5 vuela hasta ella
+        // y Alt+Shift+1..5 salta directo
+        let ctrl = window.is_key_down(Key::LeftCtrl) || window.is_key_down(Key::RightCtrl);
+        let alt = window.is_key_down(Key::LeftAlt) || window.is_key_down(Key::RightAlt);
+        let shift = window.is_key_down(Key::LeftShift) || window.is_key_down(Key::RightShift);
+        for (slot, key) in [Key::Key1, Key::Key2, Key::Key3, Key::Key4, Key::Key5].iter().enumerate() {
+            if !window.is_key_pressed(*key, minifb::KeyRepeat::No) {
+                continue;
+            }
+            if ctrl {
+                bookmarks.set(slot, tween::CameraPose::from_camera(&camera));
+                if let Err(err) = bookmarks.save() {
+                    eprintln!("No se pudieron guardar los marcadores: {}", err);
+                }
+            } else if alt {
+                if let Some(pose) = bookmarks.get(slot) {
+                    camera_mode = CameraMode::Orbit;
+                    spacecraft.flying = false;
+                    spacecraft.landing = None;
+                    spacecraft.warp = None;
+                    spacecraft.docked = false;
+                    tour = None;
+                    path_playback = None;
+                    if shift {
+                        pose.apply(&mut camera);
+                        camera.sync_orbit_angles();
+                        transition = None;
+                    } else {
+                        let from = tween::CameraPose::from_camera(&camera);
+                        transition = Some((tween::CameraTween::new(from, 60, tween::Easing::EaseInOut), pose));
+                    }
+                }
+            }
+        }
+
+        // En vuelo libre 1, 2 y 3 eligen la velocidad en vez de un planeta
+        let free_fly = camera_mode == CameraMode::FreeFly;
+        if free_fly {
+            for (preset, key) in [Key::Key1, Key::Key2, Key::Key3].iter().enumerate() {
+                if !ctrl && !alt && window.is_key_pressed(*key, minifb::KeyRepeat::No) {
+                    free_fly_speed = FREE_FLY_SPEEDS[preset];
+                }
+            }
+        }
 
-    while window.is_open() && !window.is_key_down(Key::Escape) {
         // Manejo de selección de planetas
         for (i, key) in [Key::Key1, Key::Key2, Key::Key3, Key::Key4, Key::Key5, 
                          Key::Key6, Key::Key7, Key::Key8, Key::Key9]
                          .iter()
                          .enumerate() {
-            if window.is_key_pressed(*key, minifb::KeyRepeat::No) {
+            // Con una tabla de `--real` puede haber menos de nueve cuerpos
+            if i >= planets.len() || (free_fly && i < FREE_FLY_SPEEDS.len()) {
+                continue;
+            }
+            if !ctrl && !alt && window.is_key_pressed(*key, minifb::KeyRepeat::No) {
                 if Some(i) == selected_planet {
                     selected_planet = None;
                     planets[i].scale = planets[i].original_scale;
-                    moon.scale = 1.2;            
-                    moon.orbit_radius = 1.5;      
+                    moon.scale = 1.2 * moon_size;            
+                    moon.orbit_radius = 1.5 * moon_size;      
                 } else {
                     if let Some(prev) = selected_planet {
                         planets[prev].scale = planets[prev].original_scale;
@@ -410,194 +1156,1008 @@ fn main() {
                     planets[i].scale = planets[i].original_scale * zoom_scale;
     
                     if matches!(planets[i].body_type, CelestialBody::CloudyPlanet) {
-                        moon.scale = 1.2 * moon_zoom_scale;       
-                        moon.orbit_radius = 1.5 * moon_zoom_scale; 
+                        moon.scale = 1.2 * moon_size * moon_zoom_scale;       
+                        moon.orbit_radius = 1.5 * moon_size * moon_zoom_scale; 
                     } else {
-                        moon.scale = 1.2;             
-                        moon.orbit_radius = 1.5;      
+                        moon.scale = 1.2 * moon_size;             
+                        moon.orbit_radius = 1.5 * moon_size;      
                     }
                 }
             }
         }
     
-        time += 1;
-        handle_input(&window, &mut camera);
+        // L alterna entre profundidad estándar y logarítmica
+        if keybindings::pressed(&window, Action::DepthMode) {
+            depth_mode = match depth_mode {
+                DepthMode::Standard => DepthMode::Logarithmic,
+                DepthMode::Logarithmic => DepthMode::Standard,
+            };
+        }
+
+        // F10 pasa por las vistas de depuración del shader
+        if keybindings::pressed(&window, Action::DebugView) {
+            debug_view = debug_view.next();
+        }
+
+        // Tab muestra los tiempos de cada etapa del cuadro
+        if keybindings::pressed(&window, Action::Profiler) {
+            render_context.profiler.visible = !render_context.profiler.visible;
+        }
+
+        // F12 muestra cuántas veces se escribió cada píxel
+        if keybindings::pressed(&window, Action::Overdraw) {
+            show_overdraw = !show_overdraw;
+        }
+
+        // F3 cambia el operador de tonemapping
+        if keybindings::pressed(&window, Action::Tonemapping) {
+            if let Some(tonemap) = post_chain.get_mut::<ToneMapping>() {
+                tonemap.operator = tonemap.operator.next();
+            }
+        }
+
+        // F4 compara el flujo lineal (gamma correcto) contra el antiguo en sRGB
+        if keybindings::pressed(&window, Action::LinearWorkflow) {
+            linear_workflow = !linear_workflow;
+        }
+
+        // F5 activa o desactiva el bloom
+        if keybindings::pressed(&window, Action::Bloom) {
+            settings.bloom = !settings.bloom;
+            post_chain.set_enabled("bloom", settings.bloom);
+        }
+
+        // F6 activa los efectos de cabina (viñeta y aberración cromática)
+        if keybindings::pressed(&window, Action::CockpitEffects) {
+            settings.cockpit_effects = !settings.cockpit_effects;
+            settings.apply_post(&mut post_chain);
+        }
+
+        // F7 activa o desactiva el dithering de la salida
+        if keybindings::pressed(&window, Action::Dither) {
+            if let Some(tonemap) = post_chain.get_mut::<ToneMapping>() {
+                tonemap.dither = !tonemap.dither;
+            }
+        }
+
+        // F8 activa el FXAA y F9 el grano de película
+        if keybindings::pressed(&window, Action::Fxaa) {
+            settings.fxaa = !settings.fxaa;
+            post_chain.set_enabled("fxaa", settings.fxaa);
+        }
+        if keybindings::pressed(&window, Action::Grain) {
+            settings.grain = !settings.grain;
+            post_chain.set_enabled("grain", settings.grain);
+        }
+
+        // B alterna los choques de la nave entre rebote y deslizamiento
+        if keybindings::pressed(&window, Action::Bounce) {
+            spacecraft.restitution = if spacecraft.restitution > 0.0 { 0.0 } else { 0.5 };
+        }
+
+        // Y salta hacia el planeta seleccionado mientras se vuela; otra vez lo corta
+        if keybindings::pressed(&window, Action::Warp) && spacecraft.flying && spacecraft.warp.take().is_none() {
+            if let Some(i) = selected_planet {
+                spacecraft.start_warp(i);
+            }
+        }
+
+        // J muestra u oculta las trazas de las órbitas de los satélites
+        if keybindings::pressed(&window, Action::SatelliteTrails) {
+            satellites.show_trails = !satellites.show_trails;
+        }
+
+        // R cambia la escala del sistema real: estilizada, logarítmica o real
+        if keybindings::pressed(&window, Action::ScaleMode) && planets.iter().any(|planet| planet.elements.is_some()) {
+            scale_mode = scale_mode.next();
+            units = units::Units::for_scale(scale_mode);
+            for planet in planets.iter_mut() {
+                planet.apply_scale_mode(scale_mode);
+            }
+
+            // La luna y su órbita siguen a la Tierra
+            if let Some(earth) = planets.iter().find(|planet| matches!(planet.body_type, CelestialBody::CloudyPlanet)) {
+                if let Some(elements) = &earth.elements {
+                    moon_size = scale_mode.scale(elements.radius_km) / ephemeris::ScaleMode::Stylized.scale(elements.radius_km);
+                }
+            }
+            let earth_selected = selected_planet.is_some_and(|i| matches!(planets[i].body_type, CelestialBody::CloudyPlanet));
+            let zoom = if earth_selected { moon_zoom_scale } else { 1.0 };
+            moon.scale = 1.2 * moon_size * zoom;
+            moon.orbit_radius = 1.5 * moon_size * zoom;
+            moon.trail.clear();
+
+            // La cámara de órbita se aleja o se acerca para encuadrar el sistema entero
+            if camera_mode == CameraMode::Orbit && tour.is_none() {
+                let extent = planets.iter()
+                    .map(|planet| planet.orbit_radius * (1.0 + planet.eccentricity))
+                    .fold(1.0, f32::max);
+                let direction = (camera.eye - camera.center).try_normalize(1e-6).unwrap_or(Vec3::new(0.0, 0.5, -1.0).normalize());
+                let from = tween::CameraPose::from_camera(&camera);
+                let pose = tween::CameraPose { eye: direction * extent * 1.3, center: Vec3::new(0.0, 0.0, 0.0), up: camera.up };
+                transition = Some((tween::CameraTween::new(from, 90, tween::Easing::EaseInOut), pose));
+                path_playback = None;
+            }
+        }
+
+        // H muestra u oculta la ayuda con las teclas
+        if keybindings::pressed(&window, Action::Help) {
+            show_help = !show_help;
+        }
+
+        // F1 abre el panel de depuración con los parámetros en vivo
+        if keybindings::pressed(&window, Action::DebugUi) {
+            debug_ui.open = !debug_ui.open;
+        }
+
+        // U muestra u oculta los puntos de Lagrange (solo los del planeta
+        // seleccionado, si hay uno)
+        if keybindings::pressed(&window, Action::Lagrange) {
+            show_lagrange = !show_lagrange;
+        }
+
+        // G engancha la cámara al planeta seleccionado para acompañarlo en su órbita
+        if keybindings::pressed(&window, Action::FollowPlanet) {
+            follow_planet = !follow_planet;
+        }
+
+        // V divide la pantalla: vista principal a la izquierda y la nave a la derecha
+        if keybindings::pressed(&window, Action::SplitScreen) {
+            split_screen = !split_screen;
+        }
+
+        // M muestra u oculta el minimapa
+        if keybindings::pressed(&window, Action::Minimap) {
+            show_minimap = !show_minimap;
+        }
+
+        // I muestra u oculta el primer plano del planeta seleccionado
+        if keybindings::pressed(&window, Action::Inset) {
+            show_inset = !show_inset;
+        }
+
+        // N muestra u oculta las constelaciones
+        if keybindings::pressed(&window, Action::Constellations) {
+            show_constellations = !show_constellations;
+        }
+
+        // K pasa al siguiente fondo: procedural o imágenes de assets/skybox
+        if keybindings::pressed(&window, Action::NextSky) {
+            sky_background_index = (sky_background_index + 1) % (sky_backgrounds.len() + 1);
+            sky_texture = None;
+            if sky_background_index > 0 {
+                let path = &sky_backgrounds[sky_background_index - 1];
+                match assets.load_texture(path) {
+                    Ok(id) => sky_texture = Some(id),
+                    Err(err) => eprintln!("No se pudo cargar el fondo {}: {}", path, err),
+                }
+            }
+            skybox.set_custom_background(sky_texture.map(|id| assets.texture(id)));
+        }
+
+        // F2 alterna el supersampling 2x
+        if keybindings::pressed(&window, Action::Supersampling) {
+            render_scale = if render_scale == 1 { 2 } else { 1 };
+            settings.render_scale = render_scale;
+            framebuffer = internal_framebuffer(framebuffer_width, framebuffer_height, render_scale, resolution.scale());
+        }
+
+        // C cambia el modo de cámara; F entra o sale del vuelo con la cámara de persecución
+        let previous_mode = camera_mode;
+        if keybindings::pressed(&window, Action::NextCameraMode) {
+            camera_mode = camera_mode.next();
+        }
+        if keybindings::pressed(&window, Action::ToggleFlight) {
+            camera_mode = if camera_mode == CameraMode::Orbit { CameraMode::Chase } else { CameraMode::Orbit };
+        }
+        // T inicia o corta el recorrido automático por los planetas
+        if keybindings::pressed(&window, Action::Tour) {
+            if tour.take().is_none() {
+                camera_mode = CameraMode::Orbit;
+                // Del más cercano al sol al más lejano, sin el sol
+                let mut route: Vec<usize> = (0..planets.len())
+                    .filter(|&i| !matches!(planets[i].body_type, CelestialBody::Sun))
+                    .collect();
+                route.sort_by(|&a, &b| planets[a].orbit_radius.total_cmp(&planets[b].orbit_radius));
+                tour = Some(tour::Tour::new(&camera, route));
+                transition = None;
+                path_playback = None;
+            } else {
+                camera.sync_orbit_angles();
+            }
+        }
+        // P agrega la pose actual al camino de cámara (Shift+P lo borra), O lo
+        // reproduce o lo detiene y [ / ] cambian su duración
+        if keybindings::pressed(&window, Action::PathKey) {
+            if shift {
+                camera_path.clear();
+                path_playback = None;
+            } else {
+                camera_path.add(tween::CameraPose::from_camera(&camera));
+            }
+        }
+        if keybindings::pressed(&window, Action::PathPlay) {
+            if path_playback.take().is_some() {
+                camera.sync_orbit_angles();
+            } else if camera_path.len() >= 2 {
+                camera_mode = CameraMode::Orbit;
+                tour = None;
+                transition = None;
+                path_playback = Some(camera_path::PathPlayback::new(path_duration));
+            }
+        }
+        if keybindings::pressed(&window, Action::PathShorter) {
+            path_duration = path_duration.saturating_sub(120).max(120);
+        }
+        if keybindings::pressed(&window, Action::PathLonger) {
+            path_duration += 120;
+        }
+
+        if camera_mode != previous_mode {
+            // El recorrido y las transiciones solo corren en la órbita
+            if camera_mode != CameraMode::Orbit {
+                tour = None;
+                transition = None;
+                path_playback = None;
+            }
+            // Fuera de la órbita libre la nave se pilota
+            spacecraft.flying = matches!(camera_mode, CameraMode::Chase | CameraMode::Cockpit);
+            if !spacecraft.flying {
+                spacecraft.landing = None;
+                spacecraft.warp = None;
+                spacecraft.docked = false;
+            }
+            if camera_mode == CameraMode::Orbit {
+                camera.up = Vec3::new(0.0, 1.0, 0.0);
+                camera.sync_orbit_angles();
+            }
+            camera.has_changed = true;
+        }
+
+        // Arrastrar con el botón izquierdo también orienta la nave
+        let mouse = window.get_mouse_pos(MouseMode::Pass);
+        let mouse_delta = match (mouse, last_mouse) {
+            (Some(now), Some(before)) if window.get_mouse_down(MouseButton::Left) && !menu.open && !debug_ui.wants_mouse() => (
+                (now.0 - before.0) * settings.sensitivity,
+                (now.1 - before.1) * settings.sensitivity,
+            ),
+            _ => (0.0, 0.0),
+        };
+        last_mouse = mouse;
+
+        if !menu.open {
+            time += 1;
+        }
+
+        // Mueve los cuerpos antes que la cámara, así la cámara que sigue a un
+        // planeta ya lo ve en la posición de este cuadro
+        let mut earth_position = Vec3::new(0.0, 0.0, 0.0);
+        let mut station_parent_position = Vec3::new(0.0, 0.0, 0.0);
+        for planet in planets.iter_mut() {
+            planet.update(time_scale);
+            
+            if matches!(planet.body_type, CelestialBody::CloudyPlanet) {
+                earth_position = planet.position;
+            }
+            if planet.body_type == station.parent {
+                station_parent_position = planet.position;
+            }
+        }
+        // La Tierra y la luna giran alrededor del baricentro, que está sobre la órbita
+        let earth_shift = moon.update(earth_position, time_scale);
+        for planet in planets.iter_mut() {
+            if matches!(planet.body_type, CelestialBody::CloudyPlanet) {
+                planet.position += earth_shift;
+            }
+            planet.trail.record(planet.position);
+        }
+        station.update(station_parent_position, time_scale);
+        let planet_positions: Vec<Vec3> = planets.iter().map(|planet| planet.position).collect();
+        satellites.update(&planet_positions, time_scale);
+
+        let surfaces: Vec<Surface> = planets.iter()
+            .map(|planet| Surface {
+                center: planet.position,
+                radius: planet.scale * SURFACE_RADIUS,
+                spin: planet.rotation.y,
+                landable: !matches!(planet.body_type, CelestialBody::Sun),
+            })
+            .chain(std::iter::once(Surface {
+                center: moon.position,
+                radius: moon.scale * SURFACE_RADIUS,
+                spin: moon.rotation.y,
+                landable: true,
+            }))
+            .collect();
+
+        // Esferas de los cuerpos en una grilla: las de choque, del radio del
+        // suelo (el mismo donde se posa la nave), y las de la vista, con la
+        // atmósfera, para descartar lo que queda fuera. Los índices son los
+        // de `planets` y, al final, la luna.
+        let body_grid = |radius: f32| spatial::SpatialGrid::new(
+            8.0,
+            planets.iter()
+                .map(|planet| (planet.position, planet.scale))
+                .chain(std::iter::once((moon.position, moon.scale)))
+                .map(|(center, scale)| spatial::Bounds { center, radius: scale * radius })
+                .collect(),
+        );
+        let (body_grid, view_grid) = (body_grid(SURFACE_RADIUS), body_grid(BODY_RADIUS));
+
+        let flight_input = if menu.open { FlightInput::default() } else { read_flight_input(&window, mouse_delta) };
+        match camera_mode {
+            // En la órbita, el recorrido, las transiciones y la reproducción
+            // de caminos toman la cámara mientras duran
+            CameraMode::Orbit => {
+                if let Some(active) = tour.as_mut() {
+                    let targets: Vec<tour::TourTarget> = planets.iter()
+                        .map(|planet| tour::TourTarget { position: planet.position, radius: planet.scale })
+                        .collect();
+                    if !active.update(&mut camera, &targets) {
+                        tour = None;
+                        camera.sync_orbit_angles();
+                    }
+                } else if let Some((tween, target)) = transition.as_mut() {
+                    tween.step(target).apply(&mut camera);
+                    if tween.is_finished() {
+                        transition = None;
+                        camera.sync_orbit_angles();
+                    }
+                } else if let Some(playback) = path_playback.as_mut() {
+                    match playback.step(&camera_path) {
+                        Some(pose) => pose.apply(&mut camera),
+                        None => {
+                            path_playback = None;
+                            camera.sync_orbit_angles();
+                        }
+                    }
+                } else if !menu.open {
+                    handle_input(&window, &mut camera);
+                }
+            }
+            CameraMode::Chase => {
+                spacecraft.fly(&flight_input, &body_grid, &surfaces, &station);
+                if spacecraft.landing.is_some() {
+                    spacecraft.surface_camera(&mut camera);
+                } else {
+                    spacecraft.chase(&mut camera);
+                }
+            }
+            CameraMode::Cockpit => {
+                spacecraft.fly(&flight_input, &body_grid, &surfaces, &station);
+                spacecraft.cockpit(&mut camera);
+            }
+            CameraMode::FreeFly if !menu.open => handle_free_fly(&window, &mut camera, free_fly_speed, mouse_delta),
+            CameraMode::FreeFly => {}
+        }
+
+        // Con el seguimiento activo el centro de la órbita va pegado al planeta
+        // seleccionado y la cámara se desplaza con él
+        let follow_target = match camera_mode {
+            CameraMode::Orbit if follow_planet && tour.is_none() && transition.is_none() && path_playback.is_none() => {
+                selected_planet
+            }
+            _ => None,
+        };
+        match follow_target {
+            Some(i) if followed_planet == Some(i) => camera.translate(planets[i].position - camera.center),
+            Some(i) => {
+                camera.center = planets[i].position;
+                camera.sync_orbit_angles();
+                camera.has_changed = true;
+            }
+            None => {}
+        }
+        followed_planet = follow_target;
+
+        // La cámara no atraviesa los cuerpos: se mantiene fuera de su radio
+        // visible más un margen para que el plano cercano no los corte
+        if camera_mode != CameraMode::Cockpit {
+            let margin = NEAR_PLANE * 2.5;
+            for planet in &planets {
+                camera.push_out_of_sphere(planet.position, planet.scale * BODY_RADIUS + margin);
+            }
+            camera.push_out_of_sphere(moon.position, moon.scale * BODY_RADIUS + margin);
+        }
+
+        let background = Color::from_hex(background_color);
+        framebuffer.set_background_color(if linear_workflow { background.to_linear() } else { background });
+        framebuffer.set_overdraw_counting(show_overdraw);
         framebuffer.clear();
 
         // Actualiza la nave y verifica colisiones
-        spacecraft.update(&camera);
-        if spacecraft.check_collisions(&planets, &moon) {
+        // En vuelo los choques se resuelven al mover la nave, en `fly`
+        if !spacecraft.flying {
+            spacecraft.update(&camera);
+        }
+        if !spacecraft.flying && spacecraft.check_collisions(&body_grid) {
             spacecraft.position -= spacecraft.velocity;
             spacecraft.velocity = Vec3::new(0.0, 0.0, 0.0);
         }
 
-        let view_matrix = create_view_matrix(camera.eye, camera.center, camera.up);
-        let projection_matrix = create_perspective_matrix(window_width as f32, window_height as f32);
-        let viewport_matrix = create_viewport_matrix(framebuffer_width as f32, framebuffer_height as f32);
+        let view_matrix = match camera_mode {
+            CameraMode::Cockpit => spacecraft.cockpit_view_matrix(),
+            _ => create_view_matrix(camera.eye, camera.center, camera.up),
+        };
+        let projection_matrix = create_perspective_matrix(framebuffer.width as f32, framebuffer.height as f32);
+        let viewport_matrix = create_viewport_matrix(&framebuffer.viewport());
+
+        // El sol, el foco de la nave en vuelo y los reflectores de la estación
+        let mut lights = vec![lighting::Light::point(light_position, Color::from_hex(0xFFFFFF), light_intensity, 0.0)];
+        if spacecraft.flying {
+            lights.push(lighting::Light::spot(
+                spacecraft.position + spacecraft.forward * 0.3,
+                spacecraft.forward,
+                Color::new(255, 240, 210),
+                1.5,
+                40.0,
+                0.92,
+            ));
+        }
+        for side in [-1.0, 1.0] {
+            lights.push(lighting::Light::point(
+                station.position + Vec3::new(side * station.scale * 1.5, station.scale, 0.0),
+                Color::new(200, 220, 255),
+                0.8,
+                station.scale * 6.0,
+            ));
+        }
 
-        // 1. Renderiza el skybox primero
-        skybox.render(&mut framebuffer, &Uniforms {
+        let frame_uniforms = Uniforms {
             model_matrix: Mat4::identity(),
             view_matrix,
             projection_matrix,
             viewport_matrix,
             time,
-            noise: create_noise(),
-            current_body: CelestialBody::Sun, 
-        });
+            noise: &scene_noise,
+            material: &scene_material,
+            light_position,
+            lights: &lights,
+            pbr_materials: settings.pbr,
+            camera_position: camera.eye,
+            depth_mode,
+            linear_workflow,
+            debug_view,
+        };
 
-        // 2. Renderiza las órbitas de los planetas
-        for planet in &planets {
-            if planet.orbit_radius > 0.0 {
-                let uniforms = Uniforms {
-                    model_matrix: Mat4::identity(),
-                    view_matrix,
-                    projection_matrix,
-                    viewport_matrix,
-                    time,
-                    noise: create_noise(),
-                    current_body: planet.body_type,
-                };
-                
-                framebuffer.set_current_color(0x404040);
-                draw_orbit(&mut framebuffer, planet.orbit_radius, &uniforms, 0.9999);
-            }
-        }
-
-        // 3. Actualiza y renderiza planetas
-        let mut earth_position = Vec3::new(0.0, 0.0, 0.0);
-        for planet in planets.iter_mut() {
-            planet.update();
-            
-            if matches!(planet.body_type, CelestialBody::CloudyPlanet) {
-                earth_position = planet.position;
+        // Lo que se mueve solo avanza una vez por cuadro, aunque haya dos vistas
+        meteor_shower.update();
+        spacecraft.emit_exhaust(&camera, &mut exhaust, &mut exhaust_rng);
+        exhaust.update();
+
+        // Las masas siguen al tamaño de los cuerpos, como todo en este sistema
+        let mut lagrange_points = Vec::new();
+        if let (true, Some(sun)) = (show_lagrange, planets.iter().find(|p| matches!(p.body_type, CelestialBody::Sun))) {
+            for (i, planet) in planets.iter().enumerate() {
+                if matches!(planet.body_type, CelestialBody::Sun) || selected_planet.is_some_and(|selected| selected != i) {
+                    continue;
+                }
+                let (r, g, b) = planet.material.map_color;
+                lagrange_points.push(lagrange::LagrangePoints::new(
+                    sun.position,
+                    planet.position,
+                    (planet.scale / sun.scale).powi(3),
+                    frame_uniforms.palette(r, g, b),
+                ));
             }
-            
-            let model_matrix = create_model_matrix(
-                planet.position,
-                planet.scale,
-                planet.rotation
-            );
-            
+        }
+
+        let scene = Scene {
+            planets: &planets,
+            moon: &moon,
+            earth_position,
+            station: &station,
+            station_model: assets.lod(station_mesh),
+            satellites: &satellites,
+            lagrange: &lagrange_points,
+            skybox: &skybox,
+            selected: selected_planet,
+            show_constellations,
+            meteors: &meteor_shower,
+            exhaust: &exhaust,
+            sphere: assets.lod(sphere_mesh),
+            ship: assets.lod(spacecraft_mesh),
+            ship_model_matrix: spacecraft.get_model_matrix(&camera),
+            ship_material: &ship_material,
+            bodies: &view_grid,
+            predicted_path: (spacecraft.flying && spacecraft.landing.is_none() && !spacecraft.docked)
+                .then(|| spacecraft.predict_path(300, &body_grid)),
+        };
+
+        // Con la pantalla dividida, a la derecha va la cabina de la nave (o la
+        // persecución si la vista principal ya es la cabina)
+        let full = Viewport::new(0, 0, framebuffer.width, framebuffer.height);
+        let mut views = vec![(view_matrix, camera.eye, camera_mode != CameraMode::Cockpit)];
+        if split_screen {
+            views.push(if camera_mode == CameraMode::Cockpit {
+                (spacecraft.chase_view_matrix(), spacecraft.chase_eye(), true)
+            } else {
+                (spacecraft.cockpit_view_matrix(), spacecraft.cockpit_eye(), false)
+            });
+        }
+        let half = full.width / 2;
+        // Con una sola vista y la cámara quieta el fondo y las órbitas salen
+        // del cuadro anterior (la cabina se mira desde la nave, no desde la cámara)
+        if camera.has_changed || camera_mode == CameraMode::Cockpit || views.len() > 1 {
+            static_layers.invalidate();
+        }
+        static_layers.check(redraw::LayerKey {
+            width: framebuffer.width,
+            height: framebuffer.height,
+            viewport: full,
+            background: sky_background_index,
+            linear_workflow,
+            depth_mode,
+        });
+        // El ratón señala en la vista principal
+        let mut main_view_uniforms = frame_uniforms;
+        for (index, &(view_matrix, eye, draw_ship)) in views.iter().enumerate() {
+            let viewport = match (views.len(), index) {
+                (1, _) => full,
+                (_, 0) => Viewport::new(0, 0, half, full.height),
+                _ => Viewport::new(half, 0, full.width - half, full.height),
+            };
+            framebuffer.set_viewport(viewport);
             let uniforms = Uniforms {
-                model_matrix,
                 view_matrix,
-                projection_matrix,
-                viewport_matrix,
-                time,
-                noise: create_noise(),
-                current_body: planet.body_type,
+                projection_matrix: create_perspective_matrix(viewport.width as f32, viewport.height as f32),
+                viewport_matrix: create_viewport_matrix(&viewport),
+                camera_position: eye,
+                ..frame_uniforms
             };
-    
-            render(&mut framebuffer, &uniforms, &vertex_arrays);
+            let layers = if views.len() == 1 { Some(&mut static_layers) } else { None };
+            render_scene(&mut framebuffer, &mut render_context, &uniforms, &scene, draw_ship, layers);
+            if index == 0 {
+                main_view_uniforms = uniforms;
+            }
         }
+        camera.has_changed = false;
 
-        // 4. Actualiza y renderiza la luna y su órbita
-        moon.update(earth_position);
-        
-        let moon_model_matrix = create_model_matrix(
-            moon.position,
-            moon.scale,
-            moon.rotation
-        );
-        
-        let moon_uniforms = Uniforms {
-            model_matrix: moon_model_matrix,
-            view_matrix,
-            projection_matrix,
-            viewport_matrix,
-            time,
-            noise: create_noise(),
-            current_body: CelestialBody::Moon,
-        };
+        // El mapa de sobredibujo cuenta solo la escena: la interfaz va encima.
+        // Queda el promedio y el máximo de escrituras para el panel.
+        let overdraw_stats = framebuffer.show_overdraw(|color| frame_uniforms.srgb(color));
 
-        framebuffer.set_current_color(0x303030);
-        draw_orbit(&mut framebuffer, moon.orbit_radius, &moon_uniforms, 0.99);
-        render(&mut framebuffer, &moon_uniforms, &vertex_arrays);
+        // Primer plano del planeta seleccionado (I lo muestra u oculta)
+        if show_inset {
+            if let Some(i) = selected_planet {
+                render_inset(&mut framebuffer, &mut render_context, &frame_uniforms, &planets[i], assets.mesh(sphere_mesh), render_scale);
+            }
+        }
 
-        // 5. Renderiza la nave espacial al final
-        let spacecraft_model_matrix = spacecraft.get_model_matrix(&camera);
-        let spacecraft_uniforms = Uniforms {
-            model_matrix: spacecraft_model_matrix,
-            view_matrix,
-            projection_matrix,
-            viewport_matrix,
-            time,
-            noise: create_noise(),
-            current_body: CelestialBody::Spaceship,
+        // 7. Post-proceso: brillo alrededor del sol, tonemapping y efectos de
+        // cámara, solo sobre la escena; la interfaz se dibuja después
+        let warp_intensity = spacecraft.warp_intensity();
+        post_chain.set_enabled("warp", warp_intensity > 0.01);
+        if let Some(streaks) = post_chain.get_mut::<WarpStreaks>() {
+            streaks.strength = warp_intensity;
+        }
+        let post_context = PostContext { scale: render_scale, time, encode_srgb: linear_workflow };
+        render_context.profiler.time(profiler::Stage::Post, || post_chain.run(&mut framebuffer, &post_context));
+
+        // 8. Interfaz, sobre la imagen ya procesada
+        framebuffer.begin_overlay(linear_workflow);
+
+        // Línea que separa las dos vistas
+        if split_screen {
+            framebuffer.set_viewport(Viewport::new(half - render_scale / 2, 0, render_scale, full.height));
+            framebuffer.set_background_color(frame_uniforms.palette(0x60, 0x60, 0x70));
+            framebuffer.clear_viewport();
+        }
+        framebuffer.reset_viewport();
+
+        // Minimapa del sistema visto desde arriba (M lo muestra u oculta)
+        if show_minimap {
+            let color = |material: &Material| {
+                let (r, g, b) = material.map_color;
+                frame_uniforms.palette(r, g, b)
+            };
+            let mut bodies: Vec<minimap::MapBody> = planets.iter()
+                .map(|planet| minimap::MapBody {
+                    position: planet.position,
+                    orbit_radius: planet.orbit_radius,
+                    color: color(&planet.material),
+                    size: planet.original_scale.sqrt() * 1.5,
+                })
+                .collect();
+            bodies.push(minimap::MapBody { position: moon.position, orbit_radius: 0.0, color: color(&moon.material), size: 1.0 });
+            bodies.push(minimap::MapBody { position: station.position, orbit_radius: 0.0, color: color(&station.material), size: 1.0 });
+            let pointers = [
+                minimap::MapPointer { position: spacecraft.position, heading: spacecraft.forward, color: color(&ship_material) },
+                minimap::MapPointer { position: camera.eye, heading: camera.get_view_direction(), color: frame_uniforms.palette(255, 80, 80) },
+            ];
+            minimap::render_minimap(
+                &mut framebuffer,
+                &bodies,
+                &pointers,
+                frame_uniforms.palette(0x04, 0x04, 0x0C),
+                frame_uniforms.palette(0x30, 0x30, 0x40),
+                render_scale,
+            );
+        }
+
+        // Distancia al planeta seleccionado y velocidad, desde la nave si se
+        // la pilota y si no desde la cámara
+        let (viewer, speed) = if spacecraft.flying {
+            (spacecraft.position, spacecraft.velocity.magnitude())
+        } else {
+            (camera.eye, (camera.eye - last_eye).magnitude())
         };
-        
-        render(&mut framebuffer, &spacecraft_uniforms, &spacecraft_vertex_arrays);
+        last_eye = camera.eye;
+        if selected_planet.is_some() || spacecraft.flying {
+            let mut lines = Vec::new();
+            if let Some(i) = selected_planet {
+                let planet = &planets[i];
+                let altitude = ((viewer - planet.position).magnitude() - planet.scale * SURFACE_RADIUS).max(0.0);
+                lines.push(format!("{}: {}", planet.name, units.format_distance(altitude)));
+            }
+            lines.push(format!("Velocidad {}", units.format_speed(speed)));
+            render_readout(&mut framebuffer, &frame_uniforms, &lines, render_scale);
+        }
+
+        // Panel con los datos del planeta seleccionado
+        if let Some(i) = selected_planet {
+            let planet = &planets[i];
+            let seconds = |radians_per_frame: f32| {
+                if radians_per_frame > 0.0 {
+                    format!("{:.1} s", std::f32::consts::TAU / radians_per_frame / 60.0)
+                } else {
+                    "-".to_string()
+                }
+            };
+            let period = match &planet.elements {
+                Some(elements) if elements.period_days > 0.0 => format!("{:.0} días", elements.period_days),
+                _ => seconds(planet.orbit_speed),
+            };
+            let moons = if matches!(planet.body_type, CelestialBody::CloudyPlanet) { 1 } else { 0 };
+            let rows = [
+                ("Tipo".to_string(), planet.body_type.description().to_string()),
+                ("Órbita".to_string(), units.format_distance(planet.orbit_radius)),
+                ("Período".to_string(), period),
+                ("Rotación".to_string(), seconds(planet.rotation_speed)),
+                ("Lunas".to_string(), moons.to_string()),
+                ("Satélites".to_string(), satellites.count_around(i).to_string()),
+                ("Semilla".to_string(), planet.material.noise.seed.to_string()),
+            ];
+            panel::render_panel(&mut framebuffer, &frame_uniforms, panel::Anchor::Left, &planet.name, &rows, render_scale);
+        }
+
+        // Globo con el nombre y la distancia del planeta bajo el ratón, si
+        // se queda quieto encima un momento
+        let mouse_on_screen = window.get_mouse_pos(MouseMode::Discard).map(|(x, y)| {
+            let (width, height) = window.get_size();
+            (x / width as f32 * framebuffer.width as f32, y / height as f32 * framebuffer.height as f32)
+        });
+        let targets: Vec<picking::PickTarget> = planets.iter()
+            .map(|planet| picking::PickTarget { center: planet.position, radius: planet.scale * BODY_RADIUS })
+            .collect();
+        let picked = mouse_on_screen.and_then(|(x, y)| picking::pick(&main_view_uniforms, x, y, &targets));
+        if let (Some(i), Some((x, y))) = (hover.update(picked), mouse_on_screen) {
+            let planet = &planets[i];
+            let distance = ((camera.eye - planet.position).magnitude() - planet.scale * SURFACE_RADIUS).max(0.0);
+            let lines = [planet.name.clone(), units.format_distance(distance)];
+            panel::render_tooltip(&mut framebuffer, &frame_uniforms, x as isize, y as isize, &lines, render_scale);
+        }
+
+        // Panel de depuración: lo que se cambia acá vale desde el cuadro siguiente
+        if debug_ui.open {
+            debug_ui.begin(&framebuffer, mouse_on_screen, window.get_mouse_down(MouseButton::Left), render_scale);
+            debug_ui.heading(&mut framebuffer, &frame_uniforms, "Depuración (F1)");
+            let pacing = format!(
+                "{} FPS, {} perdidos/s, {}%",
+                limiter.fps,
+                limiter.missed_per_second,
+                (resolution.scale() * 100.0).round(),
+            );
+            debug_ui.heading(&mut framebuffer, &frame_uniforms, &pacing);
+            debug_ui.heading(&mut framebuffer, &frame_uniforms, &format!("Vista: {} (F10)", debug_view.label()));
+            if let Some((average, max)) = overdraw_stats {
+                debug_ui.heading(&mut framebuffer, &frame_uniforms, &format!("Escrituras: {:.2} prom., {} máx.", average, max));
+            }
+            debug_ui.slider(&mut framebuffer, &frame_uniforms, "Luz del sol", &mut light_intensity, 0.0..=3.0);
+            debug_ui.slider(&mut framebuffer, &frame_uniforms, "Velocidad de cámara", &mut camera.movement_speed, 0.05..=3.0);
+            debug_ui.slider(&mut framebuffer, &frame_uniforms, "Giro de cámara", &mut camera.rotation_speed, 0.005..=0.1);
+            debug_ui.slider(&mut framebuffer, &frame_uniforms, "Vuelo libre", &mut free_fly_speed, 0.01..=2.0);
+
+            if let Some(i) = selected_planet {
+                let planet = &mut planets[i];
+                debug_ui.heading(&mut framebuffer, &frame_uniforms, &planet.name);
+                debug_ui.slider(&mut framebuffer, &frame_uniforms, "Órbita", &mut planet.orbit_speed, 0.0..=0.05);
+                debug_ui.slider(&mut framebuffer, &frame_uniforms, "Rotación", &mut planet.rotation_speed, 0.0..=0.1);
+
+                let config = &mut planet.material.noise;
+                let mut octaves = config.fractal.octaves as i32;
+                let mut changed = debug_ui.slider(&mut framebuffer, &frame_uniforms, "Frecuencia", &mut config.frequency, 0.001..=0.1);
+                changed |= debug_ui.slider_int(&mut framebuffer, &frame_uniforms, "Octavas", &mut octaves, 1..=8);
+                changed |= debug_ui.slider(&mut framebuffer, &frame_uniforms, "Lacunaridad", &mut config.fractal.lacunarity, 1.0..=4.0);
+                changed |= debug_ui.slider(&mut framebuffer, &frame_uniforms, "Ganancia", &mut config.fractal.gain, 0.0..=1.0);
+                changed |= debug_ui.slider_int(&mut framebuffer, &frame_uniforms, "Semilla", &mut config.seed, 0..=10000);
+                config.fractal.octaves = octaves as u32;
+                if changed {
+                    planet.noise = planet.material.noise.build();
+                }
+            } else {
+                debug_ui.heading(&mut framebuffer, &frame_uniforms, "1-9: elegir planeta");
+            }
+            debug_ui.end();
+        }
+
+        // Misiones: solo cuentan mientras se pilota la nave
+        if spacecraft.flying {
+            let mission_bodies: Vec<missions::MissionBody> = planets.iter()
+                .map(|planet| missions::MissionBody { name: &planet.name, position: planet.position, radius: planet.scale * BODY_RADIUS })
+                .chain([
+                    missions::MissionBody {
+                        name: CelestialBody::Moon.default_name(),
+                        position: moon.position,
+                        radius: moon.scale * BODY_RADIUS,
+                    },
+                    missions::MissionBody {
+                        name: "Estacion",
+                        position: station.position,
+                        radius: station.scale,
+                    },
+                ])
+                .collect();
+            mission_tracker.update(spacecraft.position, home, &mission_bodies);
+            mission_tracker.render_hud(&mut framebuffer, &frame_uniforms, home, &mission_bodies, render_scale);
+            render_flight_status(&mut framebuffer, &frame_uniforms, &spacecraft, render_scale);
+        }
+
+        // Ayuda: las teclas que valen en el modo de cámara actual, sacadas
+        // de la tabla de atajos
+        if show_help {
+            let rows: Vec<(String, String)> = keybindings::active(camera_mode)
+                .map(|binding| (binding.label.to_string(), binding.description.to_string()))
+                .collect();
+            panel::render_panel(&mut framebuffer, &frame_uniforms, panel::Anchor::Center, "Controles", &rows, render_scale);
+        }
+        if render_context.profiler.visible {
+            render_context.profiler.render_bar(&mut framebuffer, &frame_uniforms, render_scale);
+            let rows = render_context.profiler.rows();
+            panel::render_panel(&mut framebuffer, &frame_uniforms, panel::Anchor::Left, "Perfil (Tab)", &rows, render_scale);
+        }
+        if menu.open {
+            menu.render(&mut framebuffer, &frame_uniforms, &settings, render_scale);
+        }
+
+        framebuffer.end_overlay(linear_workflow);
 
         // Actualiza la ventana
-        window
-            .update_with_buffer(&framebuffer.buffer, framebuffer_width, framebuffer_height)
-            .unwrap();
-    
-        std::thread::sleep(frame_delay);
+        // Con supersampling se promedia; si la resolución dinámica bajó la
+        // medida interna, se estira a la ventana
+        render_context.profiler.time(profiler::Stage::Present, || {
+            let output = if (framebuffer.width, framebuffer.height) == (framebuffer_width, framebuffer_height) {
+                &framebuffer.buffer
+            } else if (framebuffer.width, framebuffer.height) == (framebuffer_width * render_scale, framebuffer_height * render_scale) {
+                framebuffer.downsample_into(render_scale, &mut present_buffer);
+                &present_buffer
+            } else {
+                framebuffer.resample_into(&mut present_buffer, framebuffer_width, framebuffer_height, settings.bilinear_upscale);
+                &present_buffer
+            };
+            window
+                .update_with_buffer(output, framebuffer_width, framebuffer_height)
+                .unwrap();
+        });
+        render_context.profiler.end_frame();
+
+        limiter.end_frame();
+
+        // Resolución dinámica contra el objetivo de cuadros; sin límite no hay
+        // presupuesto y se vuelve a la resolución completa
+        let budget = limiter.target().frame_time().filter(|_| settings.dynamic_resolution);
+        let rescaled = match budget {
+            Some(budget) => resolution.update(limiter.work_time, budget),
+            None if resolution.scale() < 1.0 => {
+                resolution.reset();
+                true
+            }
+            None => false,
+        };
+        if rescaled {
+            framebuffer = internal_framebuffer(framebuffer_width, framebuffer_height, render_scale, resolution.scale());
+        }
     }
 }
 pub struct Star {
     position: Vec3,
+    // Flujo lineal según la magnitud aparente
     brightness: f32,
-    size: f32,
+    // Tinte (sRGB) de su temperatura de color
+    tint: Color,
+    // Desfase propio de cada estrella para que no parpadeen todas a la vez
+    twinkle_seed: f32,
+}
+
+// Escala de Pogson: 5 magnitudes son un factor 100. La magnitud 3 vale 1.0
+fn magnitude_to_flux(magnitude: f32) -> f32 {
+    10f32.powf(-0.4 * (magnitude - 3.0)).min(8.0)
 }
 
 pub struct Skybox {
     stars: Vec<Star>,
+    // Cuántas de `stars` son al azar; van primero
+    random_stars: usize,
     radius: f32,
+    constellations: Vec<Constellation>,
+    galaxy: sky::SkySource,
+    // Fondo cargado de una imagen; reemplaza a la galaxia y a las estrellas
+    custom_background: Option<Rc<sky::SkySource>>,
+    // Las estrellas al azar salen siempre de la misma semilla, así volver
+    // a una cantidad devuelve el mismo cielo
+    seed: Seed,
 }
 
 impl Skybox {
-    pub fn new(num_stars: usize, radius: f32) -> Self {
-        let mut rng = rand::thread_rng();
-        let stars = (0..num_stars).map(|_| {
+    pub fn new(num_stars: usize, radius: f32, constellations: Vec<Constellation>, seed: Seed) -> Self {
+        let mut rng = seed.rng(Stream::Constellations);
+        let mut stars = Skybox::random_stars(num_stars, radius, seed);
+
+        // Las estrellas de las constelaciones son fijas y de las más brillantes
+        for direction in constellations.iter().flat_map(|constellation| constellation.stars.iter()) {
+            let temperature = rng.gen_range(4000.0..12000.0);
+            stars.push(Star {
+                position: direction * radius,
+                brightness: magnitude_to_flux(1.5),
+                tint: Color::from_temperature(temperature),
+                twinkle_seed: rng.gen_range(0.0..1000.0),
+            });
+        }
+
+        // Vía Láctea y nebulosas, detrás de las estrellas
+        let galaxy_noise = NoiseConfig { seed: seed.noise(4242), ..NoiseConfig::default() }.build();
+        let galaxy = sky::SkySource::Equirect(sky::bake_galaxy(512, 256, &galaxy_noise));
+
+        Skybox { stars, random_stars: num_stars, radius, constellations, galaxy, custom_background: None, seed }
+    }
+
+    // Estrellas al azar; las de las constelaciones se agregan aparte
+    fn random_stars(count: usize, radius: f32, seed: Seed) -> Vec<Star> {
+        let mut rng = seed.rng(Stream::Stars);
+        (0..count).map(|_| {
+            // Las estrellas frías (rojas, naranjas) son mucho más comunes que
+            // las calientes (azules), pero estas se ven más brillantes
+            let temperature = 2500.0 + 27500.0 * rng.gen_range(0.0f32..1.0).powi(4);
+            let luminosity = (temperature / 6500.0).sqrt().clamp(0.6, 1.6);
+            // Hay muchas más estrellas débiles que brillantes
+            let magnitude = 6.5 - 8.0 * rng.gen_range(0.0f32..1.0).powi(3);
+
             Star {
                 position: Vec3::new(
                     rng.gen_range(-1.0..1.0),
                     rng.gen_range(-1.0..1.0),
                     rng.gen_range(-1.0..1.0),
                 ).normalize() * radius,
-                brightness: rng.gen_range(0.5..1.0),
-                size: rng.gen_range(1.0..3.0),
+                brightness: magnitude_to_flux(magnitude) * luminosity,
+                tint: Color::from_temperature(temperature),
+                twinkle_seed: rng.gen_range(0.0..1000.0),
             }
-        }).collect();
+        }).collect()
+    }
 
-        Skybox { stars, radius }
+    // Rehace las estrellas al azar con otra cantidad y deja las de las constelaciones
+    pub fn set_star_count(&mut self, count: usize) {
+        if count != self.random_stars {
+            self.stars.splice(..self.random_stars, Skybox::random_stars(count, self.radius, self.seed));
+            self.random_stars = count;
+        }
+    }
+
+    pub fn set_custom_background(&mut self, background: Option<Rc<sky::SkySource>>) {
+        self.custom_background = background;
+    }
+
+    // Posición en pantalla y profundidad de un punto del cielo, si está delante de la cámara
+    fn project(uniforms: &Uniforms, position: Vec3) -> Option<(isize, isize, f32)> {
+        let clip = uniforms.projection_matrix * uniforms.view_matrix * Vec4::new(position.x, position.y, position.z, 1.0);
+        if clip.w <= 0.0 {
+            return None;
+        }
+
+        let ndc = clip / clip.w;
+        // Muy fuera de la pantalla: las líneas serían enormes
+        if ndc.x.abs() > 4.0 || ndc.y.abs() > 4.0 {
+            return None;
+        }
+        let (screen_x, screen_y) = uniforms.ndc_to_screen(ndc.x, ndc.y);
+        Some((screen_x as isize, screen_y as isize, encode_depth(&clip, uniforms.depth_mode)))
     }
-    pub fn render(&self, framebuffer: &mut Framebuffer, uniforms: &Uniforms) {
+
+    // Líneas tenues entre las estrellas de cada constelación y su nombre debajo
+    pub fn render_constellations(&self, framebuffer: &mut Framebuffer, uniforms: &Uniforms) {
+        let line_color = uniforms.palette(70, 100, 160) * 0.6;
+        let label_color = uniforms.palette(140, 170, 220) * 0.8;
+        let scale = (uniforms.viewport_height() as usize / 450).max(1);
+
+        for constellation in &self.constellations {
+            let projected: Vec<Option<(isize, isize, f32)>> = constellation.stars.iter()
+                .map(|direction| Skybox::project(uniforms, direction * self.radius))
+                .collect();
+
+            framebuffer.set_current_color(line_color);
+            for &(a, b) in &constellation.lines {
+                if let (Some(start), Some(end)) = (projected[a], projected[b]) {
+                    draw_line(framebuffer, start, end);
+                }
+            }
+
+            let visible: Vec<(isize, isize, f32)> = projected.iter().flatten().copied().collect();
+            if visible.is_empty() {
+                continue;
+            }
+            let lowest = visible.iter().map(|star| star.1).max().unwrap_or(0);
+            let center_x = visible.iter().map(|star| star.0).sum::<isize>() / visible.len() as isize;
+            let depth = visible[0].2;
+
+            let label_x = center_x - text::text_width(&constellation.name, scale) as isize / 2;
+            let label_y = lowest + 6 * scale as isize;
+            framebuffer.set_current_color(label_color);
+            text::draw_text(&constellation.name, label_x, label_y, scale, |x, y| {
+                if x >= 0 && y >= 0 {
+                    framebuffer.point(x as usize, y as usize, depth);
+                }
+            });
+        }
+    }
+    // La galaxia, o la imagen cargada si hay una. Solo depende de la cámara.
+    pub fn render_background(&self, framebuffer: &mut Framebuffer, uniforms: &Uniforms) {
+        sky::render_background(framebuffer, uniforms, self.custom_background.as_deref().unwrap_or(&self.galaxy));
+    }
+
+    pub fn render_stars(&self, framebuffer: &mut Framebuffer, uniforms: &Uniforms) {
+        // La imagen ya trae sus propias estrellas
+        if self.custom_background.is_some() {
+            return;
+        }
+        // El ruido de la escena tiene frecuencia baja, así que el tiempo avanza rápido
+        let time = uniforms.time as f32 * 1.5;
+
+        // Los tamaños están pensados para 900 px de alto; con SSAA crecen igual
+        let pixel_scale = uniforms.viewport_height() / 900.0;
+
         for star in &self.stars {
-            let world_pos = uniforms.view_matrix * nalgebra_glm::Vec4::new(
+            let clip = uniforms.projection_matrix * uniforms.view_matrix * Vec4::new(
                 star.position.x,
                 star.position.y,
                 star.position.z,
                 1.0
             );
-            
-            let mut transformed = uniforms.projection_matrix * world_pos;
-            transformed /= transformed.w;
-
-            if transformed.z < 1.0 {
-                let screen_x = ((transformed.x + 1.0) * framebuffer.width as f32 / 2.0) as usize;
-                let screen_y = ((1.0 - transformed.y) * framebuffer.height as f32 / 2.0) as usize;
-
-                let intensity = (star.brightness * 255.0) as u32;
-                let color = (intensity << 16) | (intensity << 8) | intensity;
-
-                if screen_x < framebuffer.width && screen_y < framebuffer.height {
-                    framebuffer.set_current_color(color);
-
-                    let size = star.size as usize;
-                    for dy in 0..size {
-                        for dx in 0..size {
-                            let px = screen_x.saturating_add(dx).saturating_sub(size/2);
-                            let py = screen_y.saturating_add(dy).saturating_sub(size/2);
-                            if px < framebuffer.width && py < framebuffer.height {
-                                // Cambiamos la profundidad a 1.0 para que las estrellas estén en el fondo
-                                framebuffer.point(px, py, 1.0); 
-                            }
-                        }
+            if clip.w <= 0.0 {
+                continue;
+            }
+
+            let ndc = clip / clip.w;
+            let (screen_x, screen_y) = uniforms.ndc_to_screen(ndc.x, ndc.y);
+
+            // Flujo según la distancia real a la cámara (el cielo no está centrado en ella)
+            let distance = (star.position - uniforms.camera_position).magnitude();
+            let attenuation = (self.radius / distance).powi(2).clamp(0.25, 4.0);
+
+            // Centelleo suave: ruido en el tiempo desplazado por la semilla de la estrella
+            let twinkle = uniforms.noise.get_noise_2d(star.twinkle_seed * 13.0, time + star.twinkle_seed);
+            let flux = star.brightness * attenuation * (1.0 + twinkle * 0.25);
+
+            // Sprite circular con caída gaussiana: las brillantes son más grandes,
+            // pero el pico se reparte para que el total siga al flujo
+            let radius = (0.8 + 0.6 * flux.sqrt()).min(4.0) * pixel_scale.max(1.0);
+            let peak = flux / (radius * radius) * pixel_scale.max(1.0).powi(2);
+            let color = uniforms.srgb(star.tint) * peak;
+
+            let reach = radius.ceil() as isize + 1;
+            let (cx, cy) = (screen_x.floor() as isize, screen_y.floor() as isize);
+            for py in cy - reach..=cy + reach {
+                for px in cx - reach..=cx + reach {
+                    if px < 0 || py < 0 {
+                        continue;
                     }
+                    let dx = px as f32 + 0.5 - screen_x;
+                    let dy = py as f32 + 0.5 - screen_y;
+                    let falloff = (-(dx * dx + dy * dy) / (radius * radius) * 3.0).exp();
+                    if falloff < 0.01 {
+                        continue;
+                    }
+                    // Profundidad en el infinito: nada de la escena queda detrás
+                    framebuffer.point_additive(px as usize, py as usize, 1.0, color * falloff);
                 }
             }
         }
@@ -607,29 +2167,116 @@ impl Skybox {
 //nave
 struct Spacecraft {
     position: Vec3,
-    rotation: Vec3,
+    // Orientación de la nave: el modelo apunta en +X con +Y arriba
+    forward: Vec3,
+    up: Vec3,
     scale: f32,
     velocity: Vec3,
     acceleration: f32,
     screen_size: f32, 
     collision_radius: f32,
     min_height: f32, 
+    // En modo vuelo la nave se controla directamente y la cámara la persigue
+    flying: bool,
+    // Empuje actual, para la intensidad del escape de los motores
+    throttle: f32,
+    // Entrada de empuje del último cuadro, con signo (negativo frena)
+    thrust: f32,
+    // Al chocar: cuánto rebota la velocidad normal (0 desliza, 1 rebote
+    // elástico) y cuánto se pierde de la tangencial
+    restitution: f32,
+    friction: f32,
+    // Si está posada sobre un cuerpo
+    landing: Option<Landing>,
+    warp: Option<Warp>,
+    // Acoplada a la estación, recargando combustible
+    docked: bool,
+    fuel: f32,
+}
+
+// Nave posada: índice del cuerpo, y normal y rumbo en el marco del cuerpo
+#[derive(Clone, Copy, Debug)]
+struct Landing {
+    body: usize,
+    normal: Vec3,
+    forward: Vec3,
+}
+
+// Suelo de un cuerpo para aterrizar, con los mismos índices que la grilla
+#[derive(Clone, Copy, Debug)]
+struct Surface {
+    center: Vec3,
+    radius: f32,
+    // Giro actual del cuerpo alrededor de Y
+    spin: f32,
+    landable: bool,
+}
+
+const SHIP_TURN_RATE: f32 = 0.03;
+// Velocidad de acercamiento máxima para aterrizar en vez de rebotar
+const LANDING_SPEED: f32 = 0.06;
+const TAKEOFF_SPEED: f32 = 0.08;
+// Salto: aceleración y frenado por cuadro y velocidad tope
+const WARP_ACCELERATION: f32 = 0.04;
+const WARP_MAX_SPEED: f32 = 4.0;
+// Combustible (de 0 a 1): gasto por cuadro a empuje completo, lo que cuesta
+// un salto y la recarga por cuadro acoplada a la estación
+const FUEL_PER_THRUST: f32 = 0.0008;
+const WARP_FUEL: f32 = 0.2;
+const REFUEL_RATE: f32 = 0.004;
+// Velocidad máxima para acoplarse a la estación
+const DOCKING_SPEED: f32 = 0.08;
+
+// Salto en curso hacia el cuerpo `target` (índice de la grilla)
+#[derive(Clone, Copy, Debug)]
+struct Warp {
+    target: usize,
+    speed: f32,
+}
+
+// Mandos del modo vuelo para un cuadro, en -1..1 (salvo el ratón, en radianes)
+#[derive(Default)]
+struct FlightInput {
+    thrust: f32,
+    pitch: f32,
+    yaw: f32,
+    roll: f32,
 }
 
 impl Spacecraft {
     fn new() -> Self {
         Spacecraft {
             position: Vec3::new(0.0, 7.0, -5.0), 
-            rotation: Vec3::new(0.0, 0.0, 0.0),
+            forward: Vec3::new(1.0, 0.0, 0.0),
+            up: Vec3::new(0.0, 1.0, 0.0),
             scale: 0.35, 
             velocity: Vec3::new(0.0, 0.0, 0.0),
             acceleration: 0.05, 
             screen_size: 0.05, 
             collision_radius: 0.3,
             min_height: 8.0, 
+            flying: false,
+            throttle: 0.0,
+            thrust: 0.0,
+            restitution: 0.5,
+            friction: 0.1,
+            landing: None,
+            warp: None,
+            docked: false,
+            fuel: 1.0,
         }
     }
 
+    // La nave se escala con la distancia para verse siempre del mismo tamaño
+    fn world_scale(&self, camera: &Camera) -> f32 {
+        let distance = (self.position - camera.eye).magnitude();
+        self.scale * distance * self.screen_size
+    }
+
+    fn right(&self) -> Vec3 {
+        self.forward.cross(&self.up).normalize()
+    }
+
     fn update(&mut self, camera: &Camera) {
         // La nave sigue a la cámara 
         let offset = Vec3::new(0.0, 2.0, -3.0); // Aumentado offset.y de -0.5 a 2.0
@@ -650,40 +2297,381 @@ impl Spacecraft {
         new_position.y = new_position.y.max(self.min_height);
         self.position = new_position;
         
-        self.rotation.y = (-camera_forward.z).atan2(camera_forward.x);
-        self.rotation.x = (camera_forward.y).asin();
+        self.forward = camera_forward;
+        self.up = camera_right.cross(&camera_forward).normalize();
+        self.throttle = (self.velocity.magnitude() * 3.0).min(1.0);
     }
 
-    fn check_collisions(&self, planets: &[Planet], moon: &Moon) -> bool {
+    // Vuelo libre de 6 grados de libertad: se gira sobre los ejes propios
+    // de la nave y el empuje se integra en la velocidad
+    fn fly(&mut self, input: &FlightInput, bodies: &spatial::SpatialGrid, surfaces: &[Surface], station: &SpaceStation) {
+        if self.docked {
+            self.ride_station(input, station);
+            return;
+        }
+        if let Some(landing) = self.landing {
+            self.ride_surface(input, landing, surfaces);
+            return;
+        }
+        if let Some(warp) = self.warp {
+            self.warp_step(warp, bodies);
+            return;
+        }
 
-        if self.position.y <= self.min_height + 1.0 {
-            for planet in planets {
-                let distance = (self.position - planet.position).magnitude();
-                let collision_distance = self.collision_radius + planet.scale * 0.9;
-                
-                if distance < collision_distance {
-                    return true;
+        let right = self.right();
+        let yaw = input.yaw * SHIP_TURN_RATE;
+        let pitch = input.pitch * SHIP_TURN_RATE;
+        let roll = input.roll * SHIP_TURN_RATE;
+
+        self.forward = nalgebra_glm::rotate_vec3(&self.forward, yaw, &self.up);
+        self.forward = nalgebra_glm::rotate_vec3(&self.forward, pitch, &right);
+        self.up = nalgebra_glm::rotate_vec3(&self.up, pitch, &right);
+        self.up = nalgebra_glm::rotate_vec3(&self.up, roll, &self.forward);
+
+        // Se vuelve a ortonormalizar para que el error numérico no se acumule
+        self.forward = self.forward.normalize();
+        self.up = self.right().cross(&self.forward).normalize();
+
+        // Sin combustible los motores no responden
+        let thrust = if self.fuel > 0.0 { input.thrust } else { 0.0 };
+        self.fuel = (self.fuel - thrust.abs() * FUEL_PER_THRUST).max(0.0);
+        self.throttle = thrust.max(0.0);
+        self.thrust = thrust;
+        self.velocity = Spacecraft::integrate_velocity(self.velocity, self.forward, thrust);
+
+        // A alta velocidad el desplazamiento de un cuadro puede atravesar un
+        // planeta entero, así que se barre la esfera de la nave por el tramo.
+        // Tras un choque el resto del cuadro sigue con la velocidad nueva.
+        let mut remaining = 1.0;
+        for _ in 0..3 {
+            let step = self.velocity * remaining;
+            match self.sweep(self.position, step, bodies) {
+                Some((time, normal, body)) => {
+                    // Despacio y de frente sobre un cuerpo sólido, la nave aterriza
+                    if surfaces[body].landable && -self.velocity.dot(&normal) < LANDING_SPEED {
+                        self.land(body, normal, surfaces);
+                        return;
+                    }
+                    // Un poco afuera de la superficie para no quedar tocándola
+                    self.position += step * time + normal * 1e-3;
+                    self.velocity = self.collision_response(self.velocity, normal);
+                    remaining *= 1.0 - time;
+                }
+                None => {
+                    self.position += step;
+                    break;
                 }
             }
+        }
 
-            let moon_distance = (self.position - moon.position).magnitude();
-            let moon_collision_distance = self.collision_radius + moon.scale * 0.9;
-            
-            if moon_distance < moon_collision_distance {
-                return true;
+        // Entrar despacio al radio de acoplamiento engancha la nave a la estación
+        if (self.position - station.position).magnitude() < station.docking_radius
+            && self.velocity.magnitude() < DOCKING_SPEED
+        {
+            self.docked = true;
+            self.ride_station(&FlightInput::default(), station);
+        }
+    }
+
+    // Acoplada: va pegada al puerto de la estación y recarga. El empuje
+    // hacia adelante la suelta.
+    fn ride_station(&mut self, input: &FlightInput, station: &SpaceStation) {
+        self.position = station.docking_port();
+        self.velocity = Vec3::new(0.0, 0.0, 0.0);
+        self.throttle = 0.0;
+        self.thrust = 0.0;
+        self.fuel = (self.fuel + REFUEL_RATE).min(1.0);
+
+        if input.thrust > 0.0 {
+            self.docked = false;
+            // Hacia afuera del puerto, para no volver a acoplarse enseguida
+            self.position = station.position + self.forward * (station.docking_radius + 0.1);
+            self.velocity = self.forward * TAKEOFF_SPEED;
+        }
+    }
+
+    // Salto hacia un cuerpo: la nave gira hacia él, acelera hasta la
+    // velocidad tope y frena sola para quedar a una distancia segura
+    fn start_warp(&mut self, target: usize) {
+        if self.landing.is_none() && !self.docked && self.fuel >= WARP_FUEL {
+            self.fuel -= WARP_FUEL;
+            self.warp = Some(Warp { target, speed: self.velocity.magnitude() });
+        }
+    }
+
+    // Intensidad del efecto de salto, de 0 a 1
+    fn warp_intensity(&self) -> f32 {
+        self.warp.map_or(0.0, |warp| (warp.speed / WARP_MAX_SPEED).min(1.0))
+    }
+
+    fn warp_step(&mut self, mut warp: Warp, bodies: &spatial::SpatialGrid) {
+        let target = bodies.entries()[warp.target];
+        let offset = target.center - self.position;
+        let stop_distance = target.radius * 3.0 + 2.0;
+        let remaining = offset.magnitude() - stop_distance;
+
+        // Se apunta de a poco al objetivo, que sigue moviéndose en su órbita
+        let direction = offset.normalize();
+        self.forward = self.forward.lerp(&direction, 0.1).normalize();
+        self.up = self.right().cross(&self.forward).normalize();
+
+        // La velocidad queda limitada por la que se puede frenar en lo que falta
+        let braking = (2.0 * WARP_ACCELERATION * remaining.max(0.0)).sqrt();
+        warp.speed = (warp.speed + WARP_ACCELERATION).min(WARP_MAX_SPEED).min(braking);
+        self.throttle = 1.0;
+        self.thrust = 1.0;
+
+        let step = direction * warp.speed.min(remaining.max(0.0));
+        self.velocity = step;
+        if let Some((time, normal, _)) = self.sweep(self.position, step, bodies) {
+            // Algo se cruzó en el camino: se sale del salto y se choca normalmente
+            self.position += step * time + normal * 1e-3;
+            self.velocity = self.collision_response(step.normalize() * LANDING_SPEED, normal);
+            self.warp = None;
+            return;
+        }
+        self.position += step;
+
+        if remaining <= 0.05 {
+            self.velocity = Vec3::new(0.0, 0.0, 0.0);
+            self.warp = None;
+        } else {
+            self.warp = Some(warp);
+        }
+    }
+
+    // Se posa sobre el cuerpo `body` en el punto de normal `normal`. La pose
+    // se guarda en el marco del cuerpo para girar con él.
+    fn land(&mut self, body: usize, normal: Vec3, surfaces: &[Surface]) {
+        let spin = surfaces[body].spin;
+        let tangent = self.forward - normal * self.forward.dot(&normal);
+        let tangent = if tangent.magnitude() > 1e-3 {
+            tangent.normalize()
+        } else {
+            normal.cross(&Vec3::new(0.0, 1.0, 0.0)).try_normalize(1e-6).unwrap_or(Vec3::new(1.0, 0.0, 0.0))
+        };
+        let to_local = |v: Vec3| nalgebra_glm::rotate_vec3(&v, -spin, &Vec3::new(0.0, 1.0, 0.0));
+
+        self.landing = Some(Landing { body, normal: to_local(normal), forward: to_local(tangent) });
+        self.velocity = Vec3::new(0.0, 0.0, 0.0);
+        self.ride_surface(&FlightInput::default(), self.landing.unwrap(), surfaces);
+    }
+
+    // En tierra: sigue la superficie del cuerpo que gira y se traslada, A / D
+    // giran el rumbo y el empuje hacia adelante despega
+    fn ride_surface(&mut self, input: &FlightInput, mut landing: Landing, surfaces: &[Surface]) {
+        let surface = surfaces[landing.body];
+        landing.forward = nalgebra_glm::rotate_vec3(&landing.forward, input.yaw * SHIP_TURN_RATE, &landing.normal);
+
+        let to_world = |v: Vec3| nalgebra_glm::rotate_vec3(&v, surface.spin, &Vec3::new(0.0, 1.0, 0.0));
+        self.up = to_world(landing.normal).normalize();
+        self.forward = to_world(landing.forward).normalize();
+        // Apoyada sobre su esfera de choque, donde la dejó el barrido, y un
+        // poco afuera para no quedar tocando el cuerpo
+        self.position = surface.center + self.up * (surface.radius + self.collision_radius + 1e-3);
+        self.velocity = Vec3::new(0.0, 0.0, 0.0);
+        self.throttle = 0.0;
+        self.thrust = 0.0;
+        self.landing = Some(landing);
+
+        if input.thrust > 0.0 {
+            self.landing = None;
+            self.velocity = self.up * TAKEOFF_SPEED;
+        }
+    }
+
+    // Cámara a ras del suelo, detrás de la nave y mirando al horizonte
+    fn surface_camera(&self, camera: &mut Camera) {
+        camera.eye = self.position - self.forward * 1.5 + self.up * 0.5;
+        camera.center = self.position + self.forward * 4.0 + self.up * 0.3;
+        camera.up = self.up;
+        camera.has_changed = true;
+    }
+
+    // Velocidad después de tocar una superficie con normal `normal`: la parte
+    // normal rebota según la restitución y la tangencial desliza con roce
+    fn collision_response(&self, velocity: Vec3, normal: Vec3) -> Vec3 {
+        let into = velocity.dot(&normal);
+        if into >= 0.0 {
+            return velocity;
+        }
+        let normal_part = normal * into;
+        let tangent_part = velocity - normal_part;
+        tangent_part * (1.0 - self.friction) - normal_part * self.restitution
+    }
+
+    // Primer contacto al mover la nave de `start` a `start + displacement`:
+    // fracción del tramo (0 a 1), normal de la superficie tocada e índice del cuerpo
+    fn sweep(&self, start: Vec3, displacement: Vec3, bodies: &spatial::SpatialGrid) -> Option<(f32, Vec3, usize)> {
+        let mut first: Option<(f32, Vec3, usize)> = None;
+
+        for index in bodies.query_segment(start, start + displacement, self.collision_radius) {
+            let body = bodies.entries()[index];
+            let (center, radius) = (body.center, self.collision_radius + body.radius);
+            let offset = start - center;
+            let c = offset.dot(&offset) - radius * radius;
+            let time = if c <= 0.0 {
+                // Ya estaba dentro: solo cuenta si se sigue metiendo
+                if offset.dot(&displacement) >= 0.0 {
+                    continue;
+                }
+                0.0
+            } else {
+                // |offset + t·d|² = r²; la raíz menor es la entrada a la esfera
+                let a = displacement.dot(&displacement);
+                let b = offset.dot(&displacement);
+                let discriminant = b * b - a * c;
+                if a <= 1e-12 || b >= 0.0 || discriminant < 0.0 {
+                    continue;
+                }
+                let time = (-b - discriminant.sqrt()) / a;
+                if time > 1.0 {
+                    continue;
+                }
+                time
+            };
+
+            if first.is_none_or(|(best, _, _)| time < best) {
+                let contact = start + displacement * time - center;
+                let normal = if contact.magnitude() > 1e-6 { contact.normalize() } else { -displacement.normalize() };
+                first = Some((time, normal, index));
             }
         }
-        false
+        first
+    }
+
+    // Un paso de la física de vuelo: empuje, rozamiento y velocidad máxima
+    fn integrate_velocity(velocity: Vec3, forward: Vec3, thrust: f32) -> Vec3 {
+        const THRUST: f32 = 0.01;
+        const DRAG: f32 = 0.985;
+        const MAX_SPEED: f32 = 1.0;
+
+        let velocity = (velocity + forward * thrust * THRUST) * DRAG;
+        if velocity.magnitude() > MAX_SPEED {
+            velocity.normalize() * MAX_SPEED
+        } else {
+            velocity
+        }
+    }
+
+    // Trayectoria si se mantienen el rumbo y el empuje actuales, con los
+    // cuerpos quietos donde están. Se corta en el primer choque; el booleano
+    // dice si lo hubo.
+    fn predict_path(&self, steps: usize, bodies: &spatial::SpatialGrid) -> (Vec<Vec3>, bool) {
+        let mut position = self.position;
+        let mut velocity = self.velocity;
+        let mut points = vec![position];
+
+        for _ in 0..steps {
+            velocity = Spacecraft::integrate_velocity(velocity, self.forward, self.thrust);
+            if velocity.magnitude() < 1e-4 {
+                break;
+            }
+            if let Some((time, _, _)) = self.sweep(position, velocity, bodies) {
+                points.push(position + velocity * time);
+                return (points, true);
+            }
+            position += velocity;
+            points.push(position);
+        }
+        (points, false)
+    }
+
+    // Cono de partículas detrás de los motores, más denso con más empuje
+    fn emit_exhaust(&self, camera: &Camera, particles: &mut particles::ParticleSystem, rng: &mut StdRng) {
+        if self.throttle <= 0.01 {
+            return;
+        }
+
+        let scale = self.world_scale(camera);
+        let right = self.right();
+        // La cola del modelo está cerca de x = -9
+        let nozzle = self.position - self.forward * (9.0 * scale);
+        let count = (self.throttle * 8.0).ceil() as usize;
+
+        for _ in 0..count {
+            let spread = right * rng.gen_range(-0.25..0.25) + self.up * rng.gen_range(-0.25..0.25);
+            let direction = (-self.forward + spread).normalize();
+            let speed = scale * rng.gen_range(1.5..3.0) * self.throttle.min(1.5);
+
+            particles.emit(particles::Particle {
+                position: nozzle + spread * scale,
+                velocity: self.velocity + direction * speed,
+                size: scale * rng.gen_range(0.8..1.6),
+                age: 0,
+                lifetime: rng.gen_range(12..28),
+                start_color: Color::from_rgb_f32(0.8, 0.9, 1.0) * (1.5 + self.throttle),
+                end_color: Color::new(255, 110, 40),
+            });
+        }
+    }
+
+    // Punto de vista de la cabina, en la nariz de la nave
+    fn cockpit_eye(&self) -> Vec3 {
+        self.position + self.forward * 0.3 + self.up * 0.05
+    }
+
+    // Mantiene la cámara en la cabina; la vista se arma con `cockpit_view_matrix`
+    fn cockpit(&self, camera: &mut Camera) {
+        camera.eye = self.cockpit_eye();
+        camera.center = camera.eye + self.forward;
+        camera.up = self.up;
+        camera.has_changed = true;
+    }
+
+    // Vista directamente desde la base de la nave, sin pasar por look_at
+    fn cockpit_view_matrix(&self) -> Mat4 {
+        let eye = self.cockpit_eye();
+        let right = self.right();
+        let back = -self.forward;
+
+        Mat4::new(
+            right.x, right.y, right.z, -right.dot(&eye),
+            self.up.x, self.up.y, self.up.z, -self.up.dot(&eye),
+            back.x, back.y, back.z, -back.dot(&eye),
+            0.0, 0.0, 0.0, 1.0,
+        )
+    }
+
+    // Cámara de persecución detrás y un poco arriba de la nave
+    fn chase_eye(&self) -> Vec3 {
+        self.position - self.forward * 4.0 + self.up * 1.2
+    }
+
+    // Persecución sin el retraso de `chase`, para una vista aparte
+    fn chase_view_matrix(&self) -> Mat4 {
+        create_view_matrix(self.chase_eye(), self.position + self.forward * 3.0, self.up)
+    }
+
+    fn chase(&self, camera: &mut Camera) {
+        camera.eye += (self.chase_eye() - camera.eye) * 0.2;
+        camera.center = self.position + self.forward * 3.0;
+        camera.up = self.up;
+        camera.has_changed = true;
+    }
+
+    fn check_collisions(&self, bodies: &spatial::SpatialGrid) -> bool {
+        (self.flying || self.position.y <= self.min_height + 1.0) && self.collides_at(self.position, bodies)
+    }
+
+    // Si la nave en `position` tocaría algún cuerpo
+    fn collides_at(&self, position: Vec3, bodies: &spatial::SpatialGrid) -> bool {
+        bodies.query_segment(position, position, self.collision_radius).into_iter().any(|index| {
+            let body = bodies.entries()[index];
+            (position - body.center).magnitude() < self.collision_radius + body.radius
+        })
     }
 
     fn get_model_matrix(&self, camera: &Camera) -> Mat4 {
-        let distance = (self.position - camera.eye).magnitude();
-        let scale_factor = distance * self.screen_size;
-        
-        create_model_matrix(
-            self.position,
-            self.scale * scale_factor,
-            self.rotation
+        let scale = self.world_scale(camera);
+        let right = self.right();
+
+        // Columnas: el +X del modelo va al frente, +Y arriba y +Z a la derecha
+        Mat4::new(
+            self.forward.x * scale, self.up.x * scale, right.x * scale, self.position.x,
+            self.forward.y * scale, self.up.y * scale, right.y * scale, self.position.y,
+            self.forward.z * scale, self.up.z * scale, right.z * scale, self.position.z,
+            0.0, 0.0, 0.0, 1.0,
         )
     }
 }
\ No newline at end of file
@@ -0,0 +1,144 @@
+use crate::color::Color;
+use crate::lighting::SurfaceMaterial;
+use crate::noise_utils::NoiseConfig;
+use crate::CelestialBody;
+
+// Shader de superficie con el que se sombrea un objeto
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ShaderId {
+    Sun,
+    Rocky,
+    GasGiant,
+    Cloudy,
+    Ringed,
+    Ice,
+    Colorful,
+    Moon,
+    Ocean,
+    Nature,
+    Aurora,
+    Lava,
+    Desert,
+    Spaceship,
+    SpaceStation,
+}
+
+// Vórtice de un gigante gaseoso: centro en latitud y longitud (radianes),
+// radio en latitud y cuánto deriva en longitud con el tiempo
+#[derive(Clone, Copy, Debug)]
+pub struct StormSpot {
+    pub latitude: f32,
+    pub longitude: f32,
+    pub radius: f32,
+    pub drift: f32,
+}
+
+impl StormSpot {
+    // La gran mancha de siempre, en el hemisferio sur
+    pub const GREAT_SPOT: StormSpot = StormSpot { latitude: -0.38, longitude: 1.2, radius: 0.28, drift: 0.05 };
+}
+
+// Cómo se ve un objeto, aparte de qué es: dos planetas del mismo tipo
+// pueden tener otro ruido u otro tinte, y la nave y la estación no
+// necesitan ser cuerpos celestes para tener el suyo. No hay texturas: todo
+// sale del shader y del ruido.
+#[derive(Clone, Copy, Debug)]
+pub struct Material {
+    pub shader: ShaderId,
+    // Color aproximado en sRGB, para el minimapa, las estelas y los puntos
+    pub map_color: (u8, u8, u8),
+    // Multiplica al color del shader (en sRGB); blanco no cambia nada
+    pub tint: Color,
+    pub noise: NoiseConfig,
+    // Si brilla por sí mismo y pasa al bloom
+    pub emissive: bool,
+    // Metálico y rugosidad para la BRDF de la nave y la estación. `trim` es
+    // el de las juntas entre placas y `panel` el de los paneles solares; en
+    // lo que no los tiene son iguales a `surface`.
+    pub surface: SurfaceMaterial,
+    pub trim: SurfaceMaterial,
+    pub panel: SurfaceMaterial,
+    // Solo para el shader de gigante gaseoso; None es sin mancha
+    pub spot: Option<StormSpot>,
+}
+
+// Dieléctrico medio rugoso, para lo que no elige otra cosa
+const DEFAULT_SURFACE: SurfaceMaterial = SurfaceMaterial::new(0.0, 0.5);
+
+impl Material {
+    fn new(shader: ShaderId, map_color: (u8, u8, u8), noise: NoiseConfig) -> Self {
+        Material {
+            shader,
+            map_color,
+            tint: Color::from_rgb_f32(1.0, 1.0, 1.0),
+            noise,
+            emissive: matches!(shader, ShaderId::Sun | ShaderId::Lava),
+            surface: DEFAULT_SURFACE,
+            trim: DEFAULT_SURFACE,
+            panel: DEFAULT_SURFACE,
+            spot: match shader {
+                ShaderId::GasGiant => Some(StormSpot::GREAT_SPOT),
+                _ => None,
+            },
+        }
+    }
+
+    // El material de siempre de cada tipo de cuerpo
+    pub fn for_body(body: CelestialBody) -> Self {
+        let (shader, map_color) = match body {
+            CelestialBody::Sun => (ShaderId::Sun, (255, 200, 80)),
+            CelestialBody::RockyPlanet => (ShaderId::Rocky, (170, 120, 90)),
+            CelestialBody::GasGiant => (ShaderId::GasGiant, (210, 170, 120)),
+            CelestialBody::CloudyPlanet => (ShaderId::Cloudy, (80, 140, 230)),
+            CelestialBody::RingedPlanet => (ShaderId::Ringed, (220, 200, 150)),
+            CelestialBody::IcePlanet => (ShaderId::Ice, (180, 230, 255)),
+            CelestialBody::ColorPlanet => (ShaderId::Colorful, (200, 90, 200)),
+            CelestialBody::Moon => (ShaderId::Moon, (180, 180, 180)),
+            CelestialBody::OceanPlanet => (ShaderId::Ocean, (40, 110, 200)),
+            CelestialBody::NaturePlanet => (ShaderId::Nature, (80, 180, 90)),
+            CelestialBody::AuroraPlanet => (ShaderId::Aurora, (90, 220, 170)),
+            CelestialBody::LavaPlanet => (ShaderId::Lava, (240, 90, 30)),
+            CelestialBody::DesertPlanet => (ShaderId::Desert, (220, 180, 110)),
+        };
+        Material::new(shader, map_color, NoiseConfig::for_body(body))
+    }
+
+    // Laca blanca
+    pub fn spaceship() -> Self {
+        Material::new(ShaderId::Spaceship, (255, 255, 255), NoiseConfig::default())
+            .with_surface(SurfaceMaterial::new(0.15, 0.35))
+    }
+
+    // Casco de metal, juntas más ásperas y paneles solares de vidrio
+    pub fn space_station() -> Self {
+        Material {
+            trim: SurfaceMaterial::new(0.8, 0.75),
+            panel: SurfaceMaterial::new(0.1, 0.15),
+            ..Material::new(ShaderId::SpaceStation, (120, 200, 255), NoiseConfig::default())
+                .with_surface(SurfaceMaterial::new(0.85, 0.4))
+        }
+    }
+
+    pub fn with_tint(mut self, tint: Color) -> Self {
+        self.tint = tint;
+        self
+    }
+
+    pub fn with_noise(mut self, noise: NoiseConfig) -> Self {
+        self.noise = noise;
+        self
+    }
+
+    pub fn with_spot(mut self, spot: Option<StormSpot>) -> Self {
+        self.spot = spot;
+        self
+    }
+
+    // Cambia las tres superficies a la vez
+    pub fn with_surface(mut self, surface: SurfaceMaterial) -> Self {
+        self.surface = surface;
+        self.trim = surface;
+        self.panel = surface;
+        self
+    }
+}
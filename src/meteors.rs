@@ -0,0 +1,145 @@
+use nalgebra_glm::{Vec3, Vec4};
+use rand::rngs::StdRng;
+use rand::Rng;
+use crate::framebuffer::Framebuffer;
+use crate::color::Color;
+use crate::shaders::encode_depth;
+use crate::Uniforms;
+
+// Estrella fugaz: recorre un arco sobre la esfera del cielo dejando una estela
+struct Meteor {
+    origin: Vec3,
+    // Tangente unitaria en `origin`, hacia donde avanza
+    heading: Vec3,
+    // Radianes por cuadro
+    speed: f32,
+    trail: f32,
+    age: u32,
+    lifetime: u32,
+    brightness: f32,
+}
+
+impl Meteor {
+    // Punto del arco recorrido `angle` radianes desde el origen
+    fn point(&self, angle: f32, radius: f32) -> Vec3 {
+        (self.origin * angle.cos() + self.heading * angle.sin()) * radius
+    }
+}
+
+pub struct MeteorShower {
+    meteors: Vec<Meteor>,
+    radius: f32,
+    next_spawn: u32,
+    rng: StdRng,
+}
+
+impl MeteorShower {
+    // `radius` es el de la esfera del skybox
+    pub fn new(radius: f32, rng: StdRng) -> Self {
+        MeteorShower { meteors: Vec::new(), radius, next_spawn: 120, rng }
+    }
+
+    pub fn update(&mut self) {
+        let rng = &mut self.rng;
+
+        for meteor in &mut self.meteors {
+            meteor.age += 1;
+        }
+        self.meteors.retain(|meteor| meteor.age < meteor.lifetime);
+
+        if self.next_spawn > 0 {
+            self.next_spawn -= 1;
+            return;
+        }
+        self.next_spawn = rng.gen_range(90..300);
+
+        let origin = Vec3::new(
+            rng.gen_range(-1.0..1.0),
+            rng.gen_range(-1.0..1.0),
+            rng.gen_range(-1.0..1.0),
+        ).normalize();
+        // Cualquier dirección perpendicular al origen sirve de rumbo
+        let random = Vec3::new(rng.gen_range(-1.0..1.0), rng.gen_range(-1.0..1.0), rng.gen_range(-1.0..1.0));
+        let heading = (random - origin * random.dot(&origin)).normalize();
+        if !heading.x.is_finite() {
+            return;
+        }
+
+        self.meteors.push(Meteor {
+            origin,
+            heading,
+            speed: rng.gen_range(0.008..0.02),
+            trail: rng.gen_range(0.06..0.15),
+            age: 0,
+            lifetime: rng.gen_range(25..50),
+            brightness: rng.gen_range(1.5..3.0),
+        });
+    }
+
+    // Se dibuja después de lo opaco con la prueba de profundidad normal, así
+    // los planetas tapan las estelas. No escribe profundidad.
+    pub fn render(&self, framebuffer: &mut Framebuffer, uniforms: &Uniforms) {
+        let head_color = uniforms.palette(255, 245, 220);
+        let tail_color = uniforms.palette(150, 190, 255);
+        const SEGMENTS: usize = 12;
+
+        for meteor in &self.meteors {
+            // Aparece y se apaga suavemente
+            let life = meteor.age as f32 / meteor.lifetime as f32;
+            let fade = (life * 6.0).min(1.0) * (1.0 - life).powf(0.7);
+            let head = meteor.age as f32 * meteor.speed;
+
+            let points: Vec<Option<(f32, f32, f32)>> = (0..=SEGMENTS)
+                .map(|i| {
+                    let angle = head - meteor.trail * i as f32 / SEGMENTS as f32;
+                    project(uniforms, meteor.point(angle.max(0.0), self.radius))
+                })
+                .collect();
+
+            for i in 0..SEGMENTS {
+                if let (Some(start), Some(end)) = (points[i], points[i + 1]) {
+                    let t0 = i as f32 / SEGMENTS as f32;
+                    let t1 = (i + 1) as f32 / SEGMENTS as f32;
+                    let color0 = head_color.lerp(&tail_color, t0) * ((1.0 - t0) * fade * meteor.brightness);
+                    let color1 = head_color.lerp(&tail_color, t1) * ((1.0 - t1) * fade * meteor.brightness);
+                    streak(framebuffer, start, end, |t| color0.lerp(&color1, t));
+                }
+            }
+        }
+    }
+}
+
+fn project(uniforms: &Uniforms, position: Vec3) -> Option<(f32, f32, f32)> {
+    let clip = uniforms.projection_matrix * uniforms.view_matrix * Vec4::new(position.x, position.y, position.z, 1.0);
+    if clip.w <= 0.0 {
+        return None;
+    }
+
+    let ndc = clip / clip.w;
+    if ndc.x.abs() > 2.0 || ndc.y.abs() > 2.0 {
+        return None;
+    }
+    let (screen_x, screen_y) = uniforms.ndc_to_screen(ndc.x, ndc.y);
+    Some((screen_x, screen_y, encode_depth(&clip, uniforms.depth_mode)))
+}
+
+// Segmento aditivo con color y profundidad interpolados
+fn streak(
+    framebuffer: &mut Framebuffer,
+    start: (f32, f32, f32),
+    end: (f32, f32, f32),
+    color_at: impl Fn(f32) -> Color,
+) {
+    let steps = (end.0 - start.0).abs().max((end.1 - start.1).abs()).ceil().max(1.0) as usize;
+
+    for step in 0..=steps {
+        let t = step as f32 / steps as f32;
+        let x = start.0 + (end.0 - start.0) * t;
+        let y = start.1 + (end.1 - start.1) * t;
+        if x < 0.0 || y < 0.0 {
+            continue;
+        }
+        let depth = start.2 + (end.2 - start.2) * t;
+        framebuffer.point_additive(x as usize, y as usize, depth, color_at(t));
+    }
+}
@@ -0,0 +1,117 @@
+use nalgebra_glm::Vec3;
+use crate::framebuffer::{Framebuffer, Viewport, DepthTest};
+use crate::color::Color;
+use crate::draw_line;
+
+// Cuerpo visto desde arriba: se dibuja como un punto de color
+pub struct MapBody {
+    pub position: Vec3,
+    pub orbit_radius: f32,
+    pub color: Color,
+    // Radio del punto en píxeles del mapa sin escalar
+    pub size: f32,
+}
+
+// Algo con rumbo (la nave, la cámara): punto con una raya hacia donde mira
+pub struct MapPointer {
+    pub position: Vec3,
+    pub heading: Vec3,
+    pub color: Color,
+}
+
+// Mapa ortográfico del plano XZ en un cuadrado de la esquina superior
+// derecha. Todo se pinta encima de la escena, sin prueba de profundidad.
+pub fn render_minimap(
+    framebuffer: &mut Framebuffer,
+    bodies: &[MapBody],
+    pointers: &[MapPointer],
+    background: Color,
+    orbit_color: Color,
+    pixel_scale: usize,
+) {
+    let size = framebuffer.height / 4;
+    let margin = 10 * pixel_scale;
+    if size + 2 * margin > framebuffer.width || size < 16 {
+        return;
+    }
+    let map = Viewport::new(framebuffer.width - size - margin, margin, size, size);
+
+    framebuffer.set_viewport(Viewport::new(map.x - pixel_scale, map.y - pixel_scale, size + 2 * pixel_scale, size + 2 * pixel_scale));
+    framebuffer.set_background_color(orbit_color * 2.0);
+    framebuffer.clear_viewport();
+    framebuffer.set_viewport(map);
+    framebuffer.set_background_color(background);
+    framebuffer.clear_viewport();
+
+    framebuffer.set_depth_test(DepthTest::Always);
+    framebuffer.set_write_depth(false);
+
+    // El sistema entero, con un poco de aire alrededor de la órbita más grande
+    let extent = bodies.iter().map(|body| body.orbit_radius).fold(1.0, f32::max) * 1.1;
+    let center = (map.x as f32 + size as f32 / 2.0, map.y as f32 + size as f32 / 2.0);
+    let world_to_map = size as f32 / 2.0 / extent;
+    let to_map = |position: Vec3| {
+        (center.0 + position.x * world_to_map, center.1 + position.z * world_to_map)
+    };
+
+    framebuffer.set_current_color(orbit_color);
+    for body in bodies {
+        if body.orbit_radius <= 0.0 {
+            continue;
+        }
+        let radius = body.orbit_radius * world_to_map;
+        let segments = (radius * 2.0).max(24.0) as usize;
+        let mut last = None;
+        for i in 0..=segments {
+            let angle = i as f32 / segments as f32 * std::f32::consts::TAU;
+            let point = (
+                (center.0 + angle.cos() * radius) as isize,
+                (center.1 + angle.sin() * radius) as isize,
+                0.0,
+            );
+            if let Some(previous) = last {
+                draw_line(framebuffer, previous, point);
+            }
+            last = Some(point);
+        }
+    }
+
+    for body in bodies {
+        let (x, y) = to_map(body.position);
+        framebuffer.set_current_color(body.color);
+        fill_disc(framebuffer, x, y, body.size * pixel_scale as f32);
+    }
+
+    for pointer in pointers {
+        let (x, y) = to_map(pointer.position);
+        let flat = Vec3::new(pointer.heading.x, 0.0, pointer.heading.z);
+        framebuffer.set_current_color(pointer.color);
+        if flat.magnitude() > 0.001 {
+            let flat = flat.normalize() * (8.0 * pixel_scale as f32);
+            draw_line(
+                framebuffer,
+                (x as isize, y as isize, 0.0),
+                ((x + flat.x) as isize, (y + flat.z) as isize, 0.0),
+            );
+        }
+        fill_disc(framebuffer, x, y, 1.5 * pixel_scale as f32);
+    }
+
+    framebuffer.set_depth_test(DepthTest::Less);
+    framebuffer.set_write_depth(true);
+    framebuffer.reset_viewport();
+}
+
+fn fill_disc(framebuffer: &mut Framebuffer, x: f32, y: f32, radius: f32) {
+    let radius = radius.max(1.0);
+    let reach = radius.ceil() as isize;
+    let (cx, cy) = (x as isize, y as isize);
+    for py in cy - reach..=cy + reach {
+        for px in cx - reach..=cx + reach {
+            let (dx, dy) = ((px - cx) as f32, (py - cy) as f32);
+            if px >= 0 && py >= 0 && dx * dx + dy * dy <= radius * radius {
+                framebuffer.point(px as usize, py as usize, 0.0);
+            }
+        }
+    }
+}
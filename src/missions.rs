@@ -0,0 +1,248 @@
+use nalgebra_glm::{Vec3, Vec4};
+use std::f32::consts::TAU;
+use std::fs;
+use crate::framebuffer::{Framebuffer, DepthTest};
+use crate::text;
+use crate::Uniforms;
+
+// Cuadros que queda en pantalla un mensaje de objetivo cumplido
+const MESSAGE_FRAMES: u32 = 180;
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Objective {
+    // Acercarse al cuerpo
+    Visit(String),
+    // Dar una vuelta completa alrededor del cuerpo sin alejarse
+    Orbit(String),
+    // Volver al punto de partida de la nave
+    Return,
+}
+
+impl Objective {
+    fn describe(&self) -> String {
+        match self {
+            Objective::Visit(target) => format!("Visitar {}", target),
+            Objective::Orbit(target) => format!("Orbitar {}", target),
+            Objective::Return => "Volver a la base".to_string(),
+        }
+    }
+
+    fn target(&self) -> Option<&str> {
+        match self {
+            Objective::Visit(target) | Objective::Orbit(target) => Some(target),
+            Objective::Return => None,
+        }
+    }
+}
+
+pub struct Mission {
+    pub name: String,
+    pub objectives: Vec<Objective>,
+}
+
+// Lee el archivo de misiones (ver assets/missions.txt)
+pub fn load(filename: &str) -> Result<Vec<Mission>, String> {
+    let source = fs::read_to_string(filename).map_err(|err| format!("{}: {}", filename, err))?;
+    let mut missions: Vec<Mission> = Vec::new();
+
+    for (number, line) in source.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        let error = |message: &str| format!("{}:{}: {}", filename, number + 1, message);
+        let (keyword, rest) = line.split_once(' ').unwrap_or((line, ""));
+        let rest = rest.trim();
+
+        if keyword == "mision" {
+            missions.push(Mission { name: rest.to_string(), objectives: Vec::new() });
+            continue;
+        }
+
+        let current = missions.last_mut().ok_or_else(|| error("falta 'mision' antes"))?;
+        let objective = match keyword {
+            "visitar" | "orbitar" if rest.is_empty() => return Err(error("falta el nombre del cuerpo")),
+            "visitar" => Objective::Visit(rest.to_string()),
+            "orbitar" => Objective::Orbit(rest.to_string()),
+            "volver" => Objective::Return,
+            _ => return Err(error("palabra clave desconocida")),
+        };
+        current.objectives.push(objective);
+    }
+
+    Ok(missions)
+}
+
+// Un cuerpo que puede ser objetivo, en el cuadro actual
+pub struct MissionBody<'a> {
+    pub name: &'a str,
+    pub position: Vec3,
+    pub radius: f32,
+}
+
+// Sigue las misiones en orden, un objetivo a la vez
+pub struct MissionTracker {
+    missions: Vec<Mission>,
+    mission: usize,
+    objective: usize,
+    // Ángulo recorrido alrededor del objetivo de órbita, y el último medido
+    orbit_progress: f32,
+    last_angle: Option<f32>,
+    message: Option<(String, u32)>,
+}
+
+impl MissionTracker {
+    pub fn new(missions: Vec<Mission>) -> Self {
+        let missions = missions.into_iter().filter(|mission| !mission.objectives.is_empty()).collect();
+        MissionTracker { missions, mission: 0, objective: 0, orbit_progress: 0.0, last_angle: None, message: None }
+    }
+
+    fn current(&self) -> Option<&Objective> {
+        self.missions.get(self.mission)?.objectives.get(self.objective)
+    }
+
+    fn find<'a>(bodies: &'a [MissionBody<'a>], name: &str) -> Option<&'a MissionBody<'a>> {
+        bodies.iter().find(|body| body.name.eq_ignore_ascii_case(name))
+    }
+
+    pub fn update(&mut self, ship: Vec3, home: Vec3, bodies: &[MissionBody]) {
+        if let Some((_, frames)) = &mut self.message {
+            *frames = frames.saturating_sub(1);
+            if *frames == 0 {
+                self.message = None;
+            }
+        }
+
+        let Some(objective) = self.current() else {
+            return;
+        };
+        let done = match objective {
+            Objective::Visit(target) => Self::find(bodies, target)
+                .is_some_and(|body| (ship - body.position).magnitude() < body.radius * 2.5 + 1.5),
+            Objective::Orbit(target) => match Self::find(bodies, target) {
+                Some(body) => self.track_orbit(ship, body),
+                None => false,
+            },
+            Objective::Return => (ship - home).magnitude() < 2.0,
+        };
+        if done {
+            self.advance();
+        }
+    }
+
+    // Suma el giro de la nave alrededor del cuerpo, visto desde arriba.
+    // Salir del radio de órbita reinicia la cuenta.
+    fn track_orbit(&mut self, ship: Vec3, body: &MissionBody) -> bool {
+        let offset = ship - body.position;
+        if offset.magnitude() > body.radius * 6.0 + 3.0 {
+            self.orbit_progress = 0.0;
+            self.last_angle = None;
+            return false;
+        }
+
+        let angle = offset.z.atan2(offset.x);
+        if let Some(last) = self.last_angle {
+            // Diferencia más corta entre los dos ángulos
+            let delta = (angle - last + TAU * 1.5).rem_euclid(TAU) - TAU * 0.5;
+            self.orbit_progress += delta;
+        }
+        self.last_angle = Some(angle);
+        self.orbit_progress.abs() >= TAU
+    }
+
+    fn advance(&mut self) {
+        let finished = self.current().map(Objective::describe).unwrap_or_default();
+        self.objective += 1;
+        self.orbit_progress = 0.0;
+        self.last_angle = None;
+
+        let text = if self.objective < self.missions[self.mission].objectives.len() {
+            format!("Objetivo cumplido: {}", finished)
+        } else {
+            let name = self.missions[self.mission].name.clone();
+            self.mission += 1;
+            self.objective = 0;
+            if self.mission < self.missions.len() {
+                format!("Misión cumplida: {}", name)
+            } else {
+                "Todas las misiones cumplidas!".to_string()
+            }
+        };
+        self.message = Some((text, MESSAGE_FRAMES));
+    }
+
+    // Objetivo actual arriba a la izquierda, un marcador sobre el cuerpo
+    // buscado y el último mensaje al centro. Va encima de todo.
+    pub fn render_hud(&self, framebuffer: &mut Framebuffer, uniforms: &Uniforms, home: Vec3, bodies: &[MissionBody], pixel_scale: usize) {
+        let scale = 2 * pixel_scale;
+        let margin = 10 * pixel_scale as isize;
+        let line = (text::text_height(scale) + 4 * pixel_scale) as isize;
+        let plot = |framebuffer: &mut Framebuffer, x: isize, y: isize| {
+            if x >= 0 && y >= 0 {
+                framebuffer.point(x as usize, y as usize, 0.0);
+            }
+        };
+        framebuffer.set_depth_test(DepthTest::Always);
+        framebuffer.set_write_depth(false);
+
+        if let (Some(mission), Some(objective)) = (self.missions.get(self.mission), self.current()) {
+            framebuffer.set_current_color(uniforms.palette(160, 200, 255));
+            text::draw_text(&mission.name, margin, margin, scale, |x, y| plot(framebuffer, x, y));
+
+            let mut status = objective.describe();
+            if matches!(objective, Objective::Orbit(_)) {
+                status += &format!(" ({}%)", (self.orbit_progress.abs() / TAU * 100.0).min(100.0) as u32);
+            }
+            framebuffer.set_current_color(uniforms.palette(255, 255, 255));
+            text::draw_text(&status, margin, margin + line, scale, |x, y| plot(framebuffer, x, y));
+
+            let target = match objective.target() {
+                Some(name) => Self::find(bodies, name).map(|body| (body.position, body.radius)),
+                None => Some((home, 0.5)),
+            };
+            if let Some((position, radius)) = target {
+                framebuffer.set_current_color(uniforms.palette(255, 200, 60));
+                draw_marker(framebuffer, uniforms, position, radius, pixel_scale);
+            }
+        }
+
+        if let Some((message, _)) = &self.message {
+            let width = text::text_width(message, scale) as isize;
+            let x = framebuffer.width as isize / 2 - width / 2;
+            let y = framebuffer.height as isize / 3;
+            framebuffer.set_current_color(uniforms.palette(120, 255, 140));
+            text::draw_text(message, x, y, scale, |x, y| plot(framebuffer, x, y));
+        }
+
+        framebuffer.set_depth_test(DepthTest::Less);
+        framebuffer.set_write_depth(true);
+    }
+}
+
+// Esquinas de un recuadro alrededor del cuerpo en pantalla
+fn draw_marker(framebuffer: &mut Framebuffer, uniforms: &Uniforms, position: Vec3, radius: f32, pixel_scale: usize) {
+    let clip = uniforms.projection_matrix * uniforms.view_matrix * Vec4::new(position.x, position.y, position.z, 1.0);
+    if clip.w <= 0.0 {
+        return;
+    }
+    let ndc = clip / clip.w;
+    let (x, y) = uniforms.ndc_to_screen(ndc.x, ndc.y);
+    let radius_px = radius * uniforms.projection_matrix[(1, 1)] * uniforms.viewport_height() / 2.0 / clip.w;
+    let half = (radius_px * 1.2).max(12.0 * pixel_scale as f32) as isize;
+    let arm = (half / 3).max(3);
+    let (cx, cy) = (x as isize, y as isize);
+
+    for (corner_x, corner_y, dir_x, dir_y) in [(-1, -1, 1, 1), (1, -1, -1, 1), (-1, 1, 1, -1), (1, 1, -1, -1)] {
+        let (px, py) = (cx + corner_x * half, cy + corner_y * half);
+        for step in 0..arm {
+            for thickness in 0..pixel_scale as isize {
+                for (x, y) in [(px + dir_x * step, py + dir_y * thickness), (px + dir_x * thickness, py + dir_y * step)] {
+                    if x >= 0 && y >= 0 {
+                        framebuffer.point(x as usize, y as usize, 0.0);
+                    }
+                }
+            }
+        }
+    }
+}
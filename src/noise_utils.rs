@@ -0,0 +1,141 @@
+use nalgebra_glm::Vec3;
+use fastnoise_lite::{FastNoiseLite, NoiseType};
+use crate::CelestialBody;
+
+#[derive(Clone, Copy, Debug)]
+pub struct FractalParams {
+    pub octaves: u32,
+    pub lacunarity: f32,
+    pub gain: f32,
+}
+
+impl FractalParams {
+    pub const fn new(octaves: u32, lacunarity: f32, gain: f32) -> Self {
+        FractalParams { octaves, lacunarity, gain }
+    }
+}
+
+impl Default for FractalParams {
+    fn default() -> Self {
+        FractalParams::new(4, 2.0, 0.5)
+    }
+}
+
+// Ajustes de ruido de cada cuerpo; el FastNoiseLite se construye una sola vez
+#[derive(Clone, Copy, Debug)]
+pub struct NoiseConfig {
+    pub noise_type: NoiseType,
+    pub seed: i32,
+    pub frequency: f32,
+    pub fractal: FractalParams,
+}
+
+impl NoiseConfig {
+    pub fn for_body(body: CelestialBody) -> Self {
+        let base = NoiseConfig::default();
+        match body {
+            CelestialBody::Sun => NoiseConfig { seed: 1337, ..base },
+            CelestialBody::RockyPlanet => NoiseConfig { seed: 2024, fractal: FractalParams::new(5, 2.2, 0.5), ..base },
+            CelestialBody::GasGiant => NoiseConfig { seed: 5150, fractal: FractalParams::new(3, 2.0, 0.55), ..base },
+            CelestialBody::CloudyPlanet => NoiseConfig { seed: 4321, fractal: FractalParams::new(4, 2.0, 0.5), ..base },
+            CelestialBody::IcePlanet => NoiseConfig { seed: 9001, ..base },
+            CelestialBody::Moon => NoiseConfig { seed: 7, fractal: FractalParams::new(5, 2.0, 0.45), ..base },
+            CelestialBody::NaturePlanet => NoiseConfig { seed: 8080, fractal: FractalParams::new(5, 2.1, 0.5), ..base },
+            CelestialBody::AuroraPlanet => NoiseConfig { seed: 3030, ..base },
+            CelestialBody::LavaPlanet => NoiseConfig { seed: 6660, fractal: FractalParams::new(2, 2.3, 0.4), ..base },
+            CelestialBody::DesertPlanet => NoiseConfig { seed: 1984, ..base },
+            _ => base,
+        }
+    }
+
+    pub fn build(&self) -> FastNoiseLite {
+        let mut noise = FastNoiseLite::with_seed(self.seed);
+        noise.set_noise_type(Some(self.noise_type));
+        noise.set_frequency(Some(self.frequency));
+        noise
+    }
+}
+
+impl Default for NoiseConfig {
+    fn default() -> Self {
+        NoiseConfig {
+            noise_type: NoiseType::OpenSimplex2,
+            seed: 1337,
+            frequency: 0.01,
+            fractal: FractalParams::default(),
+        }
+    }
+}
+
+// Suma de octavas normalizada, en -1..1
+pub fn fbm(noise: &FastNoiseLite, p: Vec3, params: FractalParams) -> f32 {
+    accumulate(params, |frequency| {
+        noise.get_noise_3d(p.x * frequency, p.y * frequency, p.z * frequency)
+    })
+}
+
+// Crestas afiladas (montañas, grietas), en 0..1
+pub fn ridged(noise: &FastNoiseLite, p: Vec3, params: FractalParams) -> f32 {
+    accumulate(params, |frequency| {
+        let n = 1.0 - noise.get_noise_3d(p.x * frequency, p.y * frequency, p.z * frequency).abs();
+        n * n
+    })
+}
+
+// Suma de valores absolutos (humo, turbulencia), en 0..1
+pub fn turbulence(noise: &FastNoiseLite, p: Vec3, params: FractalParams) -> f32 {
+    accumulate(params, |frequency| {
+        noise.get_noise_3d(p.x * frequency, p.y * frequency, p.z * frequency).abs()
+    })
+}
+
+// Desplaza el punto con ruido de ruido para patrones orgánicos y arremolinados
+pub fn domain_warp(noise: &FastNoiseLite, p: Vec3, strength: f32, params: FractalParams) -> Vec3 {
+    let offset = Vec3::new(
+        fbm(noise, p, params),
+        fbm(noise, p + Vec3::new(5.2, 1.3, 2.8), params),
+        fbm(noise, p + Vec3::new(1.7, 9.2, 4.1), params),
+    );
+    p + offset * strength
+}
+
+// fBm evaluado sobre el dominio deformado
+pub fn warped_fbm(noise: &FastNoiseLite, p: Vec3, strength: f32, params: FractalParams) -> f32 {
+    fbm(noise, domain_warp(noise, p, strength, params), params)
+}
+
+// Ruido 3D que evoluciona con el tiempo como si fuera una cuarta dimensión:
+// se mezclan dos cortes desplazados y se avanza de corte en corte.
+pub fn animated_noise(noise: &FastNoiseLite, p: Vec3, time: f32) -> f32 {
+    let slice = time.floor();
+    let blend = time - slice;
+    let blend = blend * blend * (3.0 - 2.0 * blend);
+
+    let offset = Vec3::new(17.3, 31.7, 47.1);
+    let a = p + offset * slice;
+    let b = p + offset * (slice + 1.0);
+
+    let na = noise.get_noise_3d(a.x, a.y, a.z);
+    let nb = noise.get_noise_3d(b.x, b.y, b.z);
+    na + (nb - na) * blend
+}
+
+pub fn animated_fbm(noise: &FastNoiseLite, p: Vec3, time: f32, params: FractalParams) -> f32 {
+    accumulate(params, |frequency| animated_noise(noise, p * frequency, time))
+}
+
+fn accumulate(params: FractalParams, mut sample: impl FnMut(f32) -> f32) -> f32 {
+    let mut sum = 0.0;
+    let mut amplitude = 1.0;
+    let mut frequency = 1.0;
+    let mut total_amplitude = 0.0;
+
+    for _ in 0..params.octaves.max(1) {
+        sum += sample(frequency) * amplitude;
+        total_amplitude += amplitude;
+        amplitude *= params.gain;
+        frequency *= params.lacunarity;
+    }
+
+    sum / total_amplitude
+}
@@ -1,62 +1,1087 @@
-use tobj;
-use nalgebra_glm::{Vec2, Vec3};
+use std::cmp::Ordering;
+use std::collections::{BinaryHeap, HashMap};
+use std::f32::consts::PI;
+use std::fmt;
+use nalgebra_glm::{DMat4, DVec3, DVec4, Vec2, Vec3};
+use crate::color::Color;
 use crate::vertex::Vertex;
 
+// Por qué no se pudo cargar un modelo
+#[derive(Debug)]
+pub enum ObjError {
+    // El archivo no está o no se puede abrir
+    Open(String),
+    // Una línea que no se entiende, con su número (desde 1)
+    Parse { path: String, line: usize, message: String },
+    // Es válido pero no tiene ningún triángulo
+    Empty(String),
+}
+
+impl fmt::Display for ObjError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ObjError::Open(path) => write!(f, "{}: no se pudo abrir", path),
+            ObjError::Parse { path, line, message } => write!(f, "{}:{}: {}", path, line, message),
+            ObjError::Empty(path) => write!(f, "{}: no tiene triángulos", path),
+        }
+    }
+}
+
+impl std::error::Error for ObjError {}
+
+// Lo que encontró `Obj::validate`
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Validation {
+    // Triángulos descartados por tener área cero
+    pub degenerate: usize,
+    // Vértices con NaN o infinito; se descartan los triángulos que los usan
+    pub non_finite: usize,
+    // Índices que no apuntan a ningún vértice; también se descarta su triángulo
+    pub out_of_range: usize,
+    // Vértices que se juntaron con otro igual
+    pub welded: usize,
+}
+
+impl Validation {
+    // Si el modelo está roto. Los triángulos degenerados son comunes en lo
+    // que exportan los programas de modelado (la nave trae varios) y no hace
+    // falta avisar por ellos.
+    pub fn has_problems(&self) -> bool {
+        self.non_finite + self.out_of_range > 0
+    }
+}
+
+impl fmt::Display for Validation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{} triángulos degenerados, {} vértices con NaN, {} índices fuera de rango",
+            self.degenerate, self.non_finite, self.out_of_range,
+        )
+    }
+}
+
+// Los modelos de assets, metidos en el binario para que corra desde
+// cualquier directorio aunque no esté la carpeta
+pub const EMBEDDED_SPHERE: &str = include_str!("../assets/sphere.obj");
+pub const EMBEDDED_SPACESHIP: &str = include_str!("../assets/nave.obj");
+pub const EMBEDDED_STATION: &str = include_str!("../assets/estacion.obj");
+
 pub struct Obj {
     meshes: Vec<Mesh>,
 }
 
+// Un objeto (`o`) o grupo (`g`) del archivo. Los atributos van por vértice
+// y los índices de a tres; un vértice sin normal la tiene en None y uno sin
+// color lo tiene blanco.
 struct Mesh {
+    name: String,
     vertices: Vec<Vec3>,
-    normals: Vec<Vec3>,
+    normals: Vec<Option<Vec3>>,
     texcoords: Vec<Vec2>,
+    colors: Vec<Color>,
     indices: Vec<u32>,
 }
 
-impl Obj {
-    pub fn load(filename: &str) -> Result<Self, tobj::LoadError> {
-        let (models, _) = tobj::load_obj(filename, &tobj::LoadOptions {
-            single_index: true,
-            triangulate: true,
-            ..Default::default()
-        })?;
-
-        let meshes = models.into_iter().map(|model| {
-            let mesh = model.mesh;
-            Mesh {
-                vertices: mesh.positions.chunks(3)
-                    .map(|v| Vec3::new(v[0], v[1], v[2]))
-                    .collect(),
-                normals: mesh.normals.chunks(3)
-                    .map(|n| Vec3::new(n[0], n[1], n[2]))
-                    .collect(),
-                texcoords: mesh.texcoords.chunks(2)
-                    .map(|t| Vec2::new(t[0], 1.0 - t[1]))
-                    .collect(),
-                indices: mesh.indices,
+impl Mesh {
+    fn new(name: &str) -> Self {
+        Mesh { name: name.to_string(), vertices: Vec::new(), normals: Vec::new(), texcoords: Vec::new(), colors: Vec::new(), indices: Vec::new() }
+    }
+
+    // Normales para los vértices que no la traen: la suma de las normales de
+    // las caras que los tocan, pesada por el área (el producto cruz sin
+    // normalizar ya es proporcional al área). Se suma por posición del
+    // archivo y grupo de suavizado, no por vértice, así una costura de UVs
+    // no deja un corte en la luz.
+    fn generate_normals(&mut self, corners: &HashMap<Corner, u32>) {
+        if self.normals.iter().all(|normal| normal.is_some()) {
+            return;
+        }
+
+        let mut keys = vec![(0, 0); self.vertices.len()];
+        for (&(position, _, _, group), &index) in corners {
+            keys[index as usize] = (position, group);
+        }
+
+        let mut sums: HashMap<(usize, u64), Vec3> = HashMap::new();
+        for triangle in self.indices.chunks(3) {
+            let [a, b, c] = [triangle[0], triangle[1], triangle[2]].map(|i| i as usize);
+            let face = (self.vertices[b] - self.vertices[a]).cross(&(self.vertices[c] - self.vertices[a]));
+            // Una cara con un vértice roto no ensucia las normales de sus
+            // vecinas; la validación la descarta después
+            if !face.iter().all(|value| value.is_finite()) {
+                continue;
+            }
+            for index in [a, b, c] {
+                if self.normals[index].is_none() {
+                    *sums.entry(keys[index]).or_insert_with(Vec3::zeros) += face;
+                }
+            }
+        }
+
+        for (normal, key) in self.normals.iter_mut().zip(&keys) {
+            if normal.is_none() {
+                // Si todas sus caras son degeneradas se queda sin normal
+                *normal = sums.get(key).filter(|sum| sum.norm() > 1e-12).map(|sum| sum.normalize());
+            }
+        }
+    }
+
+    fn validate(&mut self, validation: &mut Validation) {
+        let finite: Vec<bool> = (0..self.vertices.len()).map(|i| {
+            let normal = self.normals[i].unwrap_or(Vec3::zeros());
+            let color = self.colors[i];
+            self.vertices[i].iter().chain(normal.iter()).chain(self.texcoords[i].iter())
+                .chain([color.r(), color.g(), color.b()].iter())
+                .all(|value| value.is_finite())
+        }).collect();
+        validation.non_finite += finite.iter().filter(|&&finite| !finite).count();
+
+        let mut kept = Vec::with_capacity(self.indices.len());
+        for triangle in self.indices.chunks(3) {
+            if triangle.len() < 3 || triangle.iter().any(|&i| i as usize >= self.vertices.len()) {
+                validation.out_of_range += 1;
+                continue;
+            }
+            if triangle.iter().any(|&i| !finite[i as usize]) {
+                continue;
+            }
+            let [a, b, c] = [triangle[0], triangle[1], triangle[2]].map(|i| self.vertices[i as usize]);
+            let (ab, ac) = (b - a, c - a);
+            // Área cero o un ángulo tan chico que es una línea
+            if ab.cross(&ac).norm() <= 1e-6 * ab.norm() * ac.norm() {
+                validation.degenerate += 1;
+                continue;
+            }
+            kept.extend_from_slice(triangle);
+        }
+        self.indices = kept;
+    }
+
+    // Junta los vértices iguales a menos de `epsilon` y devuelve cuántos se
+    // juntaron. Se buscan con una grilla de celdas de ese tamaño, mirando
+    // también las vecinas para no perder los que caen justo en un borde.
+    fn weld(&mut self, epsilon: f32) -> usize {
+        let cell = |position: &Vec3| position.map(|value| (value / epsilon).floor() as i64);
+        let mut grid: HashMap<(i64, i64, i64), Vec<u32>> = HashMap::new();
+        let mut remap: Vec<u32> = Vec::with_capacity(self.vertices.len());
+        let mut welded = 0;
+
+        for index in 0..self.vertices.len() {
+            let position = self.vertices[index];
+            let base = cell(&position);
+            let same = |other: u32| {
+                let other = other as usize;
+                let normal = |i: usize| self.normals[i].unwrap_or(Vec3::zeros());
+                (self.vertices[other] - position).norm() < epsilon
+                    && (normal(other) - normal(index)).norm() < epsilon
+                    && (self.texcoords[other] - self.texcoords[index]).norm() < epsilon
+                    && same_color(self.colors[other], self.colors[index])
+            };
+
+            let mut found = None;
+            'search: for dx in -1..=1 {
+                for dy in -1..=1 {
+                    for dz in -1..=1 {
+                        let key = (base.x + dx, base.y + dy, base.z + dz);
+                        if let Some(&other) = grid.get(&key).and_then(|cell| cell.iter().find(|&&other| same(other))) {
+                            found = Some(other);
+                            break 'search;
+                        }
+                    }
+                }
+            }
+
+            match found {
+                Some(other) => {
+                    remap.push(other);
+                    welded += 1;
+                }
+                None => {
+                    remap.push(index as u32);
+                    grid.entry((base.x, base.y, base.z)).or_default().push(index as u32);
+                }
+            }
+        }
+
+        for index in &mut self.indices {
+            *index = remap[*index as usize];
+        }
+        welded
+    }
+
+    // Copia con a lo sumo `target` triángulos, juntando aristas de a una:
+    // siempre la que menos cambia la forma según la métrica de error
+    // cuadrático (Garland y Heckbert). La arista se junta sobre uno de sus
+    // extremos, así no hay que inventar normales ni UVs nuevas. Se mueven
+    // juntas todas las esquinas que comparten posición, para que las
+    // costuras de UVs y normales no se abran. Si no quedan aristas que se
+    // puedan juntar sin dar vuelta una cara, queda con más triángulos.
+    fn decimate(&self, target: usize) -> Mesh {
+        // Posiciones distintas; `position_of` es la de cada vértice
+        let mut ids: HashMap<(u32, u32, u32), usize> = HashMap::new();
+        let mut points: Vec<DVec3> = Vec::new();
+        let mut position_of: Vec<usize> = self.vertices.iter().map(|vertex| {
+            let key = (vertex.x.to_bits(), vertex.y.to_bits(), vertex.z.to_bits());
+            *ids.entry(key).or_insert_with(|| {
+                points.push(DVec3::new(vertex.x as f64, vertex.y as f64, vertex.z as f64));
+                points.len() - 1
+            })
+        }).collect();
+
+        let triangles: Vec<[usize; 3]> = self.indices.chunks(3).map(|t| [t[0] as usize, t[1] as usize, t[2] as usize]).collect();
+        let mut alive = vec![true; triangles.len()];
+        let mut live = triangles.len();
+        // Triángulos alrededor de cada posición (puede haber muertos) y vértices en ella
+        let mut around: Vec<Vec<usize>> = vec![Vec::new(); points.len()];
+        let mut corners: Vec<Vec<usize>> = vec![Vec::new(); points.len()];
+        for (vertex, &position) in position_of.iter().enumerate() {
+            corners[position].push(vertex);
+        }
+
+        let mut quadrics = vec![DMat4::zeros(); points.len()];
+        let mut edges: HashMap<(usize, usize), usize> = HashMap::new();
+        for (index, triangle) in triangles.iter().enumerate() {
+            let p = triangle.map(|vertex| position_of[vertex]);
+            let normal = (points[p[1]] - points[p[0]]).cross(&(points[p[2]] - points[p[0]]));
+            let area = normal.norm() * 0.5;
+            if area <= 0.0 {
+                alive[index] = false;
+                live -= 1;
+                continue;
+            }
+            let quadric = plane_quadric(normal / (area * 2.0), points[p[0]]) * area;
+            for k in 0..3 {
+                around[p[k]].push(index);
+                quadrics[p[k]] += quadric;
+                let edge = (p[k].min(p[(k + 1) % 3]), p[k].max(p[(k + 1) % 3]));
+                *edges.entry(edge).or_insert(0) += 1;
+            }
+        }
+
+        // Los bordes abiertos se sostienen con un plano perpendicular a la
+        // cara, pesado de más, así el contorno no se encoge
+        for (index, triangle) in triangles.iter().enumerate() {
+            let p = triangle.map(|vertex| position_of[vertex]);
+            let normal = (points[p[1]] - points[p[0]]).cross(&(points[p[2]] - points[p[0]]));
+            for k in 0..3 {
+                let (a, b) = (p[k], p[(k + 1) % 3]);
+                if !alive[index] || edges.get(&(a.min(b), a.max(b))) != Some(&1) {
+                    continue;
+                }
+                let side = (points[b] - points[a]).cross(&normal);
+                if side.norm() > 0.0 {
+                    let quadric = plane_quadric(side.normalize(), points[a]) * (BOUNDARY_WEIGHT * (points[b] - points[a]).norm_squared());
+                    quadrics[a] += quadric;
+                    quadrics[b] += quadric;
+                }
+            }
+        }
+
+        // Cada posición lleva la cuenta de sus cambios; un candidato con una
+        // cuenta vieja ya no vale
+        let mut stamps = vec![0u32; points.len()];
+        let mut heap = BinaryHeap::new();
+        let candidate = |from: usize, to: usize, quadrics: &[DMat4], stamps: &[u32]| {
+            let point = DVec4::new(points[to].x, points[to].y, points[to].z, 1.0);
+            let cost = point.dot(&((quadrics[from] + quadrics[to]) * point));
+            Collapse { cost, from, to, stamps: (stamps[from], stamps[to]) }
+        };
+        for &(a, b) in edges.keys() {
+            heap.push(candidate(a, b, &quadrics, &stamps));
+            heap.push(candidate(b, a, &quadrics, &stamps));
+        }
+
+        while live > target {
+            let Some(collapse) = heap.pop() else { break };
+            let (from, to) = (collapse.from, collapse.to);
+            if collapse.stamps != (stamps[from], stamps[to]) {
+                continue;
+            }
+            let has = |triangle: &[usize; 3], position: usize, position_of: &[usize]| triangle.iter().any(|&vertex| position_of[vertex] == position);
+
+            // No se junta si alguna cara que queda se da vuelta o se aplasta
+            let flips = around[from].iter().filter(|&&t| alive[t] && !has(&triangles[t], to, &position_of)).any(|&t| {
+                let at = |vertex: usize, moved: bool| if moved && position_of[vertex] == from { points[to] } else { points[position_of[vertex]] };
+                let [a, b, c] = triangles[t];
+                let before = (at(b, false) - at(a, false)).cross(&(at(c, false) - at(a, false)));
+                let after = (at(b, true) - at(a, true)).cross(&(at(c, true) - at(a, true)));
+                after.norm() <= 1e-12 || before.normalize().dot(&after.normalize()) < 0.2
+            });
+            if flips {
+                continue;
             }
+
+            let moved = std::mem::take(&mut around[from]);
+            for t in moved {
+                if !alive[t] {
+                    continue;
+                }
+                if has(&triangles[t], to, &position_of) {
+                    alive[t] = false;
+                    live -= 1;
+                } else {
+                    around[to].push(t);
+                }
+            }
+            for vertex in std::mem::take(&mut corners[from]) {
+                position_of[vertex] = to;
+                corners[to].push(vertex);
+            }
+            let merged = quadrics[from];
+            quadrics[to] += merged;
+            stamps[from] += 1;
+            stamps[to] += 1;
+
+            around[to].retain(|&t| alive[t]);
+            let mut neighbors: Vec<usize> = around[to].iter()
+                .flat_map(|&t| triangles[t].map(|vertex| position_of[vertex]))
+                .filter(|&position| position != to)
+                .collect();
+            neighbors.sort_unstable();
+            neighbors.dedup();
+            for neighbor in neighbors {
+                heap.push(candidate(to, neighbor, &quadrics, &stamps));
+                heap.push(candidate(neighbor, to, &quadrics, &stamps));
+            }
+        }
+
+        let mut mesh = Mesh::new(&self.name);
+        mesh.vertices = position_of.iter().map(|&position| {
+            let point = points[position];
+            Vec3::new(point.x as f32, point.y as f32, point.z as f32)
         }).collect();
+        mesh.normals = self.normals.clone();
+        mesh.texcoords = self.texcoords.clone();
+        mesh.colors = self.colors.clone();
+        mesh.indices = triangles.iter().zip(&alive)
+            .filter(|(_, &alive)| alive)
+            .flat_map(|(triangle, _)| triangle.map(|vertex| vertex as u32))
+            .collect();
+        mesh
+    }
+
+    // Un paso de subdivisión de Loop: cada triángulo en cuatro, con los
+    // vértices nuevos en las aristas y los viejos corridos hacia sus vecinos,
+    // así la superficie se alisa en vez de solo tener más caras. Como en
+    // `decimate`, la forma se calcula con las posiciones compartidas; las
+    // normales y UVs de los vértices nuevos son el promedio de los de su
+    // arista, así las costuras se mantienen. Lo mismo para los colores.
+    fn subdivide(&self) -> Mesh {
+        let mut ids: HashMap<(u32, u32, u32), usize> = HashMap::new();
+        let mut points: Vec<Vec3> = Vec::new();
+        let position_of: Vec<usize> = self.vertices.iter().map(|vertex| {
+            let key = (vertex.x.to_bits(), vertex.y.to_bits(), vertex.z.to_bits());
+            *ids.entry(key).or_insert_with(|| {
+                points.push(*vertex);
+                points.len() - 1
+            })
+        }).collect();
+
+        // Vértices opuestos a cada arista: dos adentro, uno en un borde
+        let triangles: Vec<[usize; 3]> = self.indices.chunks(3).map(|t| [t[0] as usize, t[1] as usize, t[2] as usize]).collect();
+        let mut opposite: HashMap<(usize, usize), Vec<usize>> = HashMap::new();
+        for triangle in &triangles {
+            let p = triangle.map(|vertex| position_of[vertex]);
+            for k in 0..3 {
+                let (a, b) = (p[k], p[(k + 1) % 3]);
+                opposite.entry((a.min(b), a.max(b))).or_default().push(p[(k + 2) % 3]);
+            }
+        }
+
+        let mut neighbors: Vec<Vec<usize>> = vec![Vec::new(); points.len()];
+        let mut boundary: Vec<Vec<usize>> = vec![Vec::new(); points.len()];
+        for (&(a, b), others) in &opposite {
+            neighbors[a].push(b);
+            neighbors[b].push(a);
+            if others.len() == 1 {
+                boundary[a].push(b);
+                boundary[b].push(a);
+            }
+        }
+
+        // Los viejos: en el borde solo cuentan sus vecinos del borde
+        let moved: Vec<Vec3> = (0..points.len()).map(|i| {
+            let point = points[i];
+            if boundary[i].len() == 2 {
+                return point * 0.75 + (points[boundary[i][0]] + points[boundary[i][1]]) * 0.125;
+            }
+            if !boundary[i].is_empty() || neighbors[i].is_empty() {
+                return point;
+            }
+            let n = neighbors[i].len() as f32;
+            let beta = if neighbors[i].len() == 3 { 3.0 / 16.0 } else { 3.0 / (8.0 * n) };
+            let sum = neighbors[i].iter().fold(Vec3::zeros(), |sum, &j| sum + points[j]);
+            point * (1.0 - n * beta) + sum * beta
+        }).collect();
+        let edge_point = |a: usize, b: usize| match opposite[&(a.min(b), a.max(b))].as_slice() {
+            &[c, d] => (points[a] + points[b]) * 0.375 + (points[c] + points[d]) * 0.125,
+            _ => (points[a] + points[b]) * 0.5,
+        };
+
+        let mut mesh = Mesh::new(&self.name);
+        mesh.vertices = position_of.iter().map(|&position| moved[position]).collect();
+        mesh.normals = self.normals.clone();
+        mesh.texcoords = self.texcoords.clone();
+        mesh.colors = self.colors.clone();
+        // Vértice nuevo de cada arista, por los dos vértices (con sus atributos) que la cierran
+        let mut midpoints: HashMap<(usize, usize), u32> = HashMap::new();
+        for &[a, b, c] in &triangles {
+            let mut midpoint = |u: usize, v: usize| *midpoints.entry((u.min(v), u.max(v))).or_insert_with(|| {
+                mesh.vertices.push(edge_point(position_of[u], position_of[v]));
+                let normal = self.normals[u].zip(self.normals[v]).map(|(nu, nv)| (nu + nv).normalize());
+                mesh.normals.push(normal.filter(|normal| normal.x.is_finite()));
+                mesh.texcoords.push((self.texcoords[u] + self.texcoords[v]) * 0.5);
+                mesh.colors.push(self.colors[u].lerp(&self.colors[v], 0.5));
+                mesh.vertices.len() as u32 - 1
+            });
+            let (ab, bc, ca) = (midpoint(a, b), midpoint(b, c), midpoint(c, a));
+            let [a, b, c] = [a, b, c].map(|vertex| vertex as u32);
+            mesh.indices.extend_from_slice(&[a, ab, ca, ab, b, bc, ca, bc, c, ab, bc, ca]);
+        }
+        mesh
+    }
+
+    fn push_vertices(&self, vertices: &mut Vec<Vertex>) {
+        for &index in &self.indices {
+            let index = index as usize;
+            let normal = self.normals[index].unwrap_or(Vec3::new(0.0, 1.0, 0.0));
+            vertices.push(Vertex { color: self.colors[index], ..Vertex::new(self.vertices[index], normal, self.texcoords[index]) });
+        }
+    }
+}
+
+// Índices de posición, UV y normal de una esquina de cara, y con qué otras
+// esquinas se promedia la normal si hay que calcularla (ver `smoothing_key`)
+type Corner = (usize, Option<usize>, Option<usize>, u64);
+
+// Lo que se va juntando mientras se lee el archivo. Las posiciones, UVs y
+// normales son del archivo entero; cada malla tiene sus propios vértices,
+// uno por cada combinación distinta de índices que usan sus caras.
+struct Parser<'a> {
+    path: &'a str,
+    line: usize,
+    positions: Vec<Vec3>,
+    // Uno por posición: el de `v x y z r g b`, o blanco
+    colors: Vec<Color>,
+    texcoords: Vec<Vec2>,
+    normals: Vec<Vec3>,
+    meshes: Vec<Mesh>,
+    // Vértices ya creados de cada malla
+    corners: Vec<HashMap<Corner, u32>>,
+    current: usize,
+    // Grupo de suavizado de `s`: None con `s off` o `s 0`. Antes del primer
+    // `s` se suaviza todo junto.
+    smoothing: Option<u32>,
+    faces: u64,
+}
 
+impl<'a> Parser<'a> {
+    fn new(path: &'a str) -> Self {
+        Parser {
+            path,
+            line: 0,
+            positions: Vec::new(),
+            colors: Vec::new(),
+            texcoords: Vec::new(),
+            normals: Vec::new(),
+            meshes: vec![Mesh::new("")],
+            corners: vec![HashMap::new()],
+            current: 0,
+            smoothing: Some(0),
+            faces: 0,
+        }
+    }
+
+    fn error(&self, message: String) -> ObjError {
+        ObjError::Parse { path: self.path.to_string(), line: self.line, message }
+    }
+
+    // `count` números de `fields`; los que faltan a partir de `required` valen 0
+    fn floats(&self, fields: &[&str], required: usize, count: usize) -> Result<Vec<f32>, ObjError> {
+        if fields.len() < required {
+            return Err(self.error(format!("se esperaban {} números", required)));
+        }
+        (0..count).map(|i| match fields.get(i) {
+            Some(field) => field.parse().map_err(|_| self.error(format!("número inválido '{}'", field))),
+            None => Ok(0.0),
+        }).collect()
+    }
+
+    // Índice de OBJ (desde 1, o negativo contando desde el último) a uno desde 0
+    fn resolve(&self, field: &str, count: usize, what: &str) -> Result<usize, ObjError> {
+        let index: i64 = field.parse().map_err(|_| self.error(format!("índice inválido '{}'", field)))?;
+        let resolved = if index < 0 { count as i64 + index } else { index - 1 };
+        if index == 0 || resolved < 0 || resolved >= count as i64 {
+            return Err(self.error(format!("índice de {} {} fuera de rango (hay {})", what, index, count)));
+        }
+        Ok(resolved as usize)
+    }
+
+    // Pasa a la malla `name`: si la actual todavía no tiene caras solo se
+    // renombra, y si el nombre ya apareció antes se sigue agregando a esa
+    fn switch_to(&mut self, name: &str) {
+        if let Some(index) = self.meshes.iter().position(|mesh| mesh.name == name) {
+            self.current = index;
+        } else if self.meshes[self.current].indices.is_empty() {
+            self.meshes[self.current].name = name.to_string();
+        } else {
+            self.meshes.push(Mesh::new(name));
+            self.corners.push(HashMap::new());
+            self.current = self.meshes.len() - 1;
+        }
+    }
+
+    // Una esquina de cara: `v`, `v/vt`, `v//vn` o `v/vt/vn`
+    fn corner(&mut self, field: &str) -> Result<u32, ObjError> {
+        let mut parts = field.split('/');
+        let position = self.resolve(parts.next().unwrap_or(""), self.positions.len(), "posición")?;
+        let texcoord = match parts.next() {
+            Some("") | None => None,
+            Some(part) => Some(self.resolve(part, self.texcoords.len(), "UV")?),
+        };
+        let normal = match parts.next() {
+            Some("") | None => None,
+            Some(part) => Some(self.resolve(part, self.normals.len(), "normal")?),
+        };
+        if parts.next().is_some() {
+            return Err(self.error(format!("esquina inválida '{}'", field)));
+        }
+
+        // Las normales del archivo no se tocan, así que ahí no importa el grupo
+        let group = if normal.is_some() { 0 } else { self.smoothing_key() };
+        let key = (position, texcoord, normal, group);
+        if let Some(&index) = self.corners[self.current].get(&key) {
+            return Ok(index);
+        }
+        let mesh = &mut self.meshes[self.current];
+        let index = mesh.vertices.len() as u32;
+        mesh.vertices.push(self.positions[position]);
+        mesh.colors.push(self.colors[position]);
+        mesh.texcoords.push(texcoord.map_or(Vec2::zeros(), |t| self.texcoords[t]));
+        mesh.normals.push(normal.map(|n| self.normals[n]));
+        self.corners[self.current].insert(key, index);
+        Ok(index)
+    }
+
+    // Las esquinas sin normal con la misma posición y la misma clave se
+    // promedian. Con el suavizado apagado cada cara tiene su propia clave y
+    // queda plana.
+    fn smoothing_key(&self) -> u64 {
+        match self.smoothing {
+            Some(group) => group as u64,
+            None => (1 << 32) + self.faces,
+        }
+    }
+
+    fn statement(&mut self, keyword: &str, fields: &[&str]) -> Result<(), ObjError> {
+        match keyword {
+            "v" => {
+                // Con seis números los últimos tres son un color de 0 a 1
+                // (lo exportan Blender y MeshLab); con cuatro, el cuarto es
+                // w y no se usa
+                let v = self.floats(fields, 3, 3)?;
+                self.positions.push(Vec3::new(v[0], v[1], v[2]));
+                let color = if fields.len() >= 6 {
+                    let c = self.floats(&fields[3..], 3, 3)?;
+                    Color::from_rgb_f32(c[0], c[1], c[2])
+                } else {
+                    Color::white()
+                };
+                self.colors.push(color);
+            }
+            "vt" => {
+                let t = self.floats(fields, 1, 2)?;
+                self.texcoords.push(Vec2::new(t[0], 1.0 - t[1]));
+            }
+            "vn" => {
+                let n = self.floats(fields, 3, 3)?;
+                self.normals.push(Vec3::new(n[0], n[1], n[2]));
+            }
+            "f" => {
+                if fields.len() < 3 {
+                    return Err(self.error("una cara necesita al menos tres vértices".to_string()));
+                }
+                let face = fields.iter().map(|field| self.corner(field)).collect::<Result<Vec<u32>, _>>()?;
+                fan(&face, &mut self.meshes[self.current].indices);
+                self.faces += 1;
+            }
+            "o" | "g" => self.switch_to(&fields.join(" ")),
+            "s" => {
+                self.smoothing = match fields.first() {
+                    Some(&"off") | Some(&"0") => None,
+                    Some(group) => Some(group.parse().map_err(|_| self.error(format!("grupo de suavizado inválido '{}'", group)))?),
+                    None => return Err(self.error("falta el grupo de suavizado".to_string())),
+                };
+            }
+            // Materiales, líneas, puntos y lo demás no se usan
+            _ => {}
+        }
+        Ok(())
+    }
+}
+
+impl Obj {
+    pub fn load(filename: &str) -> Result<Self, ObjError> {
+        let source = std::fs::read_to_string(filename).map_err(|_| ObjError::Open(filename.to_string()))?;
+        Obj::parse(filename, &source)
+    }
+
+    // Lee un OBJ que ya está en memoria; `name` es solo para los errores
+    pub fn parse(name: &str, source: &str) -> Result<Self, ObjError> {
+        let mut parser = Parser::new(name);
+        let mut pending = String::new();
+
+        for (number, line) in source.lines().enumerate() {
+            // Un `\` al final sigue en la línea de abajo; el error se marca en la primera
+            if pending.is_empty() {
+                parser.line = number + 1;
+            }
+            let line = line.split('#').next().unwrap_or("").trim_end();
+            if let Some(start) = line.strip_suffix('\\') {
+                pending.push_str(start);
+                pending.push(' ');
+                continue;
+            }
+            pending.push_str(line);
+
+            let fields: Vec<&str> = pending.split_whitespace().collect();
+            if let Some((keyword, fields)) = fields.split_first() {
+                parser.statement(keyword, fields)?;
+            }
+            pending.clear();
+        }
+
+        for (mesh, corners) in parser.meshes.iter_mut().zip(&parser.corners) {
+            mesh.generate_normals(corners);
+        }
+        let meshes: Vec<Mesh> = parser.meshes.into_iter().filter(|mesh| !mesh.indices.is_empty()).collect();
+        if meshes.is_empty() {
+            return Err(ObjError::Empty(name.to_string()));
+        }
         Ok(Obj { meshes })
     }
 
-    pub fn get_vertex_array(&self) -> Vec<Vertex> {
+    // Esfera UV del radio de assets/sphere.obj, para cuando no se puede cargar
+    pub fn sphere(rings: u32, segments: u32) -> Self {
+        let radius = 0.51;
+        let mut mesh = Mesh::new("esfera");
+
+        for ring in 0..=rings {
+            let v = ring as f32 / rings as f32;
+            let (sin_theta, cos_theta) = (v * PI).sin_cos();
+            for segment in 0..=segments {
+                let u = segment as f32 / segments as f32;
+                let (sin_phi, cos_phi) = (u * 2.0 * PI).sin_cos();
+                let normal = Vec3::new(sin_theta * cos_phi, cos_theta, sin_theta * sin_phi);
+                mesh.vertices.push(normal * radius);
+                mesh.normals.push(Some(normal));
+                mesh.texcoords.push(Vec2::new(u, v));
+                mesh.colors.push(Color::white());
+            }
+        }
+
+        let row = segments + 1;
+        for ring in 0..rings {
+            for segment in 0..segments {
+                let a = ring * row + segment;
+                let b = a + row;
+                mesh.indices.extend_from_slice(&[a, a + 1, b, a + 1, b + 1, b]);
+            }
+        }
+
+        Obj { meshes: vec![mesh] }
+    }
+
+    // Cubo centrado en el origen, con una normal por cara; hace de modelo
+    // provisional para la nave y la estación
+    pub fn cube(half_size: f32) -> Self {
+        let mut mesh = Mesh::new("cubo");
+        let axes = [Vec3::x(), Vec3::y(), Vec3::z()];
+
+        for (i, axis) in axes.iter().enumerate() {
+            for sign in [1.0, -1.0] {
+                let normal = axis * sign;
+                // Dos ejes sobre la cara, en el orden que la deja mirando hacia afuera
+                let u = axes[(i + 1) % 3] * sign;
+                let v = axes[(i + 2) % 3];
+                let base = mesh.vertices.len() as u32;
+                for (s, t) in [(-1.0, -1.0), (1.0, -1.0), (1.0, 1.0), (-1.0, 1.0)] {
+                    mesh.vertices.push((normal + u * s + v * t) * half_size);
+                    mesh.normals.push(Some(normal));
+                    mesh.texcoords.push(Vec2::new((s + 1.0) * 0.5, (t + 1.0) * 0.5));
+                    mesh.colors.push(Color::white());
+                }
+                mesh.indices.extend_from_slice(&[base, base + 1, base + 2, base, base + 2, base + 3]);
+            }
+        }
+
+        Obj { meshes: vec![mesh] }
+    }
+
+    // Limpia lo que haría fallar al pipeline: triángulos de área cero (no
+    // dejan fragmentos útiles y sus normales salen con NaN), vértices que no
+    // son finitos e índices fuera de rango. Con `weld` además junta los
+    // vértices que están a menos de esa distancia y tienen la misma normal,
+    // UV y color, así las mallas quedan conectadas para simplificarlas.
+    pub fn validate(&mut self, weld: Option<f32>) -> Validation {
+        let mut validation = Validation::default();
+        for mesh in &mut self.meshes {
+            mesh.validate(&mut validation);
+            if let Some(epsilon) = weld {
+                validation.welded += mesh.weld(epsilon);
+            }
+        }
+        validation
+    }
+
+    // Versión simplificada con más o menos `ratio` de los triángulos de cada
+    // malla, para verla de lejos
+    pub fn decimate(&self, ratio: f32) -> Obj {
+        let meshes = self.meshes.iter().map(|mesh| {
+            let target = ((mesh.indices.len() / 3) as f32 * ratio).ceil() as usize;
+            mesh.decimate(target)
+        }).collect();
+        Obj { meshes }
+    }
+
+    // Un paso de subdivisión de Loop: cuatro veces los triángulos y una
+    // superficie más suave, para verla de muy cerca
+    pub fn subdivide(&self) -> Obj {
+        Obj { meshes: self.meshes.iter().map(Mesh::subdivide).collect() }
+    }
+
+    // Pinta cada vértice con `paint(posición, normal)` multiplicado por el
+    // color que ya tenía: para hornear sombras o degradés en el modelo en vez
+    // de calcularlos en el shader en cada píxel
+    pub fn paint(&mut self, paint: impl Fn(Vec3, Vec3) -> Color) {
+        for mesh in &mut self.meshes {
+            for i in 0..mesh.vertices.len() {
+                let normal = mesh.normals[i].unwrap_or(Vec3::new(0.0, 1.0, 0.0));
+                mesh.colors[i] = mesh.colors[i] * paint(mesh.vertices[i], normal);
+            }
+        }
+    }
+
+    // Nombres de los objetos y grupos, en el orden del archivo. Las caras
+    // de antes del primer `o` o `g` quedan en una malla sin nombre.
+    pub fn mesh_names(&self) -> Vec<&str> {
+        self.meshes.iter().map(|mesh| mesh.name.as_str()).collect()
+    }
+
+    // Triángulos de una sola malla, por nombre
+    pub fn get_mesh_vertex_array(&self, name: &str) -> Option<Vec<Vertex>> {
+        let mesh = self.meshes.iter().find(|mesh| mesh.name == name)?;
         let mut vertices = Vec::new();
+        mesh.push_vertices(&mut vertices);
+        Some(vertices)
+    }
 
+    // Triángulos de todas las mallas juntas
+    pub fn get_vertex_array(&self) -> Vec<Vertex> {
+        let mut vertices = Vec::new();
         for mesh in &self.meshes {
-            for &index in &mesh.indices {
-                let position = mesh.vertices[index as usize];
-                let normal = mesh.normals.get(index as usize)
-                    .cloned()
-                    .unwrap_or(Vec3::new(0.0, 1.0, 0.0));
-                let tex_coords = mesh.texcoords.get(index as usize)
-                    .cloned()
-                    .unwrap_or(Vec2::new(0.0, 0.0));
+            mesh.push_vertices(&mut vertices);
+        }
+        vertices
+    }
+}
 
-                vertices.push(Vertex::new(position, normal, tex_coords));
+// Colores que se ven iguales en 8 bits
+fn same_color(a: Color, b: Color) -> bool {
+    (a.r() - b.r()).abs() < 0.5 / 255.0 && (a.g() - b.g()).abs() < 0.5 / 255.0 && (a.b() - b.b()).abs() < 0.5 / 255.0
+}
+
+// Cuánto más pesan los planos de los bordes abiertos que los de las caras
+const BOUNDARY_WEIGHT: f64 = 100.0;
+
+// Matriz de error del plano con esa normal unitaria que pasa por `point`:
+// da la distancia al cuadrado de cualquier punto al plano
+fn plane_quadric(normal: DVec3, point: DVec3) -> DMat4 {
+    let plane = DVec4::new(normal.x, normal.y, normal.z, -normal.dot(&point));
+    plane * plane.transpose()
+}
+
+// Candidato a juntar la posición `from` sobre `to`. El montón saca primero
+// el de menor costo.
+struct Collapse {
+    cost: f64,
+    from: usize,
+    to: usize,
+    stamps: (u32, u32),
+}
+
+impl PartialEq for Collapse {
+    fn eq(&self, other: &Self) -> bool {
+        self.cost.total_cmp(&other.cost) == Ordering::Equal
+    }
+}
+
+impl Eq for Collapse {}
+
+impl PartialOrd for Collapse {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Collapse {
+    fn cmp(&self, other: &Self) -> Ordering {
+        other.cost.total_cmp(&self.cost)
+    }
+}
+
+// Abanico desde el primer vértice: (0, 1, 2), (0, 2, 3)... Alcanza para los
+// cuadriláteros y polígonos convexos que exportan los programas de modelado,
+// y mantiene el sentido de giro de la cara. Las caras de menos de tres
+// vértices no dejan nada.
+fn fan(face: &[u32], triangles: &mut Vec<u32>) {
+    for i in 1..face.len().saturating_sub(1) {
+        triangles.extend_from_slice(&[face[0], face[i], face[i + 1]]);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TRIANGLE: &str = "v 0 0 0\nv 1 0 0\nv 0 1 0\n";
+
+    fn parse(source: &str) -> Obj {
+        Obj::parse("prueba.obj", source).unwrap_or_else(|err| panic!("{}", err))
+    }
+
+    fn parse_error(source: &str) -> (usize, String) {
+        match Obj::parse("prueba.obj", source) {
+            Err(ObjError::Parse { line, message, .. }) => (line, message),
+            Err(err) => panic!("se esperaba un error de línea: {}", err),
+            Ok(_) => panic!("se esperaba un error"),
+        }
+    }
+
+    #[test]
+    fn negative_indices_count_from_the_last_vertex() {
+        let obj = parse(&format!("{}f -3 -2 -1\n", TRIANGLE));
+        let positions: Vec<Vec3> = obj.get_vertex_array().iter().map(|vertex| vertex.position).collect();
+        assert_eq!(positions, vec![Vec3::new(0.0, 0.0, 0.0), Vec3::new(1.0, 0.0, 0.0), Vec3::new(0.0, 1.0, 0.0)]);
+    }
+
+    #[test]
+    fn corners_with_normal_and_no_uv() {
+        let obj = parse(&format!("{}vn 0 0 -1\nf 1//1 2//1 3//1\n", TRIANGLE));
+        for vertex in obj.get_vertex_array() {
+            assert_eq!(vertex.normal, Vec3::new(0.0, 0.0, -1.0));
+            assert_eq!(vertex.tex_coords, Vec2::zeros());
+        }
+    }
+
+    #[test]
+    fn groups_resume_an_earlier_name() {
+        let obj = parse(&format!("{}v 1 1 0\ng a\nf 1 2 3\ng b\nf 2 4 3\ng a\nf 1 2 4\n", TRIANGLE));
+        assert_eq!(obj.mesh_names(), vec!["a", "b"]);
+        assert_eq!(obj.get_mesh_vertex_array("a").map(|vertices| vertices.len()), Some(6));
+        assert_eq!(obj.get_mesh_vertex_array("b").map(|vertices| vertices.len()), Some(3));
+    }
+
+    #[test]
+    fn backslash_continues_the_line() {
+        let obj = parse(&format!("{}f 1 2 \\\n  3\n", TRIANGLE));
+        assert_eq!(obj.get_vertex_array().len(), 3);
+    }
+
+    #[test]
+    fn errors_report_the_line() {
+        assert_eq!(parse_error("v 0 0 0\n# comentario\nv 1 0\n").0, 3);
+        let (line, message) = parse_error(&format!("{}f 1 2 7\n", TRIANGLE));
+        assert_eq!(line, 4);
+        assert!(message.contains("fuera de rango"), "{}", message);
+        // Una línea continuada se marca en la primera
+        assert_eq!(parse_error(&format!("{}f 1 2 \\\n  x\n", TRIANGLE)).0, 4);
+    }
+
+    #[test]
+    fn a_file_without_faces_is_empty() {
+        assert!(matches!(Obj::parse("prueba.obj", TRIANGLE), Err(ObjError::Empty(_))));
+        assert!(matches!(Obj::parse("prueba.obj", ""), Err(ObjError::Empty(_))));
+    }
+
+    // Posiciones 2 y 6 iguales, 1-2-4 en línea y la 5 con NaN
+    const BROKEN: &str = "v 0 0 0\nv 1 0 0\nv 0 1 0\nv 2 0 0\nv nan 0 0\nv 1 0 0\nv 1 1 0\n";
+
+    #[test]
+    fn validate_drops_broken_triangles_and_welds_duplicates() {
+        let mut obj = parse(&format!("{}vn 0 0 1\nf 1//1 2//1 3//1\nf 1//1 2//1 4//1\nf 1//1 5//1 3//1\nf 6//1 7//1 3//1\n", BROKEN));
+        let validation = obj.validate(Some(1e-5));
+        assert_eq!(validation.degenerate, 1);
+        assert_eq!(validation.non_finite, 1);
+        assert_eq!(validation.out_of_range, 0);
+        assert_eq!(validation.welded, 1);
+        assert!(validation.has_problems());
+        // El segundo triángulo que queda usa el vértice 1 en lugar de su copia
+        assert_eq!(obj.meshes[0].indices, vec![0, 1, 2, 1, 6, 2]);
+    }
+
+    #[test]
+    fn nan_vertex_does_not_spoil_generated_normals() {
+        let mut obj = parse(&format!("{}f 1 2 3\nf 1 5 3\n", BROKEN));
+        let validation = obj.validate(None);
+        assert_eq!(validation.non_finite, 1);
+        assert_eq!(obj.meshes[0].indices, vec![0, 1, 2]);
+        assert_eq!(obj.get_vertex_array()[0].normal, Vec3::new(0.0, 0.0, 1.0));
+    }
+
+    #[test]
+    fn validate_reports_indices_out_of_range() {
+        let mut mesh = Mesh::new("");
+        for position in [Vec3::zeros(), Vec3::x(), Vec3::y()] {
+            mesh.vertices.push(position);
+            mesh.normals.push(Some(Vec3::z()));
+            mesh.texcoords.push(Vec2::zeros());
+            mesh.colors.push(Color::white());
+        }
+        mesh.indices = vec![0, 1, 2, 0, 1, 9, 2, 1];
+        let mut obj = Obj { meshes: vec![mesh] };
+        let validation = obj.validate(None);
+        assert_eq!(validation.out_of_range, 2);
+        assert!(validation.has_problems());
+        assert_eq!(obj.meshes[0].indices, vec![0, 1, 2]);
+    }
+
+    // Cuadrado plano de `n` x `n` celdas, abierto en los cuatro lados
+    fn grid(n: u32) -> Obj {
+        let mut mesh = Mesh::new("grilla");
+        for y in 0..=n {
+            for x in 0..=n {
+                mesh.vertices.push(Vec3::new(x as f32 / n as f32, y as f32 / n as f32, 0.0));
+                mesh.normals.push(Some(Vec3::z()));
+                mesh.texcoords.push(Vec2::zeros());
+                mesh.colors.push(Color::white());
             }
         }
+        for y in 0..n {
+            for x in 0..n {
+                let a = y * (n + 1) + x;
+                let b = a + n + 1;
+                mesh.indices.extend_from_slice(&[a, a + 1, b + 1, a, b + 1, b]);
+            }
+        }
+        Obj { meshes: vec![mesh] }
+    }
 
-        vertices
+    fn triangles(obj: &Obj) -> usize {
+        obj.meshes.iter().map(|mesh| mesh.indices.len() / 3).sum()
+    }
+
+    fn assert_indices_in_range(obj: &Obj) {
+        for mesh in &obj.meshes {
+            assert_eq!(mesh.indices.len() % 3, 0);
+            assert!(mesh.indices.iter().all(|&index| (index as usize) < mesh.vertices.len()));
+        }
+    }
+
+    // Aristas que usa un solo triángulo, por posición
+    fn boundary_edges(mesh: &Mesh) -> Vec<(Vec3, Vec3)> {
+        let key = |v: Vec3| (v.x.to_bits(), v.y.to_bits(), v.z.to_bits());
+        let mut uses: HashMap<_, (usize, Vec3, Vec3)> = HashMap::new();
+        for triangle in mesh.indices.chunks(3) {
+            for k in 0..3 {
+                let (a, b) = (mesh.vertices[triangle[k] as usize], mesh.vertices[triangle[(k + 1) % 3] as usize]);
+                let edge = if key(a) < key(b) { (key(a), key(b)) } else { (key(b), key(a)) };
+                uses.entry(edge).or_insert((0, a, b)).0 += 1;
+            }
+        }
+        uses.into_values().filter(|&(count, _, _)| count == 1).map(|(_, a, b)| (a, b)).collect()
+    }
+
+    #[test]
+    fn decimate_reaches_the_target() {
+        let sphere = Obj::sphere(16, 32);
+        for ratio in [0.5, 0.25, 0.1] {
+            let target = (triangles(&sphere) as f32 * ratio).ceil() as usize;
+            let simplified = sphere.decimate(ratio);
+            assert!(triangles(&simplified) <= target, "{} triángulos para {}", triangles(&simplified), target);
+            assert!(triangles(&simplified) >= target - 2, "{} triángulos para {}", triangles(&simplified), target);
+            assert_indices_in_range(&simplified);
+        }
+
+        let cube = Obj::cube(1.0);
+        let simplified = cube.decimate(0.5);
+        assert!(triangles(&simplified) <= 6);
+        assert_indices_in_range(&simplified);
+        assert!(boundary_edges(&simplified.meshes[0]).is_empty());
+    }
+
+    #[test]
+    fn decimate_keeps_the_open_border() {
+        let simplified = grid(8).decimate(0.25);
+        assert!(triangles(&simplified) <= 32);
+        assert_indices_in_range(&simplified);
+
+        // El contorno sigue siendo el del cuadrado: todas las aristas de
+        // borde están sobre sus lados y suman su perímetro
+        let on_side = |v: Vec3| v.x.abs() < 1e-5 || v.y.abs() < 1e-5 || (v.x - 1.0).abs() < 1e-5 || (v.y - 1.0).abs() < 1e-5;
+        let border = boundary_edges(&simplified.meshes[0]);
+        assert!(border.iter().all(|&(a, b)| on_side(a) && on_side(b) && on_side((a + b) * 0.5)));
+        let perimeter: f32 = border.iter().map(|(a, b)| (b - a).norm()).sum();
+        assert!((perimeter - 4.0).abs() < 1e-4, "perímetro {}", perimeter);
+    }
+
+    // Octaedro cerrado con un vértice por posición: 6 vértices, 12 aristas
+    fn octahedron() -> Obj {
+        let mut mesh = Mesh::new("octaedro");
+        for position in [Vec3::x(), -Vec3::x(), Vec3::y(), -Vec3::y(), Vec3::z(), -Vec3::z()] {
+            mesh.vertices.push(position);
+            mesh.normals.push(Some(position));
+            mesh.texcoords.push(Vec2::zeros());
+            mesh.colors.push(Color::white());
+        }
+        for (a, b) in [(0, 2), (2, 1), (1, 3), (3, 0)] {
+            mesh.indices.extend_from_slice(&[a, b, 4, b, a, 5]);
+        }
+        Obj { meshes: vec![mesh] }
+    }
+
+    #[test]
+    fn subdivide_splits_each_triangle_in_four() {
+        for obj in [octahedron(), Obj::cube(1.0), Obj::sphere(8, 16)] {
+            let refined = obj.subdivide();
+            assert_eq!(triangles(&refined), triangles(&obj) * 4);
+            assert_indices_in_range(&refined);
+        }
+    }
+
+    #[test]
+    fn subdivide_shares_edge_midpoints() {
+        // En una malla cerrada quedan V + E vértices y sigue sin bordes
+        let refined = octahedron().subdivide();
+        assert_eq!(refined.meshes[0].vertices.len(), 6 + 12);
+        assert!(boundary_edges(&refined.meshes[0]).is_empty());
+
+        // El cubo tiene vértices repetidos por cara, pero las posiciones se
+        // comparten igual: 8 esquinas y 18 aristas
+        let refined = Obj::cube(1.0).subdivide();
+        let mut positions: Vec<_> = refined.meshes[0].vertices.iter().map(|v| (v.x.to_bits(), v.y.to_bits(), v.z.to_bits())).collect();
+        positions.sort_unstable();
+        positions.dedup();
+        assert_eq!(positions.len(), 8 + 18);
+        assert!(boundary_edges(&refined.meshes[0]).is_empty());
+    }
+
+    #[test]
+    fn subdivide_uses_the_loop_weights() {
+        let refined = octahedron().subdivide();
+        let vertices = &refined.meshes[0].vertices;
+        // Un vértice viejo de valencia 4 va hacia sus vecinos, que acá suman cero
+        assert!((vertices[0] - Vec3::x() * 0.625).norm() < 1e-6);
+        // El de la arista x-y: 3/8 de sus extremos y 1/8 de los opuestos (±z)
+        assert!(vertices.iter().any(|v| (v - Vec3::new(0.375, 0.375, 0.0)).norm() < 1e-6));
+    }
+
+    #[test]
+    fn clean_mesh_has_no_problems() {
+        let mut obj = Obj::sphere(8, 16);
+        let validation = obj.validate(Some(1e-5));
+        assert_eq!((validation.non_finite, validation.out_of_range), (0, 0));
+        assert!(!validation.has_problems());
     }
 }
@@ -0,0 +1,177 @@
+use std::time::{Duration, Instant};
+
+// Cuadros por segundo a los que se limita el ciclo principal
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum TargetFps {
+    Thirty,
+    Sixty,
+    Uncapped,
+}
+
+impl TargetFps {
+    pub fn next(self) -> Self {
+        match self {
+            TargetFps::Thirty => TargetFps::Sixty,
+            TargetFps::Sixty => TargetFps::Uncapped,
+            TargetFps::Uncapped => TargetFps::Thirty,
+        }
+    }
+
+    pub fn previous(self) -> Self {
+        self.next().next()
+    }
+
+    // Lo que tiene que durar cada cuadro; sin límite no hay espera
+    pub fn frame_time(self) -> Option<Duration> {
+        match self {
+            TargetFps::Thirty => Some(Duration::from_secs(1) / 30),
+            TargetFps::Sixty => Some(Duration::from_secs(1) / 60),
+            TargetFps::Uncapped => None,
+        }
+    }
+
+    // Cómo se escribe en el archivo de opciones
+    pub fn name(self) -> &'static str {
+        match self {
+            TargetFps::Thirty => "30",
+            TargetFps::Sixty => "60",
+            TargetFps::Uncapped => "libre",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        [TargetFps::Thirty, TargetFps::Sixty, TargetFps::Uncapped].into_iter().find(|target| target.name() == name)
+    }
+}
+
+// Limitador de cuadros: al final de cada cuadro duerme lo que falte para el
+// objetivo, descontando lo que tardó el cuadro. Los plazos se encadenan, así
+// que lo que se pasa un `sleep` se recupera en el cuadro siguiente. Un cuadro
+// que termina después de su plazo cuenta como perdido y el plazo se corre a
+// ahora, sin intentar alcanzar los atrasados.
+pub struct FrameLimiter {
+    target: TargetFps,
+    deadline: Instant,
+    frame_start: Instant,
+    // Cuenta del segundo en curso
+    second_start: Instant,
+    frames: u32,
+    missed: u32,
+    // Lo medido en el último segundo completo
+    pub fps: u32,
+    pub missed_per_second: u32,
+    // Lo que trabajó el último cuadro, sin contar la espera
+    pub work_time: Duration,
+}
+
+impl FrameLimiter {
+    pub fn new(target: TargetFps) -> Self {
+        let now = Instant::now();
+        FrameLimiter {
+            target,
+            deadline: now,
+            frame_start: now,
+            second_start: now,
+            frames: 0,
+            missed: 0,
+            fps: 0,
+            missed_per_second: 0,
+            work_time: Duration::ZERO,
+        }
+    }
+
+    pub fn target(&self) -> TargetFps {
+        self.target
+    }
+
+    pub fn set_target(&mut self, target: TargetFps) {
+        self.target = target;
+        self.deadline = Instant::now();
+    }
+
+    pub fn end_frame(&mut self) {
+        let now = Instant::now();
+        self.work_time = now - self.frame_start;
+        match self.target.frame_time() {
+            Some(budget) => {
+                self.deadline += budget;
+                if now > self.deadline {
+                    self.missed += 1;
+                    self.deadline = now;
+                } else {
+                    std::thread::sleep(self.deadline - now);
+                }
+            }
+            None => self.deadline = now,
+        }
+
+        self.frames += 1;
+        let now = Instant::now();
+        self.frame_start = now;
+        if now - self.second_start >= Duration::from_secs(1) {
+            self.fps = self.frames;
+            self.missed_per_second = self.missed;
+            self.frames = 0;
+            self.missed = 0;
+            self.second_start = now;
+        }
+    }
+}
+
+// Niveles de resolución interna, como fracción de la de la ventana
+const RESOLUTION_LEVELS: [f32; 4] = [1.0, 0.85, 0.7, 0.5];
+// Cuadros seguidos pasados del presupuesto antes de bajar un nivel, y cuadros
+// holgados antes de volver a subir
+const FRAMES_TO_DROP: u32 = 8;
+const FRAMES_TO_RAISE: u32 = 90;
+// Un cuadro es holgado si usa menos que esta parte del presupuesto
+const RAISE_HEADROOM: f32 = 0.6;
+
+// Resolución dinámica: si los cuadros tardan más que el objetivo se baja la
+// resolución interna y cuando sobra tiempo se vuelve a subir. Baja rápido y
+// sube despacio para no saltar de un nivel a otro.
+pub struct DynamicResolution {
+    level: usize,
+    slow_frames: u32,
+    fast_frames: u32,
+}
+
+impl DynamicResolution {
+    pub fn new() -> Self {
+        DynamicResolution { level: 0, slow_frames: 0, fast_frames: 0 }
+    }
+
+    pub fn scale(&self) -> f32 {
+        RESOLUTION_LEVELS[self.level]
+    }
+
+    pub fn reset(&mut self) {
+        *self = DynamicResolution::new();
+    }
+
+    // Con el tiempo del último cuadro y el presupuesto; devuelve si cambió la escala
+    pub fn update(&mut self, work_time: Duration, budget: Duration) -> bool {
+        if work_time > budget {
+            self.slow_frames += 1;
+            self.fast_frames = 0;
+        } else if work_time.as_secs_f32() < budget.as_secs_f32() * RAISE_HEADROOM {
+            self.fast_frames += 1;
+            self.slow_frames = 0;
+        } else {
+            self.slow_frames = 0;
+            self.fast_frames = 0;
+        }
+
+        let previous = self.level;
+        if self.slow_frames >= FRAMES_TO_DROP && self.level + 1 < RESOLUTION_LEVELS.len() {
+            self.level += 1;
+        } else if self.fast_frames >= FRAMES_TO_RAISE && self.level > 0 {
+            self.level -= 1;
+        }
+        if self.level != previous {
+            self.slow_frames = 0;
+            self.fast_frames = 0;
+        }
+        self.level != previous
+    }
+}
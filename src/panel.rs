@@ -0,0 +1,107 @@
+use crate::framebuffer::{Framebuffer, DepthTest};
+use crate::text;
+use crate::Uniforms;
+
+// Dónde se apoya el recuadro en la pantalla
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Anchor {
+    Left,
+    Center,
+}
+
+// Recuadro de texto con un título y filas de etiqueta y valor. Oscurece lo
+// que queda detrás y se pinta encima de todo.
+pub fn render_panel(
+    framebuffer: &mut Framebuffer,
+    uniforms: &Uniforms,
+    anchor: Anchor,
+    title: &str,
+    rows: &[(String, String)],
+    pixel_scale: usize,
+) {
+    let scale = 2 * pixel_scale;
+    let padding = 8 * pixel_scale;
+    let line = text::text_height(scale) + 4 * pixel_scale;
+    let gap = text::text_width(" ", scale) * 2;
+
+    let label_width = rows.iter().map(|(label, _)| text::text_width(label, scale)).max().unwrap_or(0);
+    let value_width = rows.iter().map(|(_, value)| text::text_width(value, scale)).max().unwrap_or(0);
+    let width = (label_width + gap + value_width).max(text::text_width(title, scale)) + 2 * padding;
+    let height = line * (rows.len() + 1) + padding * 2 + 2 * pixel_scale;
+    if width > framebuffer.width || height > framebuffer.height {
+        return;
+    }
+
+    let x0 = match anchor {
+        Anchor::Left => 10 * pixel_scale,
+        Anchor::Center => (framebuffer.width - width) / 2,
+    };
+    let y0 = (framebuffer.height - height) / 2;
+
+    for y in y0..y0 + height {
+        for x in x0..x0 + width {
+            framebuffer.color[y * framebuffer.width + x] *= 0.3;
+        }
+    }
+
+    framebuffer.set_depth_test(DepthTest::Always);
+    framebuffer.set_write_depth(false);
+    let plot_text = |framebuffer: &mut Framebuffer, content: &str, x: usize, y: usize| {
+        text::draw_text(content, x as isize, y as isize, scale, |x, y| {
+            if x >= 0 && y >= 0 {
+                framebuffer.point(x as usize, y as usize, 0.0);
+            }
+        });
+    };
+
+    let (left, mut y) = (x0 + padding, y0 + padding);
+    framebuffer.set_current_color(uniforms.palette(255, 220, 120));
+    plot_text(framebuffer, title, left, y);
+    y += line + 2 * pixel_scale;
+
+    for (label, value) in rows {
+        framebuffer.set_current_color(uniforms.palette(150, 170, 210));
+        plot_text(framebuffer, label, left, y);
+        framebuffer.set_current_color(uniforms.palette(235, 240, 255));
+        plot_text(framebuffer, value, left + label_width + gap, y);
+        y += line;
+    }
+
+    framebuffer.set_depth_test(DepthTest::Less);
+    framebuffer.set_write_depth(true);
+}
+
+// Globo chico junto al punto (x, y) de la pantalla, sin salirse de ella
+pub fn render_tooltip(framebuffer: &mut Framebuffer, uniforms: &Uniforms, x: isize, y: isize, lines: &[String], pixel_scale: usize) {
+    let scale = pixel_scale.max(1);
+    let padding = 4 * pixel_scale;
+    let line = text::text_height(scale) + 3 * pixel_scale;
+    let width = lines.iter().map(|content| text::text_width(content, scale)).max().unwrap_or(0) + 2 * padding;
+    let height = line * lines.len() + 2 * padding;
+    if width > framebuffer.width || height > framebuffer.height {
+        return;
+    }
+
+    let offset = 12 * pixel_scale as isize;
+    let x0 = (x + offset).clamp(0, (framebuffer.width - width) as isize) as usize;
+    let y0 = (y + offset).clamp(0, (framebuffer.height - height) as isize) as usize;
+    for py in y0..y0 + height {
+        for px in x0..x0 + width {
+            framebuffer.color[py * framebuffer.width + px] *= 0.25;
+        }
+    }
+
+    framebuffer.set_depth_test(DepthTest::Always);
+    framebuffer.set_write_depth(false);
+    framebuffer.set_current_color(uniforms.palette(235, 240, 255));
+    for (row, content) in lines.iter().enumerate() {
+        let top = (y0 + padding + row * line) as isize;
+        text::draw_text(content, (x0 + padding) as isize, top, scale, |x, y| {
+            if x >= 0 && y >= 0 {
+                framebuffer.point(x as usize, y as usize, 0.0);
+            }
+        });
+    }
+    framebuffer.set_depth_test(DepthTest::Less);
+    framebuffer.set_write_depth(true);
+}
@@ -0,0 +1,83 @@
+use nalgebra_glm::{Vec3, Vec4};
+use crate::framebuffer::Framebuffer;
+use crate::color::Color;
+use crate::shaders::encode_depth;
+use crate::Uniforms;
+
+pub struct Particle {
+    pub position: Vec3,
+    pub velocity: Vec3,
+    // Radio en unidades del mundo
+    pub size: f32,
+    pub age: u32,
+    pub lifetime: u32,
+    // El color pasa de `start_color` a `end_color` durante la vida
+    pub start_color: Color,
+    pub end_color: Color,
+}
+
+// Partículas que se dibujan como billboards aditivos y suaves. Pasan la
+// prueba de profundidad (los planetas las tapan) pero no la escriben.
+pub struct ParticleSystem {
+    particles: Vec<Particle>,
+    max_particles: usize,
+}
+
+impl ParticleSystem {
+    pub fn new(max_particles: usize) -> Self {
+        ParticleSystem { particles: Vec::new(), max_particles }
+    }
+
+    pub fn emit(&mut self, particle: Particle) {
+        if self.particles.len() < self.max_particles {
+            self.particles.push(particle);
+        }
+    }
+
+    pub fn update(&mut self) {
+        for particle in &mut self.particles {
+            particle.position += particle.velocity;
+            particle.age += 1;
+        }
+        self.particles.retain(|particle| particle.age < particle.lifetime);
+    }
+
+    pub fn render(&self, framebuffer: &mut Framebuffer, uniforms: &Uniforms) {
+        let focal = uniforms.projection_matrix[(1, 1)] * uniforms.viewport_height() / 2.0;
+
+        for particle in &self.particles {
+            let p = particle.position;
+            let clip = uniforms.projection_matrix * uniforms.view_matrix * Vec4::new(p.x, p.y, p.z, 1.0);
+            if clip.w <= 0.0 {
+                continue;
+            }
+
+            let ndc = clip / clip.w;
+            let (screen_x, screen_y) = uniforms.ndc_to_screen(ndc.x, ndc.y);
+            let depth = encode_depth(&clip, uniforms.depth_mode);
+
+            let life = particle.age as f32 / particle.lifetime as f32;
+            let color = uniforms.srgb(particle.start_color.lerp(&particle.end_color, life)) * (1.0 - life);
+
+            // Radio en pantalla según la distancia; al menos medio píxel
+            let radius = (particle.size * focal / clip.w).clamp(0.5, 40.0);
+            let reach = radius.ceil() as isize + 1;
+            let (cx, cy) = (screen_x.floor() as isize, screen_y.floor() as isize);
+
+            for py in cy - reach..=cy + reach {
+                for px in cx - reach..=cx + reach {
+                    if px < 0 || py < 0 {
+                        continue;
+                    }
+                    let dx = px as f32 + 0.5 - screen_x;
+                    let dy = py as f32 + 0.5 - screen_y;
+                    let falloff = (-(dx * dx + dy * dy) / (radius * radius) * 2.0).exp();
+                    if falloff < 0.02 {
+                        continue;
+                    }
+                    framebuffer.point_additive(px as usize, py as usize, depth, color * falloff);
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,65 @@
+use nalgebra_glm::{Vec3, Vec4};
+use crate::shaders::encode_depth;
+use crate::Uniforms;
+
+// Cuadros con el ratón quieto sobre un cuerpo antes de considerarlo señalado
+const HOVER_FRAMES: u32 = 30;
+
+// Esfera que se puede señalar con el ratón
+pub struct PickTarget {
+    pub center: Vec3,
+    pub radius: f32,
+}
+
+// Centro en pantalla, profundidad y radio en píxeles de una esfera; None si
+// queda detrás de la cámara
+pub fn project_sphere(uniforms: &Uniforms, center: Vec3, radius: f32) -> Option<(f32, f32, f32, f32)> {
+    let clip = uniforms.projection_matrix * uniforms.view_matrix * Vec4::new(center.x, center.y, center.z, 1.0);
+    if clip.w <= 0.0 {
+        return None;
+    }
+    let ndc = clip / clip.w;
+    let (x, y) = uniforms.ndc_to_screen(ndc.x, ndc.y);
+    let radius_px = radius * uniforms.projection_matrix[(1, 1)] * uniforms.viewport_height() / 2.0 / clip.w;
+    Some((x, y, encode_depth(&clip, uniforms.depth_mode), radius_px))
+}
+
+// El objetivo más cercano a la cámara cuyo disco en pantalla contiene el
+// punto (x, y). Los discos chicos se agrandan un poco para poder apuntarles.
+pub fn pick(uniforms: &Uniforms, x: f32, y: f32, targets: &[PickTarget]) -> Option<usize> {
+    let mut best: Option<(usize, f32)> = None;
+    for (index, target) in targets.iter().enumerate() {
+        let Some((cx, cy, depth, radius)) = project_sphere(uniforms, target.center, target.radius) else {
+            continue;
+        };
+        let reach = radius.max(4.0);
+        let (dx, dy) = (x - cx, y - cy);
+        if dx * dx + dy * dy <= reach * reach && best.is_none_or(|(_, nearest)| depth < nearest) {
+            best = Some((index, depth));
+        }
+    }
+    best.map(|(index, _)| index)
+}
+
+// Cuánto tiempo lleva el ratón sobre el mismo objetivo
+pub struct Hover {
+    target: Option<usize>,
+    frames: u32,
+}
+
+impl Hover {
+    pub fn new() -> Self {
+        Hover { target: None, frames: 0 }
+    }
+
+    // Se llama una vez por cuadro con lo que hay bajo el ratón. Devuelve el
+    // objetivo cuando ya pasó el tiempo de espera.
+    pub fn update(&mut self, picked: Option<usize>) -> Option<usize> {
+        if picked != self.target {
+            self.target = picked;
+            self.frames = 0;
+        }
+        self.frames = self.frames.saturating_add(1);
+        self.target.filter(|_| self.frames >= HOVER_FRAMES)
+    }
+}
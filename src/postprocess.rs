@@ -0,0 +1,520 @@
+use std::any::Any;
+use crate::framebuffer::Framebuffer;
+use crate::color::Color;
+
+// Datos del cuadro que necesitan los efectos
+#[derive(Clone, Copy, Debug)]
+pub struct PostContext {
+    // Escala interna de render, para que los radios en píxeles se vean
+    // igual con y sin supersampling
+    pub scale: usize,
+    pub time: u32,
+    // La imagen está en espacio lineal y hay que codificarla a sRGB
+    pub encode_srgb: bool,
+}
+
+// Un paso de postproceso. Los efectos antes del tonemapping trabajan sobre
+// `framebuffer.color` (HDR); los de después, sobre `framebuffer.buffer`.
+pub trait PostEffect: Any {
+    fn name(&self) -> &'static str;
+    fn apply(&mut self, framebuffer: &mut Framebuffer, context: &PostContext);
+}
+
+struct ChainEntry {
+    effect: Box<dyn PostEffect>,
+    enabled: bool,
+}
+
+// Lista ordenada de efectos que se pueden prender y apagar en tiempo real
+pub struct PostChain {
+    entries: Vec<ChainEntry>,
+}
+
+impl PostChain {
+    pub fn new() -> Self {
+        PostChain { entries: Vec::new() }
+    }
+
+    pub fn push(&mut self, effect: impl PostEffect, enabled: bool) -> &mut Self {
+        self.entries.push(ChainEntry { effect: Box::new(effect), enabled });
+        self
+    }
+
+    pub fn is_enabled(&self, name: &str) -> bool {
+        self.entries.iter().any(|entry| entry.enabled && entry.effect.name() == name)
+    }
+
+    pub fn set_enabled(&mut self, name: &str, enabled: bool) {
+        for entry in self.entries.iter_mut().filter(|entry| entry.effect.name() == name) {
+            entry.enabled = enabled;
+        }
+    }
+
+    pub fn toggle(&mut self, name: &str) {
+        let enabled = self.is_enabled(name);
+        self.set_enabled(name, !enabled);
+    }
+
+    // Acceso a un efecto concreto para cambiar sus parámetros
+    pub fn get_mut<T: PostEffect>(&mut self) -> Option<&mut T> {
+        self.entries.iter_mut().find_map(|entry| {
+            let effect: &mut dyn Any = entry.effect.as_mut();
+            effect.downcast_mut::<T>()
+        })
+    }
+
+    pub fn run(&mut self, framebuffer: &mut Framebuffer, context: &PostContext) {
+        for entry in self.entries.iter_mut().filter(|entry| entry.enabled) {
+            entry.effect.apply(framebuffer, context);
+        }
+    }
+}
+
+impl Default for PostChain {
+    // Cadena del sistema solar: glow del sol, bloom, efectos de cámara,
+    // tonemapping y grano. Los efectos de cabina empiezan apagados.
+    fn default() -> Self {
+        let mut chain = PostChain::new();
+        chain
+            .push(Glow { radius: 12, strength: 0.8 }, true)
+            .push(Bloom { threshold: 1.0, radius: 6, strength: 0.6 }, true)
+            .push(WarpStreaks { strength: 0.0, samples: 12 }, false)
+            .push(ChromaticAberration { offset: 3.0 }, false)
+            .push(Fxaa { contrast_threshold: 0.06 }, false)
+            .push(Vignette { strength: 0.6 }, false)
+            .push(ToneMapping { operator: Tonemap::Aces, exposure: 1.0, dither: true }, true)
+            .push(FilmGrain { strength: 0.04 }, false);
+        chain
+    }
+}
+
+// Brillo en pantalla alrededor de los píxeles emisivos (el sol).
+// Se difumina el buffer de emisión y se suma encima de la imagen final.
+pub struct Glow {
+    pub radius: usize,
+    pub strength: f32,
+}
+
+impl PostEffect for Glow {
+    fn name(&self) -> &'static str {
+        "glow"
+    }
+
+    fn apply(&mut self, framebuffer: &mut Framebuffer, context: &PostContext) {
+        if framebuffer.emission.iter().all(|glow| glow.is_black()) {
+            return;
+        }
+
+        let glow: Vec<[f32; 3]> = framebuffer.emission.iter()
+            .map(|color| [color.r(), color.g(), color.b()])
+            .collect();
+        composite_blurred(framebuffer, glow, self.radius * context.scale, self.strength);
+    }
+}
+
+// Bloom: se quedan solo los píxeles más brillantes que `threshold` (en HDR),
+// se difuminan y se suman a la imagen. Hace brillar el sol, las auroras y
+// cualquier cosa que pase de 1.0.
+pub struct Bloom {
+    pub threshold: f32,
+    pub radius: usize,
+    pub strength: f32,
+}
+
+impl PostEffect for Bloom {
+    fn name(&self) -> &'static str {
+        "bloom"
+    }
+
+    fn apply(&mut self, framebuffer: &mut Framebuffer, context: &PostContext) {
+        let threshold = self.threshold;
+        let mut any_bright = false;
+        let bright: Vec<[f32; 3]> = framebuffer.color.iter()
+            .map(|color| {
+                let luminance = color.luminance();
+                if luminance <= threshold {
+                    return [0.0; 3];
+                }
+                any_bright = true;
+                // Solo aporta lo que sobra del umbral, así el borde no se corta de golpe
+                let excess = (luminance - threshold) / luminance;
+                [color.r() * excess, color.g() * excess, color.b() * excess]
+            })
+            .collect();
+
+        if any_bright {
+            composite_blurred(framebuffer, bright, self.radius * context.scale, self.strength);
+        }
+    }
+}
+
+// Desenfoque radial desde el centro de la pantalla para el salto: cada
+// píxel promedia la imagen hacia el centro, así las estrellas se estiran en
+// rayas. `strength` va de 0 a 1 y alarga las rayas hasta un tercio del radio.
+pub struct WarpStreaks {
+    pub strength: f32,
+    pub samples: usize,
+}
+
+impl PostEffect for WarpStreaks {
+    fn name(&self) -> &'static str {
+        "warp"
+    }
+
+    fn apply(&mut self, framebuffer: &mut Framebuffer, _context: &PostContext) {
+        if self.strength <= 0.0 || self.samples < 2 {
+            return;
+        }
+        let width = framebuffer.width;
+        let height = framebuffer.height;
+        let source = framebuffer.color.clone();
+        let cx = width as f32 * 0.5;
+        let cy = height as f32 * 0.5;
+        let length = self.strength.min(1.0) / 3.0;
+
+        for y in 0..height {
+            for x in 0..width {
+                let dx = x as f32 - cx;
+                let dy = y as f32 - cy;
+
+                // Las muestras más lejanas pesan menos, la raya se apaga hacia el centro
+                let mut sum = Color::from_rgb_f32(0.0, 0.0, 0.0);
+                let mut total = 0.0;
+                for i in 0..self.samples {
+                    let t = i as f32 / (self.samples - 1) as f32;
+                    let shrink = 1.0 - length * t;
+                    let sx = ((cx + dx * shrink).max(0.0) as usize).min(width - 1);
+                    let sy = ((cy + dy * shrink).max(0.0) as usize).min(height - 1);
+                    let weight = 1.0 - t * 0.7;
+                    sum += source[sy * width + sx] * weight;
+                    total += weight;
+                }
+                // Se suma algo de brillo para que las rayas se noten sobre el negro
+                framebuffer.color[y * width + x] = sum * ((1.0 + self.strength * 0.5) / total);
+            }
+        }
+    }
+}
+
+// Separa los canales rojo y azul hacia fuera y hacia dentro del centro,
+// como una lente barata. `offset` son los píxeles de desplazamiento en el borde.
+pub struct ChromaticAberration {
+    pub offset: f32,
+}
+
+impl PostEffect for ChromaticAberration {
+    fn name(&self) -> &'static str {
+        "chromatic_aberration"
+    }
+
+    fn apply(&mut self, framebuffer: &mut Framebuffer, context: &PostContext) {
+        let width = framebuffer.width;
+        let height = framebuffer.height;
+        let offset = self.offset * context.scale as f32;
+        let source = framebuffer.color.clone();
+        let cx = width as f32 * 0.5;
+        let cy = height as f32 * 0.5;
+        let max_distance = (cx * cx + cy * cy).sqrt();
+
+        let sample = |x: f32, y: f32| {
+            let sx = (x.round().max(0.0) as usize).min(width - 1);
+            let sy = (y.round().max(0.0) as usize).min(height - 1);
+            source[sy * width + sx]
+        };
+
+        for y in 0..height {
+            for x in 0..width {
+                let dx = x as f32 - cx;
+                let dy = y as f32 - cy;
+                let distance = (dx * dx + dy * dy).sqrt();
+                if distance < 1.0 {
+                    continue;
+                }
+
+                // El desplazamiento crece hacia los bordes, el centro queda nítido
+                let shift = offset * distance / max_distance;
+                let (ux, uy) = (dx / distance * shift, dy / distance * shift);
+
+                let red = sample(x as f32 + ux, y as f32 + uy).r();
+                let green = source[y * width + x].g();
+                let blue = sample(x as f32 - ux, y as f32 - uy).b();
+                framebuffer.color[y * width + x] = Color::from_rgb_f32(red, green, blue);
+            }
+        }
+    }
+}
+
+// Antialiasing aproximado estilo FXAA: donde el contraste de luminancia con
+// los vecinos es alto se mezcla el píxel a lo largo del borde detectado.
+pub struct Fxaa {
+    pub contrast_threshold: f32,
+}
+
+impl PostEffect for Fxaa {
+    fn name(&self) -> &'static str {
+        "fxaa"
+    }
+
+    fn apply(&mut self, framebuffer: &mut Framebuffer, _context: &PostContext) {
+        let width = framebuffer.width;
+        let height = framebuffer.height;
+        if width < 3 || height < 3 {
+            return;
+        }
+
+        let source = framebuffer.color.clone();
+        // Luminancia comprimida para que el HDR no domine la detección
+        let luma: Vec<f32> = source.iter()
+            .map(|color| {
+                let l = color.luminance();
+                l / (1.0 + l)
+            })
+            .collect();
+
+        for y in 1..height - 1 {
+            for x in 1..width - 1 {
+                let index = y * width + x;
+                let m = luma[index];
+                let n = luma[index - width];
+                let s = luma[index + width];
+                let w = luma[index - 1];
+                let e = luma[index + 1];
+
+                let highest = m.max(n).max(s).max(w).max(e);
+                let lowest = m.min(n).min(s).min(w).min(e);
+                let contrast = highest - lowest;
+                if contrast < self.contrast_threshold.max(highest * 0.125) {
+                    continue;
+                }
+
+                // Cuánto difiere el centro del promedio, suavizado como en FXAA
+                let average = (n + s + w + e) * 0.25;
+                let blend = ((average - m).abs() / contrast).clamp(0.0, 1.0);
+                let blend = blend * blend * (3.0 - 2.0 * blend);
+                let blend = blend * blend * 0.75;
+
+                // Borde horizontal: se mezcla con el vecino de arriba o abajo
+                let horizontal = (n + s - 2.0 * m).abs() >= (w + e - 2.0 * m).abs();
+                let neighbor = if horizontal {
+                    if (n - m).abs() >= (s - m).abs() { index - width } else { index + width }
+                } else if (w - m).abs() >= (e - m).abs() {
+                    index - 1
+                } else {
+                    index + 1
+                };
+
+                framebuffer.color[index] = source[index].lerp(&source[neighbor], blend * 0.5);
+            }
+        }
+    }
+}
+
+// Oscurece las esquinas. Con `strength` = 1 las esquinas quedan negras.
+pub struct Vignette {
+    pub strength: f32,
+}
+
+impl PostEffect for Vignette {
+    fn name(&self) -> &'static str {
+        "vignette"
+    }
+
+    fn apply(&mut self, framebuffer: &mut Framebuffer, _context: &PostContext) {
+        let width = framebuffer.width;
+        let height = framebuffer.height;
+
+        for y in 0..height {
+            for x in 0..width {
+                // Coordenadas en -1..1 corregidas por la relación de aspecto
+                let u = (x as f32 / width as f32) * 2.0 - 1.0;
+                let v = ((y as f32 / height as f32) * 2.0 - 1.0) * height as f32 / width as f32;
+                let distance = (u * u + v * v).sqrt() / 2.0_f32.sqrt();
+
+                let falloff = 1.0 - self.strength * distance.powf(2.5).min(1.0) * 1.6;
+                let index = y * width + x;
+                framebuffer.color[index] *= falloff.clamp(0.0, 1.0);
+            }
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Tonemap {
+    Clamp,
+    Reinhard,
+    Aces,
+}
+
+impl Tonemap {
+    pub fn next(self) -> Self {
+        match self {
+            Tonemap::Clamp => Tonemap::Reinhard,
+            Tonemap::Reinhard => Tonemap::Aces,
+            Tonemap::Aces => Tonemap::Clamp,
+        }
+    }
+
+    fn map(self, value: f32) -> f32 {
+        match self {
+            Tonemap::Clamp => value,
+            Tonemap::Reinhard => value / (1.0 + value),
+            // Aproximación de Narkowicz de la curva ACES
+            Tonemap::Aces => (value * (2.51 * value + 0.03)) / (value * (2.43 * value + 0.59) + 0.14),
+        }
+    }
+}
+
+// Matriz de Bayer 8x8 para el dithering ordenado
+const BAYER_8X8: [[u8; 8]; 8] = [
+    [0, 32, 8, 40, 2, 34, 10, 42],
+    [48, 16, 56, 24, 50, 18, 58, 26],
+    [12, 44, 4, 36, 14, 46, 6, 38],
+    [60, 28, 52, 20, 62, 30, 54, 22],
+    [3, 35, 11, 43, 1, 33, 9, 41],
+    [51, 19, 59, 27, 49, 17, 57, 25],
+    [15, 47, 7, 39, 13, 45, 5, 37],
+    [63, 31, 55, 23, 61, 29, 53, 21],
+];
+
+// Pasa la imagen HDR al buffer de salida de 8 bits por canal. Con
+// `dither` se suma menos de un escalón de 8 bits según la matriz de
+// Bayer, para que los degradados oscuros no se vean en franjas.
+pub struct ToneMapping {
+    pub operator: Tonemap,
+    pub exposure: f32,
+    pub dither: bool,
+}
+
+impl PostEffect for ToneMapping {
+    fn name(&self) -> &'static str {
+        "tonemap"
+    }
+
+    fn apply(&mut self, framebuffer: &mut Framebuffer, context: &PostContext) {
+        let width = framebuffer.width;
+        let operator = self.operator;
+        let exposure = self.exposure;
+
+        for (index, (output, color)) in framebuffer.buffer.iter_mut().zip(framebuffer.color.iter()).enumerate() {
+            let mapped = Color::from_rgb_f32(
+                operator.map(color.r() * exposure),
+                operator.map(color.g() * exposure),
+                operator.map(color.b() * exposure),
+            );
+            let mapped = if context.encode_srgb { mapped.to_srgb() } else { mapped };
+
+            *output = if self.dither {
+                let (x, y) = (index % width, index / width);
+                let offset = (BAYER_8X8[y % 8][x % 8] as f32 + 0.5) / 64.0 - 0.5;
+                let d = offset / 255.0;
+                Color::from_rgb_f32(mapped.r() + d, mapped.g() + d, mapped.b() + d).to_hex()
+            } else {
+                mapped.to_hex()
+            };
+        }
+    }
+}
+
+// Grano de película sobre la imagen ya en 8 bits; cambia en cada cuadro
+pub struct FilmGrain {
+    pub strength: f32,
+}
+
+impl PostEffect for FilmGrain {
+    fn name(&self) -> &'static str {
+        "grain"
+    }
+
+    fn apply(&mut self, framebuffer: &mut Framebuffer, context: &PostContext) {
+        let amplitude = self.strength * 255.0;
+
+        for (index, pixel) in framebuffer.buffer.iter_mut().enumerate() {
+            let mut h = (index as u32).wrapping_mul(0x9E37_79B9) ^ context.time.wrapping_mul(0x85EB_CA6B);
+            h ^= h >> 15;
+            h = h.wrapping_mul(0x2C1B_3C6D);
+            h ^= h >> 12;
+            let grain = ((h & 0xFFFF) as f32 / 65535.0 - 0.5) * amplitude;
+
+            let channel = |shift: u32| {
+                let value = ((*pixel >> shift) & 0xFF) as f32 + grain;
+                (value.round().clamp(0.0, 255.0) as u32) << shift
+            };
+            *pixel = channel(16) | channel(8) | channel(0);
+        }
+    }
+}
+
+fn composite_blurred(framebuffer: &mut Framebuffer, mut layer: Vec<[f32; 3]>, radius: usize, strength: f32) {
+    let width = framebuffer.width;
+    let height = framebuffer.height;
+    let mut scratch = vec![[0.0; 3]; width * height];
+
+    // Dos pasadas de box blur separable se parecen bastante a un gaussiano
+    for _ in 0..2 {
+        box_blur_horizontal(&layer, &mut scratch, width, height, radius);
+        box_blur_vertical(&scratch, &mut layer, width, height, radius);
+    }
+
+    for (pixel, halo) in framebuffer.color.iter_mut().zip(layer.iter()) {
+        *pixel += Color::from_rgb_f32(halo[0], halo[1], halo[2]) * strength;
+    }
+}
+
+fn box_blur_horizontal(src: &[[f32; 3]], dst: &mut [[f32; 3]], width: usize, height: usize, radius: usize) {
+    let window = (2 * radius + 1) as f32;
+
+    for y in 0..height {
+        let row = y * width;
+        let mut sum = [0.0; 3];
+
+        for x in 0..=radius.min(width - 1) {
+            add_to(&mut sum, &src[row + x]);
+        }
+
+        for x in 0..width {
+            dst[row + x] = [sum[0] / window, sum[1] / window, sum[2] / window];
+
+            if x + radius + 1 < width {
+                add_to(&mut sum, &src[row + x + radius + 1]);
+            }
+            if x >= radius {
+                sub_from(&mut sum, &src[row + x - radius]);
+            }
+        }
+    }
+}
+
+fn box_blur_vertical(src: &[[f32; 3]], dst: &mut [[f32; 3]], width: usize, height: usize, radius: usize) {
+    let window = (2 * radius + 1) as f32;
+
+    for x in 0..width {
+        let mut sum = [0.0; 3];
+
+        for y in 0..=radius.min(height - 1) {
+            add_to(&mut sum, &src[y * width + x]);
+        }
+
+        for y in 0..height {
+            dst[y * width + x] = [sum[0] / window, sum[1] / window, sum[2] / window];
+
+            if y + radius + 1 < height {
+                add_to(&mut sum, &src[(y + radius + 1) * width + x]);
+            }
+            if y >= radius {
+                sub_from(&mut sum, &src[(y - radius) * width + x]);
+            }
+        }
+    }
+}
+
+fn add_to(sum: &mut [f32; 3], value: &[f32; 3]) {
+    sum[0] += value[0];
+    sum[1] += value[1];
+    sum[2] += value[2];
+}
+
+fn sub_from(sum: &mut [f32; 3], value: &[f32; 3]) {
+    sum[0] -= value[0];
+    sum[1] -= value[1];
+    sum[2] -= value[2];
+}
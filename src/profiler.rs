@@ -0,0 +1,169 @@
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::time::{Duration, Instant};
+use crate::framebuffer::Framebuffer;
+use crate::Uniforms;
+
+// Etapas del cuadro que se miden por separado
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Stage {
+    Vertex,
+    Raster,
+    Fragment,
+    Post,
+    Present,
+}
+
+pub const STAGES: [Stage; 5] = [Stage::Vertex, Stage::Raster, Stage::Fragment, Stage::Post, Stage::Present];
+
+impl Stage {
+    pub fn label(self) -> &'static str {
+        match self {
+            Stage::Vertex => "Vértices",
+            Stage::Raster => "Rasterizado",
+            Stage::Fragment => "Fragmentos",
+            Stage::Post => "Post-proceso",
+            Stage::Present => "Presentación",
+        }
+    }
+
+    // Color de su tramo en la barra
+    fn color(self) -> (u8, u8, u8) {
+        match self {
+            Stage::Vertex => (90, 160, 255),
+            Stage::Raster => (80, 220, 140),
+            Stage::Fragment => (255, 170, 60),
+            Stage::Post => (220, 90, 220),
+            Stage::Present => (240, 230, 90),
+        }
+    }
+
+    fn index(self) -> usize {
+        STAGES.iter().position(|&stage| stage == self).unwrap_or(0)
+    }
+}
+
+// Qué tan rápido sigue el promedio de la tabla a los cuadros nuevos
+const SMOOTHING: f32 = 0.1;
+// Lo que ocupa la barra entera: un cuadro a 30 FPS
+const BAR_BUDGET_MS: f32 = 1000.0 / 30.0;
+
+// Tiempos por etapa del cuadro en curso. Solo mide mientras se muestra la
+// tabla o se escribe el CSV; si no, `measuring` es falso y los que llaman
+// ni toman el tiempo. Lo que no cae en ninguna etapa (el cielo, las
+// órbitas, la interfaz, la simulación) queda como "Otros".
+pub struct Profiler {
+    pub visible: bool,
+    frame_start: Instant,
+    current: [Duration; 5],
+    // Promedios en milisegundos para la tabla
+    average: [f32; 5],
+    average_frame: f32,
+    csv: Option<BufWriter<File>>,
+    frame: u64,
+}
+
+impl Profiler {
+    pub fn new() -> Self {
+        Profiler {
+            visible: false,
+            frame_start: Instant::now(),
+            current: [Duration::ZERO; 5],
+            average: [0.0; 5],
+            average_frame: 0.0,
+            csv: None,
+            frame: 0,
+        }
+    }
+
+    // Desde ahora cada cuadro deja una fila en `filename`
+    pub fn record_to(&mut self, filename: &str) -> Result<(), String> {
+        let file = File::create(filename).map_err(|err| format!("{}: {}", filename, err))?;
+        let mut csv = BufWriter::new(file);
+        writeln!(csv, "cuadro,vertices_ms,rasterizado_ms,fragmentos_ms,post_ms,presentacion_ms,total_ms")
+            .map_err(|err| format!("{}: {}", filename, err))?;
+        self.csv = Some(csv);
+        Ok(())
+    }
+
+    pub fn measuring(&self) -> bool {
+        self.visible || self.csv.is_some()
+    }
+
+    pub fn begin_frame(&mut self) {
+        self.frame_start = Instant::now();
+        self.current = [Duration::ZERO; 5];
+    }
+
+    pub fn add(&mut self, stage: Stage, elapsed: Duration) {
+        self.current[stage.index()] += elapsed;
+    }
+
+    // Corre `work` y suma lo que tardó a `stage`
+    pub fn time<T>(&mut self, stage: Stage, work: impl FnOnce() -> T) -> T {
+        if !self.measuring() {
+            return work();
+        }
+        let start = Instant::now();
+        let result = work();
+        self.add(stage, start.elapsed());
+        result
+    }
+
+    // Cierra el cuadro: actualiza los promedios y escribe la fila del CSV.
+    // Si no se puede escribir se deja de grabar.
+    pub fn end_frame(&mut self) {
+        if !self.measuring() {
+            return;
+        }
+        let milliseconds = |duration: Duration| duration.as_secs_f32() * 1000.0;
+        let total = milliseconds(self.frame_start.elapsed());
+        for (average, &stage) in self.average.iter_mut().zip(&self.current) {
+            *average += (milliseconds(stage) - *average) * SMOOTHING;
+        }
+        self.average_frame += (total - self.average_frame) * SMOOTHING;
+
+        if let Some(csv) = &mut self.csv {
+            let stages: Vec<String> = self.current.iter().map(|&stage| format!("{:.3}", milliseconds(stage))).collect();
+            if let Err(err) = writeln!(csv, "{},{},{:.3}", self.frame, stages.join(","), total) {
+                eprintln!("No se pudo escribir el perfil: {}", err);
+                self.csv = None;
+            }
+        }
+        self.frame += 1;
+    }
+
+    // Filas de la tabla: milisegundos promedio y parte del cuadro
+    pub fn rows(&self) -> Vec<(String, String)> {
+        let share = |ms: f32| if self.average_frame > 0.0 { ms / self.average_frame * 100.0 } else { 0.0 };
+        let row = |label: &str, ms: f32| (label.to_string(), format!("{:6.2} ms {:3.0}%", ms, share(ms)));
+        let mut rows: Vec<(String, String)> = STAGES.iter().map(|&stage| row(stage.label(), self.average[stage.index()])).collect();
+        let others = (self.average_frame - self.average.iter().sum::<f32>()).max(0.0);
+        rows.push(row("Otros", others));
+        rows.push(("Cuadro".to_string(), format!("{:6.2} ms", self.average_frame)));
+        rows
+    }
+
+    // Barra apilada arriba de todo, de izquierda a derecha en el orden de
+    // las etapas; el ancho de la pantalla es BAR_BUDGET_MS
+    pub fn render_bar(&self, framebuffer: &mut Framebuffer, uniforms: &Uniforms, pixel_scale: usize) {
+        let height = (4 * pixel_scale).min(framebuffer.height);
+        let pixels_per_ms = framebuffer.width as f32 / BAR_BUDGET_MS;
+        let others = (self.average_frame - self.average.iter().sum::<f32>()).max(0.0);
+        let segments = STAGES.iter()
+            .map(|&stage| (self.average[stage.index()], stage.color()))
+            .chain([(others, (120, 120, 130))]);
+
+        let mut x = 0.0;
+        for (ms, (r, g, b)) in segments {
+            let color = uniforms.palette(r, g, b);
+            let start = (x as usize).min(framebuffer.width);
+            x += ms * pixels_per_ms;
+            let end = (x as usize).min(framebuffer.width);
+            for y in 0..height {
+                let row = y * framebuffer.width;
+                framebuffer.color[row + start..row + end].fill(color);
+            }
+        }
+    }
+}
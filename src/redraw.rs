@@ -0,0 +1,75 @@
+use crate::color::Color;
+use crate::framebuffer::{Framebuffer, Viewport};
+use crate::shaders::DepthMode;
+
+// Lo que, además de la cámara, cambia cómo se ven las capas guardadas
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct LayerKey {
+    pub width: usize,
+    pub height: usize,
+    pub viewport: Viewport,
+    // Índice del fondo del cielo elegido con K
+    pub background: usize,
+    pub linear_workflow: bool,
+    pub depth_mode: DepthMode,
+}
+
+// Capas que solo cambian cuando se mueve la cámara: el fondo del cielo y las
+// órbitas alrededor del sol. Mientras `Camera::has_changed` sigue en falso se
+// reusan las del cuadro anterior en vez de volver a calcularlas. Las estrellas
+// centellean y se dibujan siempre.
+pub struct StaticLayers {
+    key: Option<LayerKey>,
+    background: Option<Vec<Color>>,
+    // Píxeles de las órbitas con su profundidad: se vuelven a pasar por la
+    // prueba de profundidad contra los cuerpos del cuadro actual
+    orbits: Option<Vec<(usize, usize, f32)>>,
+    // Radio, excentricidad y perihelio de las órbitas guardadas
+    orbit_shapes: Vec<(f32, f32, f32)>,
+}
+
+impl StaticLayers {
+    pub fn new() -> Self {
+        StaticLayers { key: None, background: None, orbits: None, orbit_shapes: Vec::new() }
+    }
+
+    // Descarta todo; se rearma en el próximo cuadro
+    pub fn invalidate(&mut self) {
+        self.key = None;
+        self.background = None;
+        self.orbits = None;
+    }
+
+    // Si cambió algo de `key` lo guardado ya no sirve
+    pub fn check(&mut self, key: LayerKey) {
+        if self.key != Some(key) {
+            self.invalidate();
+            self.key = Some(key);
+        }
+    }
+
+    // Copia el fondo guardado al framebuffer; falso si no hay
+    pub fn restore_background(&self, framebuffer: &mut Framebuffer) -> bool {
+        match &self.background {
+            Some(background) if background.len() == framebuffer.color.len() => {
+                framebuffer.color.copy_from_slice(background);
+                true
+            }
+            _ => false,
+        }
+    }
+
+    pub fn store_background(&mut self, framebuffer: &Framebuffer) {
+        self.background = Some(framebuffer.color.clone());
+    }
+
+    // Los píxeles guardados de las órbitas, si son de estas mismas órbitas
+    pub fn orbits(&self, shapes: &[(f32, f32, f32)]) -> Option<&[(usize, usize, f32)]> {
+        self.orbits.as_deref().filter(|_| self.orbit_shapes == shapes)
+    }
+
+    pub fn store_orbits(&mut self, shapes: Vec<(f32, f32, f32)>, pixels: Vec<(usize, usize, f32)>) {
+        self.orbit_shapes = shapes;
+        self.orbits = Some(pixels);
+    }
+}
@@ -0,0 +1,54 @@
+use nalgebra_glm::{Vec3, Vec4};
+use crate::framebuffer::BlendMode;
+use crate::vertex::Vertex;
+use crate::fragment::Fragment;
+use crate::color::Color;
+use crate::Uniforms;
+
+pub type BlendShader = fn(&Fragment, &Uniforms) -> (Color, f32);
+
+// Dibujo translúcido pendiente: se guarda para pintarlo después de lo opaco
+pub struct TransparentDraw<'a> {
+    pub uniforms: Uniforms<'a>,
+    pub vertices: &'a [Vertex],
+    pub shader: BlendShader,
+    pub blend_mode: BlendMode,
+    view_depth: f32,
+}
+
+pub struct RenderQueue<'a> {
+    transparent: Vec<TransparentDraw<'a>>,
+}
+
+impl<'a> RenderQueue<'a> {
+    pub fn new() -> Self {
+        RenderQueue { transparent: Vec::new() }
+    }
+
+    // `center` es el punto del mundo que se usa para ordenar (el centro del objeto)
+    pub fn push_transparent(
+        &mut self,
+        uniforms: Uniforms<'a>,
+        vertices: &'a [Vertex],
+        shader: BlendShader,
+        blend_mode: BlendMode,
+        center: Vec3,
+    ) {
+        let view = uniforms.view_matrix * Vec4::new(center.x, center.y, center.z, 1.0);
+
+        self.transparent.push(TransparentDraw {
+            uniforms,
+            vertices,
+            shader,
+            blend_mode,
+            view_depth: -view.z,
+        });
+    }
+
+    // Devuelve los dibujos translúcidos del más lejano al más cercano
+    pub fn drain_back_to_front(&mut self) -> Vec<TransparentDraw<'a>> {
+        let mut draws = std::mem::take(&mut self.transparent);
+        draws.sort_by(|a, b| b.view_depth.total_cmp(&a.view_depth));
+        draws
+    }
+}
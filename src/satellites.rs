@@ -0,0 +1,108 @@
+use nalgebra_glm::{Vec3, Vec4};
+use crate::framebuffer::Framebuffer;
+use crate::shaders::encode_depth;
+use crate::trails::Trail;
+use crate::Uniforms;
+
+// Satélite en órbita baja alrededor de un planeta. La órbita es un círculo
+// inclinado respecto del ecuador; la posición sale de la del planeta en cada
+// cuadro, igual que la luna.
+pub struct Satellite {
+    // Índice del planeta en la lista de planetas
+    parent: usize,
+    orbit_radius: f32,
+    // Inclinación y giro del nodo ascendente, en radianes
+    inclination: f32,
+    node: f32,
+    speed: f32,
+    angle: f32,
+    position: Vec3,
+    // Estela en el marco del planeta: dibuja la traza de la órbita alrededor
+    // de él en vez de una espiral a lo largo de la órbita del planeta
+    trail: Trail,
+}
+
+impl Satellite {
+    fn offset(&self) -> Vec3 {
+        let flat = Vec3::new(self.angle.cos(), 0.0, self.angle.sin()) * self.orbit_radius;
+        let tilted = nalgebra_glm::rotate_vec3(&flat, self.inclination, &Vec3::new(1.0, 0.0, 0.0));
+        nalgebra_glm::rotate_vec3(&tilted, self.node, &Vec3::new(0.0, 1.0, 0.0))
+    }
+}
+
+pub struct SatelliteSystem {
+    satellites: Vec<Satellite>,
+    pub show_trails: bool,
+}
+
+impl SatelliteSystem {
+    pub fn new() -> Self {
+        SatelliteSystem { satellites: Vec::new(), show_trails: false }
+    }
+
+    pub fn add(&mut self, parent: usize, orbit_radius: f32, inclination: f32, node: f32, speed: f32) {
+        self.satellites.push(Satellite {
+            parent,
+            orbit_radius,
+            inclination,
+            node,
+            speed,
+            angle: node * 2.0,
+            position: Vec3::new(0.0, 0.0, 0.0),
+            trail: Trail::new(90, 2),
+        });
+    }
+
+    pub fn count_around(&self, parent: usize) -> usize {
+        self.satellites.iter().filter(|satellite| satellite.parent == parent).count()
+    }
+
+    // `parents` son las posiciones de los planetas de este cuadro
+    pub fn update(&mut self, parents: &[Vec3], time_scale: f32) {
+        for satellite in &mut self.satellites {
+            let Some(&parent) = parents.get(satellite.parent) else {
+                continue;
+            };
+            satellite.angle += satellite.speed * time_scale;
+            let offset = satellite.offset();
+            satellite.position = parent + offset;
+            satellite.trail.record(offset);
+        }
+    }
+
+    // Un punto brillante por satélite, tapado por lo que tenga delante
+    pub fn render(&self, framebuffer: &mut Framebuffer, uniforms: &Uniforms, parents: &[Vec3]) {
+        let light = uniforms.palette(255, 250, 230);
+        let trail_color = uniforms.palette(120, 200, 255) * 0.5;
+
+        for satellite in &self.satellites {
+            if self.show_trails {
+                if let Some(&parent) = parents.get(satellite.parent) {
+                    satellite.trail.render_around(framebuffer, uniforms, parent, satellite.position, trail_color);
+                }
+            }
+
+            let position = satellite.position;
+            let clip = uniforms.projection_matrix * uniforms.view_matrix * Vec4::new(position.x, position.y, position.z, 1.0);
+            if clip.w <= 0.0 {
+                continue;
+            }
+            let ndc = clip / clip.w;
+            if ndc.x.abs() > 1.0 || ndc.y.abs() > 1.0 {
+                continue;
+            }
+            let (x, y) = uniforms.ndc_to_screen(ndc.x, ndc.y);
+            let depth = encode_depth(&clip, uniforms.depth_mode);
+
+            // Parpadeo suave por el reflejo de los paneles
+            let glint = 1.5 + (uniforms.time as f32 * 0.1 + satellite.node * 7.0).sin() * 0.5;
+            framebuffer.set_current_color(light * glint);
+            for (dx, dy) in [(0.0, 0.0), (1.0, 0.0), (0.0, 1.0), (1.0, 1.0)] {
+                let (px, py) = (x + dx, y + dy);
+                if px >= 0.0 && py >= 0.0 {
+                    framebuffer.point_emissive(px as usize, py as usize, depth);
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,41 @@
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+
+// Con esta semilla los ruidos de los cuerpos quedan con sus semillas de siempre
+pub const DEFAULT_SEED: u64 = 1337;
+
+// Partes de la simulación que usan azar. Cada una saca su propio generador
+// de la semilla global, así cambiar la cantidad de estrellas no cambia,
+// por ejemplo, las estrellas fugaces.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Stream {
+    Stars,
+    Constellations,
+    Meteors,
+    Exhaust,
+}
+
+// Semilla global: la misma semilla da el mismo sistema y el mismo cielo
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Seed(pub u64);
+
+impl Seed {
+    pub fn rng(self, stream: Stream) -> StdRng {
+        let stream = stream as u64 + 1;
+        StdRng::seed_from_u64(self.0 ^ stream.wrapping_mul(0x9E37_79B9_7F4A_7C15))
+    }
+
+    // Corre la semilla fija de un ruido según la global
+    pub fn noise(self, seed: i32) -> i32 {
+        seed.wrapping_add(self.0.wrapping_sub(DEFAULT_SEED) as i32)
+    }
+}
+
+// `--semilla <n>` en la línea de comandos
+pub fn from_args(args: &[String]) -> Result<Option<Seed>, String> {
+    let Some(index) = args.iter().position(|arg| arg == "--semilla") else {
+        return Ok(None);
+    };
+    let value = args.get(index + 1).ok_or("--semilla necesita un número")?;
+    value.parse().map(|seed| Some(Seed(seed))).map_err(|_| format!("semilla inválida '{}'", value))
+}
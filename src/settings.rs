@@ -0,0 +1,323 @@
+use std::fs;
+use minifb::{Key, KeyRepeat, Window};
+use crate::framebuffer::Framebuffer;
+use crate::pacing::TargetFps;
+use crate::panel;
+use crate::postprocess::PostChain;
+use crate::seed::DEFAULT_SEED;
+use crate::Uniforms;
+
+// Valores que se pueden elegir en el menú para cada opción numérica
+const RENDER_SCALES: [usize; 2] = [1, 2];
+const STAR_COUNTS: [usize; 5] = [1000, 2000, 4000, 8000, 16000];
+const TIME_SCALES: [f32; 7] = [0.0, 0.25, 0.5, 1.0, 2.0, 4.0, 8.0];
+const SENSITIVITIES: [f32; 6] = [0.25, 0.5, 0.75, 1.0, 1.5, 2.0];
+
+// Opciones que se cambian en el menú y se guardan en un archivo de texto,
+// una por línea:
+//   <clave> <valor>
+// con si/no para las que se prenden y apagan
+pub struct Settings {
+    path: String,
+    pub render_scale: usize,
+    // Bajar la resolución interna cuando los cuadros tardan de más, y cómo
+    // se estira después a la ventana
+    pub dynamic_resolution: bool,
+    pub bilinear_upscale: bool,
+    pub bloom: bool,
+    pub fxaa: bool,
+    pub grain: bool,
+    pub cockpit_effects: bool,
+    // Dibujar primero solo la profundidad de lo opaco para sombrear cada
+    // píxel una sola vez
+    pub depth_prepass: bool,
+    // Sombrear lo opaco en una pasada aparte, a partir de un G-buffer
+    pub deferred: bool,
+    // BRDF metálico/rugosidad para la nave y la estación
+    pub pbr: bool,
+    pub star_count: usize,
+    pub time_scale: f32,
+    pub sensitivity: f32,
+    pub target_fps: TargetFps,
+    // Pantalla completa: ventana sin bordes del tamaño de la pantalla
+    pub fullscreen: bool,
+    pub screen_width: usize,
+    pub screen_height: usize,
+    // Semilla del azar (cielo, estrellas fugaces, ruidos); `--semilla` la pisa
+    pub seed: u64,
+}
+
+impl Settings {
+    pub fn new(path: &str) -> Self {
+        Settings {
+            path: path.to_string(),
+            render_scale: 1,
+            dynamic_resolution: true,
+            bilinear_upscale: true,
+            bloom: true,
+            fxaa: false,
+            grain: false,
+            cockpit_effects: false,
+            depth_prepass: false,
+            deferred: false,
+            pbr: true,
+            star_count: 4000,
+            time_scale: 1.0,
+            sensitivity: 1.0,
+            target_fps: TargetFps::Sixty,
+            fullscreen: false,
+            screen_width: 1920,
+            screen_height: 1080,
+            seed: DEFAULT_SEED,
+        }
+    }
+
+    // Si el archivo no existe se usan los valores por defecto
+    pub fn load(path: &str) -> Result<Self, String> {
+        let mut settings = Settings::new(path);
+        let Ok(contents) = fs::read_to_string(path) else {
+            return Ok(settings);
+        };
+
+        for (number, line) in contents.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let error = || format!("{}:{}: línea inválida '{}'", path, number + 1, line);
+
+            let (key, value) = line.split_once(' ').ok_or_else(error)?;
+            let value = value.trim();
+            let flag = || match value {
+                "si" => Ok(true),
+                "no" => Ok(false),
+                _ => Err(error()),
+            };
+            match key {
+                "resolucion" => settings.render_scale = value.parse().map_err(|_| error())?,
+                "resolucion_dinamica" => settings.dynamic_resolution = flag()?,
+                "escalado" => settings.bilinear_upscale = match value {
+                    "bilineal" => true,
+                    "cercano" => false,
+                    _ => return Err(error()),
+                },
+                "bloom" => settings.bloom = flag()?,
+                "fxaa" => settings.fxaa = flag()?,
+                "grano" => settings.grain = flag()?,
+                "cabina" => settings.cockpit_effects = flag()?,
+                "prepasada" => settings.depth_prepass = flag()?,
+                "diferido" => settings.deferred = flag()?,
+                "pbr" => settings.pbr = flag()?,
+                "estrellas" => settings.star_count = value.parse().map_err(|_| error())?,
+                "tiempo" => settings.time_scale = value.parse().map_err(|_| error())?,
+                "sensibilidad" => settings.sensitivity = value.parse().map_err(|_| error())?,
+                "fps" => settings.target_fps = TargetFps::from_name(value).ok_or_else(error)?,
+                "pantalla_completa" => settings.fullscreen = flag()?,
+                "pantalla" => {
+                    let (width, height) = value.split_once(' ').ok_or_else(error)?;
+                    settings.screen_width = width.trim().parse().map_err(|_| error())?;
+                    settings.screen_height = height.trim().parse().map_err(|_| error())?;
+                }
+                "semilla" => settings.seed = value.parse().map_err(|_| error())?,
+                _ => return Err(error()),
+            }
+        }
+        if settings.screen_width == 0 || settings.screen_height == 0 {
+            return Err(format!("{}: la pantalla no puede medir 0", path));
+        }
+        if !RENDER_SCALES.contains(&settings.render_scale) {
+            return Err(format!("{}: resolución {} no soportada", path, settings.render_scale));
+        }
+        Ok(settings)
+    }
+
+    pub fn save(&self) -> Result<(), String> {
+        let flag = |value: bool| if value { "si" } else { "no" };
+        let contents = format!(
+            "# Opciones del menú (Esc)\nresolucion {}\nresolucion_dinamica {}\nescalado {}\nbloom {}\nfxaa {}\ngrano {}\ncabina {}\nprepasada {}\ndiferido {}\npbr {}\nestrellas {}\ntiempo {}\nsensibilidad {}\nfps {}\npantalla_completa {}\npantalla {} {}\nsemilla {}\n",
+            self.render_scale,
+            flag(self.dynamic_resolution),
+            if self.bilinear_upscale { "bilineal" } else { "cercano" },
+            flag(self.bloom),
+            flag(self.fxaa),
+            flag(self.grain),
+            flag(self.cockpit_effects),
+            flag(self.depth_prepass),
+            flag(self.deferred),
+            flag(self.pbr),
+            self.star_count,
+            self.time_scale,
+            self.sensitivity,
+            self.target_fps.name(),
+            flag(self.fullscreen),
+            self.screen_width,
+            self.screen_height,
+            self.seed,
+        );
+        fs::write(&self.path, contents).map_err(|err| format!("{}: {}", self.path, err))
+    }
+
+    // Prende o apaga los efectos de la cadena según las opciones
+    pub fn apply_post(&self, chain: &mut PostChain) {
+        chain.set_enabled("bloom", self.bloom);
+        chain.set_enabled("fxaa", self.fxaa);
+        chain.set_enabled("grain", self.grain);
+        chain.set_enabled("vignette", self.cockpit_effects);
+        chain.set_enabled("chromatic_aberration", self.cockpit_effects);
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Item {
+    RenderScale,
+    DynamicResolution,
+    Upscale,
+    Bloom,
+    Fxaa,
+    Grain,
+    CockpitEffects,
+    DepthPrepass,
+    Deferred,
+    Pbr,
+    Stars,
+    TimeScale,
+    Sensitivity,
+    TargetFps,
+    Fullscreen,
+    Resume,
+    Quit,
+}
+
+const ITEMS: [Item; 17] = [
+    Item::RenderScale,
+    Item::DynamicResolution,
+    Item::Upscale,
+    Item::Bloom,
+    Item::Fxaa,
+    Item::Grain,
+    Item::CockpitEffects,
+    Item::DepthPrepass,
+    Item::Deferred,
+    Item::Pbr,
+    Item::Stars,
+    Item::TimeScale,
+    Item::Sensitivity,
+    Item::TargetFps,
+    Item::Fullscreen,
+    Item::Resume,
+    Item::Quit,
+];
+
+// Lo que pasó en el menú durante el cuadro
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum MenuEvent {
+    None,
+    Changed,
+    Quit,
+}
+
+// El valor de la lista que sigue (o precede) al actual; en los extremos
+// se queda donde está
+fn step<T: Copy + PartialOrd>(options: &[T], current: T, forward: bool) -> T {
+    let next = if forward {
+        options.iter().find(|option| **option > current)
+    } else {
+        options.iter().rev().find(|option| **option < current)
+    };
+    next.copied().unwrap_or(current)
+}
+
+// Menú de opciones. Flechas arriba y abajo eligen la fila, izquierda y
+// derecha cambian el valor y Enter activa la fila elegida.
+pub struct SettingsMenu {
+    pub open: bool,
+    selected: usize,
+}
+
+impl SettingsMenu {
+    pub fn new() -> Self {
+        SettingsMenu { open: false, selected: 0 }
+    }
+
+    pub fn handle(&mut self, window: &Window, settings: &mut Settings) -> MenuEvent {
+        let pressed = |key: Key| window.is_key_pressed(key, KeyRepeat::Yes);
+        if pressed(Key::Up) {
+            self.selected = (self.selected + ITEMS.len() - 1) % ITEMS.len();
+        }
+        if pressed(Key::Down) {
+            self.selected = (self.selected + 1) % ITEMS.len();
+        }
+
+        let item = ITEMS[self.selected];
+        let enter = window.is_key_pressed(Key::Enter, KeyRepeat::No);
+        let direction = if pressed(Key::Right) || enter {
+            Some(true)
+        } else if pressed(Key::Left) {
+            Some(false)
+        } else {
+            None
+        };
+        let Some(forward) = direction else {
+            return MenuEvent::None;
+        };
+
+        match item {
+            Item::RenderScale => settings.render_scale = step(&RENDER_SCALES, settings.render_scale, forward),
+            Item::DynamicResolution => settings.dynamic_resolution = !settings.dynamic_resolution,
+            Item::Upscale => settings.bilinear_upscale = !settings.bilinear_upscale,
+            Item::Bloom => settings.bloom = !settings.bloom,
+            Item::Fxaa => settings.fxaa = !settings.fxaa,
+            Item::Grain => settings.grain = !settings.grain,
+            Item::CockpitEffects => settings.cockpit_effects = !settings.cockpit_effects,
+            Item::DepthPrepass => settings.depth_prepass = !settings.depth_prepass,
+            Item::Deferred => settings.deferred = !settings.deferred,
+            Item::Pbr => settings.pbr = !settings.pbr,
+            Item::Stars => settings.star_count = step(&STAR_COUNTS, settings.star_count, forward),
+            Item::TimeScale => settings.time_scale = step(&TIME_SCALES, settings.time_scale, forward),
+            Item::Sensitivity => settings.sensitivity = step(&SENSITIVITIES, settings.sensitivity, forward),
+            Item::TargetFps if forward => settings.target_fps = settings.target_fps.next(),
+            Item::TargetFps => settings.target_fps = settings.target_fps.previous(),
+            Item::Fullscreen => settings.fullscreen = !settings.fullscreen,
+            Item::Resume if enter => {
+                self.open = false;
+                return MenuEvent::None;
+            }
+            Item::Quit if enter => return MenuEvent::Quit,
+            Item::Resume | Item::Quit => return MenuEvent::None,
+        }
+        MenuEvent::Changed
+    }
+
+    pub fn render(&self, framebuffer: &mut Framebuffer, uniforms: &Uniforms, settings: &Settings, pixel_scale: usize) {
+        let flag = |value: bool| if value { "Si" } else { "No" }.to_string();
+        let rows: Vec<(String, String)> = ITEMS.iter().enumerate()
+            .map(|(i, item)| {
+                let (label, value) = match item {
+                    Item::RenderScale => ("Resolución", format!("{}x", settings.render_scale)),
+                    Item::DynamicResolution => ("Resolución dinámica", flag(settings.dynamic_resolution)),
+                    Item::Upscale => ("Escalado", if settings.bilinear_upscale { "Bilineal" } else { "Cercano" }.to_string()),
+                    Item::Bloom => ("Bloom", flag(settings.bloom)),
+                    Item::Fxaa => ("FXAA", flag(settings.fxaa)),
+                    Item::Grain => ("Grano", flag(settings.grain)),
+                    Item::CockpitEffects => ("Efectos de cabina", flag(settings.cockpit_effects)),
+                    Item::DepthPrepass => ("Pre-pasada de profundidad", flag(settings.depth_prepass)),
+                    Item::Deferred => ("Sombreado diferido", flag(settings.deferred)),
+                    Item::Pbr => ("Materiales PBR", flag(settings.pbr)),
+                    Item::Stars => ("Estrellas", settings.star_count.to_string()),
+                    Item::TimeScale => ("Velocidad del tiempo", format!("{}x", settings.time_scale)),
+                    Item::Sensitivity => ("Sensibilidad", format!("{}x", settings.sensitivity)),
+                    Item::TargetFps => ("Cuadros por segundo", match settings.target_fps {
+                        TargetFps::Uncapped => "Sin límite".to_string(),
+                        target => target.name().to_string(),
+                    }),
+                    Item::Fullscreen => ("Pantalla completa", flag(settings.fullscreen)),
+                    Item::Resume => ("Continuar", String::new()),
+                    Item::Quit => ("Salir del programa", String::new()),
+                };
+                let marker = if i == self.selected { "> " } else { "  " };
+                (format!("{}{}", marker, label), value)
+            })
+            .collect();
+        panel::render_panel(framebuffer, uniforms, panel::Anchor::Center, "Opciones", &rows, pixel_scale);
+    }
+}
@@ -4,7 +4,10 @@ use crate::vertex::Vertex;
 use crate::Uniforms;
 use crate::fragment::Fragment;
 use crate::color::Color;
-use crate::CelestialBody;
+use crate::lighting::{self, SurfaceMaterial};
+use crate::FAR_PLANE;
+use crate::material::ShaderId;
+use crate::noise_utils::{fbm, ridged, turbulence, domain_warp, warped_fbm, animated_noise, animated_fbm, FractalParams};
 
 pub fn vertex_shader(vertex: &Vertex, uniforms: &Uniforms) -> Vertex {
     let position = Vec4::new(
@@ -20,7 +23,7 @@ pub fn vertex_shader(vertex: &Vertex, uniforms: &Uniforms) -> Vertex {
     let transformed_position = Vec4::new(
         transformed.x / w,
         transformed.y / w,
-        transformed.z / w,
+        encode_depth(&transformed, uniforms.depth_mode),
         1.0
     );
 
@@ -37,32 +40,260 @@ pub fn vertex_shader(vertex: &Vertex, uniforms: &Uniforms) -> Vertex {
         tex_coords: vertex.tex_coords,
         color: vertex.color,
         transformed_position: Vec3::new(screen_position.x, screen_position.y, screen_position.z),
-        transformed_normal: transformed_normal
+        transformed_normal
     }
 }
 
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum DepthMode {
+  Standard,
+  Logarithmic,
+}
+
+// Profundidad a partir de la posición en clip space. La logarítmica reparte
+// la precisión según la distancia y evita el z-fighting con near=0.1, far=1000.
+pub fn encode_depth(clip: &Vec4, mode: DepthMode) -> f32 {
+  match mode {
+    DepthMode::Standard => clip.z / clip.w,
+    DepthMode::Logarithmic => {
+      let w = clip.w.max(1e-6);
+      2.0 * (w + 1.0).ln() / (FAR_PLANE + 1.0).ln() - 1.0
+    }
+  }
+}
+
+// Antes del shader de cada cuerpo se suman las luces de la escena: la
+// intensidad reemplaza a la del rasterizador y el color de las luces tiñe el
+// resultado. El sol brilla solo y la nave y la estación pueden usar la BRDF.
+// El material del objeto elige el shader y tiñe lo que sale, y el color de
+// los vértices lo vuelve a teñir: sirve para sombras horneadas o degradés
+// sin gastar en el shader.
 pub fn fragment_shader(fragment: &Fragment, uniforms: &Uniforms) -> Color {
-  match uniforms.current_body {
-      CelestialBody::Sun => sun_shader(fragment, uniforms),
-      CelestialBody::RockyPlanet => rocky_planet_shader(fragment, uniforms),
-      CelestialBody::GasGiant => gas_giant_shader(fragment, uniforms),
-      CelestialBody::CloudyPlanet => cloudy_planet_shader(fragment, uniforms),
-      CelestialBody::RingedPlanet => ring_shader(fragment, uniforms),
-      CelestialBody::IcePlanet => ice_planet_shader(fragment, uniforms),
-      CelestialBody::ColorPlanet => colorful_planet_shader(fragment, uniforms),
-      CelestialBody::Moon => moon_shader(fragment, uniforms),
-      CelestialBody::OceanPlanet => ocean_planet_shader(fragment, uniforms),
-      CelestialBody::AuroraPlanet => aurora_planet_shader(fragment, uniforms),
-      CelestialBody::NaturePlanet => nature_planet_shader(fragment, uniforms),
-      CelestialBody::Spaceship => spaceship_shader(fragment, uniforms),
+  if uniforms.debug_view != DebugView::Off {
+    return debug_shader(fragment, uniforms);
   }
+  let material = uniforms.material;
+  let tint = uniforms.srgb(material.tint) * uniforms.srgb(fragment.color);
+  match material.shader {
+    ShaderId::Sun => return sun_shader(fragment, uniforms) * tint,
+    ShaderId::Spaceship | ShaderId::SpaceStation if uniforms.pbr_materials => return pbr_shader(fragment, uniforms, tint),
+    _ => {}
+  }
+  let (intensity, light_tint) = lighting::illuminate(uniforms.lights, world_position(fragment, uniforms), fragment.normal);
+  let lit = Fragment { intensity, ..*fragment };
+  let fragment = &lit;
+  let color = match material.shader {
+      ShaderId::Sun => sun_shader(fragment, uniforms),
+      ShaderId::Rocky => rocky_planet_shader(fragment, uniforms),
+      ShaderId::GasGiant => gas_giant_shader(fragment, uniforms),
+      ShaderId::Cloudy => cloudy_planet_shader(fragment, uniforms),
+      ShaderId::Ringed => ring_shader(fragment, uniforms),
+      ShaderId::Ice => ice_planet_shader(fragment, uniforms),
+      ShaderId::Colorful => colorful_planet_shader(fragment, uniforms),
+      ShaderId::Moon => moon_shader(fragment, uniforms),
+      ShaderId::Ocean => ocean_planet_shader(fragment, uniforms),
+      ShaderId::Aurora => aurora_planet_shader(fragment, uniforms),
+      ShaderId::Nature => nature_planet_shader(fragment, uniforms),
+      ShaderId::Lava => lava_planet_shader(fragment, uniforms),
+      ShaderId::Desert => desert_planet_shader(fragment, uniforms),
+      ShaderId::Spaceship => spaceship_shader(fragment, uniforms),
+      ShaderId::SpaceStation => space_station_shader(fragment, uniforms),
+  };
+  color * light_tint * tint
+}
+
+// Lo que sale en lugar del color mientras se desarrolla el pipeline (F10)
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum DebugView {
+  Off,
+  // Normal en el mundo, de -1..1 a 0..1 por canal
+  Normals,
+  // Distancia a la cámara en gris: blanco cerca, negro a DEBUG_DEPTH_RANGE
+  Depth,
+  // Tablero de ajedrez con las coordenadas de textura
+  TexCoords,
+  // Bordes de los triángulos sobre el sombreado plano
+  Barycentric,
+  // Color de los vértices, sin luz
+  VertexColor,
 }
+
+// Distancia a la que la vista de profundidad llega a negro
+const DEBUG_DEPTH_RANGE: f32 = 60.0;
+
+impl DebugView {
+  pub fn next(self) -> Self {
+    match self {
+      DebugView::Off => DebugView::Normals,
+      DebugView::Normals => DebugView::Depth,
+      DebugView::Depth => DebugView::TexCoords,
+      DebugView::TexCoords => DebugView::Barycentric,
+      DebugView::Barycentric => DebugView::VertexColor,
+      DebugView::VertexColor => DebugView::Off,
+    }
+  }
+
+  pub fn label(self) -> &'static str {
+    match self {
+      DebugView::Off => "Sombreado",
+      DebugView::Normals => "Normales",
+      DebugView::Depth => "Profundidad",
+      DebugView::TexCoords => "Coordenadas UV",
+      DebugView::Barycentric => "Bordes",
+      DebugView::VertexColor => "Color de vértices",
+    }
+  }
+}
+
+// No pasa por las luces ni por el material: lo que se ve es el dato tal
+// cual sale del rasterizador
+fn debug_shader(fragment: &Fragment, uniforms: &Uniforms) -> Color {
+  match uniforms.debug_view {
+    DebugView::Off | DebugView::Normals => {
+      let normal = fragment.normal * 0.5 + Vec3::new(0.5, 0.5, 0.5);
+      Color::from_rgb_f32(normal.x, normal.y, normal.z)
+    }
+    DebugView::Depth => {
+      let distance = (uniforms.camera_position - world_position(fragment, uniforms)).magnitude();
+      let gray = 1.0 - (distance / DEBUG_DEPTH_RANGE).min(1.0);
+      Color::from_rgb_f32(gray, gray, gray)
+    }
+    DebugView::TexCoords => {
+      let cells = (fragment.tex_coords * 16.0).map(|value| value.floor() as i32);
+      let even = (cells.x + cells.y).rem_euclid(2) == 0;
+      let corner = Color::from_rgb_f32(fragment.tex_coords.x.fract(), fragment.tex_coords.y.fract(), 0.0);
+      if even { corner } else { corner * 0.35 }
+    }
+    DebugView::Barycentric => {
+      let weights = fragment.barycentric;
+      let edge = smoothstep(0.0, 0.04, weights.x.min(weights.y).min(weights.z));
+      let face = Color::from_rgb_f32(0.25, 0.25, 0.3) * (0.3 + 0.7 * fragment.intensity);
+      Color::from_rgb_f32(1.0, 0.85, 0.2).lerp(&face, edge)
+    }
+    DebugView::VertexColor => uniforms.srgb(fragment.color),
+  }
+}
+
+fn smoothstep(edge0: f32, edge1: f32, x: f32) -> f32 {
+  let t = ((x - edge0) / (edge1 - edge0)).clamp(0.0, 1.0);
+  t * t * (3.0 - 2.0 * t)
+}
+
+fn world_position(fragment: &Fragment, uniforms: &Uniforms) -> Vec3 {
+  let position = fragment.vertex_position;
+  let world = uniforms.model_matrix * Vec4::new(position.x, position.y, position.z, 1.0);
+  Vec3::new(world.x, world.y, world.z)
+}
+
+// Coseno entre la normal del fragmento y la dirección hacia el sol
+fn sun_facing(fragment: &Fragment, uniforms: &Uniforms) -> f32 {
+  let to_sun = (uniforms.light_position - world_position(fragment, uniforms)).normalize();
+  fragment.normal.dot(&to_sun)
+}
+
+// Fresnel de Schlick con la dirección a la cámara: `f0` de frente y 1 en el
+// borde. Con `power` más alto el efecto se pega más al borde.
+fn fresnel(fragment: &Fragment, uniforms: &Uniforms, f0: f32, power: i32) -> f32 {
+  let to_camera = (uniforms.camera_position - world_position(fragment, uniforms)).normalize();
+  let facing = fragment.normal.dot(&to_camera).abs();
+  f0 + (1.0 - f0) * (1.0 - facing).powi(power)
+}
+
+// Borde que se enciende cuando el sol queda detrás del cuerpo: la luz que
+// atraviesa la atmósfera o el hielo hacia la cámara. 0 con el sol a la espalda.
+fn backlit_rim(fragment: &Fragment, uniforms: &Uniforms, f0: f32, power: i32) -> f32 {
+  let position = world_position(fragment, uniforms);
+  let to_camera = (uniforms.camera_position - position).normalize();
+  let to_sun = (uniforms.light_position - position).normalize();
+  let behind = smoothstep(0.0, 0.8, -to_camera.dot(&to_sun));
+  fresnel(fragment, uniforms, f0, power) * behind
+}
+
+// Dispersión atmosférica en el borde del planeta: azul en el lado de día,
+// anaranjada y más intensa cerca del terminador, y un halo a contraluz
+fn atmosphere_rim(fragment: &Fragment, uniforms: &Uniforms, base: Color) -> Color {
+  let sky_blue = uniforms.palette(90, 160, 255);
+  let sunset_orange = uniforms.palette(255, 140, 60);
+
+  let rim = fresnel(fragment, uniforms, 0.0, 3);
+
+  let facing = sun_facing(fragment, uniforms);
+  let terminator = 1.0 - (facing.abs() * 2.5).min(1.0);
+  let lit = smoothstep(-0.3, 0.2, facing);
+
+  let tint = sky_blue.lerp(&sunset_orange, terminator);
+  let strength = rim * lit * (0.6 + terminator * 0.6);
+  let backlight = backlit_rim(fragment, uniforms, 0.0, 4);
+  base + tint * strength + sunset_orange.lerp(&sky_blue, 0.4) * (backlight * 0.8)
+}
+
+// Hash entero barato para valores pseudoaleatorios por celda
+fn hash_cell(x: i32, y: i32, z: i32, seed: u32) -> u32 {
+  let mut h = seed.wrapping_mul(0x27d4eb2d)
+      ^ (x as u32).wrapping_mul(0x8da6b343)
+      ^ (y as u32).wrapping_mul(0xd8163841)
+      ^ (z as u32).wrapping_mul(0xcb1ab31f);
+  h ^= h >> 15;
+  h = h.wrapping_mul(0x2c1b3c6d);
+  h ^= h >> 12;
+  h
+}
+
+fn hash_unit(h: u32, channel: u32) -> f32 {
+  let mixed = hash_cell(channel as i32, 0, 0, h);
+  (mixed & 0xFFFF) as f32 / 65535.0
+}
+
+// Campo de cráteres tipo Worley: cada celda puede tener un cráter con cuenco
+// hundido y borde elevado. Devuelve (cuenco, borde) en 0..1.
+fn crater_field(position: Vec3, frequency: f32, seed: u32) -> (f32, f32) {
+  let p = position.normalize() * frequency;
+  let cell = Vec3::new(p.x.floor(), p.y.floor(), p.z.floor());
+
+  let mut bowl: f32 = 0.0;
+  let mut rim: f32 = 0.0;
+
+  for dz in -1..=1 {
+    for dy in -1..=1 {
+      for dx in -1..=1 {
+        let cx = cell.x as i32 + dx;
+        let cy = cell.y as i32 + dy;
+        let cz = cell.z as i32 + dz;
+        let h = hash_cell(cx, cy, cz, seed);
+
+        if hash_unit(h, 0) > 0.6 {
+          continue;
+        }
+
+        let center = Vec3::new(
+          cx as f32 + hash_unit(h, 1),
+          cy as f32 + hash_unit(h, 2),
+          cz as f32 + hash_unit(h, 3),
+        );
+        let radius = 0.15 + hash_unit(h, 4) * 0.3;
+        let d = (p - center).magnitude() / radius;
+
+        if d < 1.0 {
+          bowl = bowl.max(1.0 - d * d);
+        }
+        rim = rim.max((-((d - 1.0) / 0.18).powi(2)).exp());
+      }
+    }
+  }
+
+  (bowl, rim)
+}
+
 fn spaceship_shader(fragment: &Fragment, uniforms: &Uniforms) -> Color {
+    spaceship_albedo(fragment, uniforms) * fragment.intensity
+}
+
+fn spaceship_albedo(fragment: &Fragment, uniforms: &Uniforms) -> Color {
         let position = fragment.vertex_position;
         let time = uniforms.time as f32 * 0.03;
-        let pure_white = Color::new(255, 255, 255);    
-        let soft_white = Color::new(245, 245, 245);    
-        let bright_white = Color::new(250, 250, 252);  
+        let pure_white = uniforms.palette(255, 255, 255);    
+        let soft_white = uniforms.palette(245, 245, 245);    
+        let bright_white = uniforms.palette(250, 250, 252);  
     
         let gradient = (position.y + 1.0) * 0.5;
 
@@ -75,22 +306,72 @@ fn spaceship_shader(fragment: &Fragment, uniforms: &Uniforms) -> Color {
             soft_white.lerp(&bright_white, shine_intensity)
         };
 
-        base_color * fragment.intensity * (0.95 + shine_intensity * 0.05)
+        base_color * (0.95 + shine_intensity * 0.05)
     }
     
+fn space_station_shader(fragment: &Fragment, uniforms: &Uniforms) -> Color {
+    let (albedo, _, emission) = space_station_surface(fragment, uniforms);
+    albedo * fragment.intensity + emission
+}
+
+// Casco metálico en paneles, paneles solares azules arriba y abajo del
+// núcleo y ventanas del anillo que se encienden y apagan. Devuelve el color
+// de la superficie, cuál de las superficies del material le toca y la luz
+// propia de las ventanas.
+fn space_station_surface(fragment: &Fragment, uniforms: &Uniforms) -> (Color, SurfaceMaterial, Color) {
+    let position = fragment.vertex_position;
+    let hull = uniforms.palette(150, 155, 165);
+    let seam = uniforms.palette(90, 95, 105);
+    let solar = uniforms.palette(30, 60, 140);
+    let window_light = uniforms.palette(255, 220, 140);
+
+    if position.y.abs() > 0.38 {
+        // Celdas del panel solar con una grilla clara
+        let grid = ((position.x * 20.0).fract().abs() < 0.1) || ((position.z * 20.0).fract().abs() < 0.1);
+        let base = if grid { solar * 1.6 } else { solar };
+        return (base, uniforms.material.panel, Color::black());
+    }
+
+    // Juntas entre placas según el ángulo alrededor del eje
+    let angle = position.z.atan2(position.x);
+    let plate = (angle * 16.0 / std::f32::consts::PI).fract().abs();
+    let (base, material) = if plate < 0.06 { (seam, uniforms.material.trim) } else { (hull, uniforms.material.surface) };
+
+    // Ventanas en el anillo exterior, cada una con su propio parpadeo
+    let radius = (position.x * position.x + position.z * position.z).sqrt();
+    if radius > 0.95 && position.y.abs() < 0.05 {
+        let window = (angle * 48.0 / std::f32::consts::PI).floor();
+        let blink = ((window * 12.9898).sin() * 43758.547).fract().abs();
+        let on = if (uniforms.time as f32 * 0.02 + blink * 10.0).sin() > -0.6 { 0.8 } else { 0.0 };
+        return (base, material, window_light * on);
+    }
+    (base, material, Color::black())
+}
+
+// La nave y la estación con la BRDF: las luces ya vienen con su color
+// `tint` es el del material con el de los vértices; tiñe el albedo
+fn pbr_shader(fragment: &Fragment, uniforms: &Uniforms, tint: Color) -> Color {
+    let position = world_position(fragment, uniforms);
+    let to_camera = (uniforms.camera_position - position).normalize();
+    let (albedo, material, emission) = match uniforms.material.shader {
+        ShaderId::SpaceStation => space_station_surface(fragment, uniforms),
+        _ => (spaceship_albedo(fragment, uniforms), uniforms.material.surface, Color::black()),
+    };
+    lighting::shade_pbr(uniforms.lights, position, fragment.normal, to_camera, albedo * tint, material) + emission
+}
+
 fn colorful_planet_shader(fragment: &Fragment, uniforms: &Uniforms) -> Color {
     let position = fragment.vertex_position;
     let time = uniforms.time as f32 * 0.01;
 
-    let color1 = Color::new(245, 56, 121);   
-    let color2 = Color::new(245, 140, 105); 
-    let color3 = Color::new(245, 115, 105); 
-    let color4 = Color::new(245, 105, 238); 
-    let color5 = Color::new(245, 159, 95);  
-    let color6 = Color::new(245, 168, 162); 
+    let color1 = uniforms.palette(245, 56, 121);   
+    let color2 = uniforms.palette(245, 140, 105); 
+    let color3 = uniforms.palette(245, 115, 105); 
+    let color4 = uniforms.palette(245, 105, 238); 
+    let color5 = uniforms.palette(245, 159, 95);  
 
-    let ring1_color = Color::new(245, 7, 123); 
-    let ring2_color = Color::new(245, 166, 195);  
+    let ring1_color = uniforms.palette(245, 7, 123); 
+    let ring2_color = uniforms.palette(245, 166, 195);  
 
     let curve_pattern = uniforms.noise.get_noise_3d(
         position.x * 5.0 + time * 1.5,
@@ -128,8 +409,8 @@ fn sun_shader(fragment: &Fragment, uniforms: &Uniforms) -> Color {
   let position = fragment.vertex_position;
   let time = uniforms.time as f32 * 0.01;
 
-  let core_color = Color::new(255, 200, 0);      
-  let corona_color = Color::new(255, 100, 0);    
+  let core_color = uniforms.palette(255, 200, 0);      
+  let corona_color = uniforms.palette(255, 100, 0);    
   
   let plasma1 = uniforms.noise.get_noise_3d(
       position.x * 50.0 + time,
@@ -152,18 +433,20 @@ fn sun_shader(fragment: &Fragment, uniforms: &Uniforms) -> Color {
   let combined_noise = (plasma1 + plasma2) * 0.5;
   let final_color = core_color.lerp(&corona_color, combined_noise.abs());
 
-  let brightness = 1.0 + corona * 0.5;
-  
-  final_color * brightness * fragment.intensity
+  // Más de 1.0: el tonemapping lo comprime en vez de recortarlo a blanco
+  let brightness = 1.6 + corona * 1.2;
+
+  // El sol es emisivo: no depende de la iluminación
+  final_color * brightness
 }
 
 fn rocky_planet_shader(fragment: &Fragment, uniforms: &Uniforms) -> Color {
   let position = fragment.vertex_position;
   let time = uniforms.time as f32 * 0.001;
 
-  let desert_color = Color::new(180, 80, 20);     
-  let crater_color = Color::new(120, 50, 10);     
-  let highland_color = Color::new(200, 100, 30);  
+  let desert_color = uniforms.palette(180, 80, 20);     
+  let crater_color = uniforms.palette(120, 50, 10);     
+  let highland_color = uniforms.palette(200, 100, 30);  
   
   let terrain = uniforms.noise.get_noise_3d(
       position.x * 100.0,
@@ -189,31 +472,37 @@ fn rocky_planet_shader(fragment: &Fragment, uniforms: &Uniforms) -> Color {
   } else if terrain > 0.3 {
       final_color = highland_color;
   }
+
+  let (bowl, rim) = crater_field(position, 12.0, uniforms.noise.seed as u32);
+  let rim_color = uniforms.palette(215, 120, 50);
+  final_color = final_color.lerp(&crater_color, bowl * 0.8);
+  final_color = final_color.lerp(&rim_color, rim * 0.5);
   
-  let dust_color = Color::new(200, 150, 100);
+  let dust_color = uniforms.palette(200, 150, 100);
   final_color = final_color.lerp(&dust_color, dust.abs() * 0.3);
   
-  final_color * fragment.intensity
+  atmosphere_rim(fragment, uniforms, final_color * fragment.intensity)
 }
 
 fn cloudy_planet_shader(fragment: &Fragment, uniforms: &Uniforms) -> Color {
   let position = fragment.vertex_position;
   let time = uniforms.time as f32 * 0.01;
 
-  let surface_color = Color::new(30, 100, 200);  
-  let land_color = Color::new(50, 120, 50);      
-  let cloud_color = Color::new(255, 255, 255);   
+  let surface_color = uniforms.palette(30, 100, 200);  
+  let land_color = uniforms.palette(50, 120, 50);      
+  let cloud_color = uniforms.palette(255, 255, 255);   
   
   let surface = uniforms.noise.get_noise_2d(
       position.x * 100.0,
       position.y * 100.0
   );
   
-  let clouds = uniforms.noise.get_noise_3d(
-      position.x * 50.0 + time,
-      position.y * 50.0 + time * 0.5,
-      time
-  );
+  let clouds = warped_fbm(
+      uniforms.noise,
+      Vec3::new(position.x * 50.0 + time, position.y * 50.0 + time * 0.5, time),
+      0.8,
+      uniforms.material.noise.fractal
+  ) * 1.3;
   
   let base_color = if surface > 0.2 {
       land_color
@@ -226,16 +515,33 @@ fn cloudy_planet_shader(fragment: &Fragment, uniforms: &Uniforms) -> Color {
   } else {
       base_color
   };
-  
-  final_color * fragment.intensity
+
+  // Lado nocturno: luces de ciudades sobre los continentes, tapadas por las nubes
+  let night_base = uniforms.palette(5, 10, 25);
+  let city_color = uniforms.palette(255, 200, 120);
+  let cities = uniforms.noise.get_noise_3d(
+      position.x * 400.0,
+      position.y * 400.0,
+      position.z * 400.0
+  );
+  let city_lights = if surface > 0.2 && cities > 0.4 {
+      (cities - 0.4) * 2.5 * (1.0 - clouds.max(0.0))
+  } else {
+      0.0
+  };
+  let night_color = night_base.lerp(&city_color, city_lights);
+
+  // Terminador suave entre el hemisferio iluminado por el sol y el nocturno
+  let daylight = smoothstep(-0.15, 0.25, sun_facing(fragment, uniforms));
+  night_color.lerp(&(final_color * fragment.intensity), daylight)
 }
 
 fn ring_shader(fragment: &Fragment, uniforms: &Uniforms) -> Color {
   let position = fragment.vertex_position;
   let time = uniforms.time as f32 * 0.001;
   
-  let ring1_color = Color::new(180, 150, 120);  
-  let ring2_color = Color::new(100, 80, 60);  
+  let ring1_color = uniforms.palette(180, 150, 120);  
+  let ring2_color = uniforms.palette(100, 80, 60);  
   
   let ring_pattern = uniforms.noise.get_noise_3d(
       position.x * 200.0 + time,
@@ -263,27 +569,21 @@ fn ice_planet_shader(fragment: &Fragment, uniforms: &Uniforms) -> Color {
     let time = uniforms.time as f32 * 0.002;
 
     // Paleta de colores expandida para efectos de hielo
-    let ice_color = Color::new(220, 240, 255);        // Hielo superficial
-    let deep_ice_color = Color::new(120, 180, 255);   // Hielo profundo
-    let crack_color = Color::new(80, 130, 255);       // Grietas profundas
-    let crystal_glow = Color::new(230, 255, 255);     // Brillo cristalino
-    let aurora_ice = Color::new(160, 255, 220);       // Hielo con aurora
-    let deep_blue = Color::new(40, 100, 255);         // Azul profundo
-    let frost_white = Color::new(255, 255, 255);      // Escarcha brillante
-    let twilight_ice = Color::new(180, 200, 255);     // Hielo crepuscular
+    let ice_color = uniforms.palette(220, 240, 255);        // Hielo superficial
+    let deep_ice_color = uniforms.palette(120, 180, 255);   // Hielo profundo
+    let crack_color = uniforms.palette(80, 130, 255);       // Grietas profundas
+    let crystal_glow = uniforms.palette(230, 255, 255);     // Brillo cristalino
+    let aurora_ice = uniforms.palette(160, 255, 220);       // Hielo con aurora
+    let deep_blue = uniforms.palette(40, 100, 255);         // Azul profundo
+    let frost_white = uniforms.palette(255, 255, 255);      // Escarcha brillante
+    let twilight_ice = uniforms.palette(180, 200, 255);     // Hielo crepuscular
 
     // Capas de hielo con variación temporal
-    let ice_base = uniforms.noise.get_noise_3d(
-        position.x * 80.0 + time * 0.1,
-        position.y * 80.0,
-        position.z * 80.0
-    ).abs();
-
-    let ice_detail = uniforms.noise.get_noise_3d(
-        position.x * 150.0 + time * 0.2,
-        position.y * 150.0,
-        position.z * 150.0
-    ).abs();
+    let ice_layers = turbulence(
+        uniforms.noise,
+        Vec3::new(position.x * 80.0 + time * 0.1, position.y * 80.0, position.z * 80.0),
+        FractalParams::new(3, 1.9, 0.45)
+    );
 
     // Sistema de grietas dinámicas
     let cracks_primary = uniforms.noise.get_noise_3d(
@@ -324,7 +624,6 @@ fn ice_planet_shader(fragment: &Fragment, uniforms: &Uniforms) -> Color {
     ).abs();
 
     // Color base con capas de hielo
-    let ice_layers = ice_base * 0.7 + ice_detail * 0.3;
     let mut final_color = ice_color.lerp(&deep_ice_color, ice_layers);
 
     // Sistema de grietas mejorado
@@ -372,15 +671,18 @@ fn ice_planet_shader(fragment: &Fragment, uniforms: &Uniforms) -> Color {
 
     // Ajuste final de intensidad con variación de profundidad
     let depth_intensity = 1.0 - (depth * 0.3);
-    final_color * fragment.intensity * depth_intensity
+
+    // A contraluz el hielo deja pasar el sol por el borde
+    let backlight = backlit_rim(fragment, uniforms, 0.02, 4);
+    final_color * fragment.intensity * depth_intensity + frost_white * (backlight * 0.9)
 }
 fn moon_shader(fragment: &Fragment, uniforms: &Uniforms) -> Color {
   let position = fragment.vertex_position;
   let time = uniforms.time as f32 * 0.001;
 
-  let base_color = Color::new(180, 180, 180);  // Gris claro
-  let crater_color = Color::new(100, 100, 100); // Gris oscuro
-  let dust_color = Color::new(150, 150, 150);   // Gris medio
+  let base_color = uniforms.palette(180, 180, 180);  // Gris claro
+  let crater_color = uniforms.palette(100, 100, 100); // Gris oscuro
+  let dust_color = uniforms.palette(150, 150, 150);   // Gris medio
 
   // Patrón base de cráteres
   let craters = uniforms.noise.get_noise_3d(
@@ -418,6 +720,14 @@ fn moon_shader(fragment: &Fragment, uniforms: &Uniforms) -> Color {
       final_color = final_color.lerp(&crater_color, (surface_details - 0.8) * 0.5);
   }
 
+  // Cráteres grandes y pequeños con cuenco oscuro y borde claro
+  let rim_color = uniforms.palette(215, 215, 215);
+  for (frequency, strength) in [(6.0, 1.0), (16.0, 0.6)] {
+      let (bowl, rim) = crater_field(position, frequency, uniforms.noise.seed as u32);
+      final_color = final_color.lerp(&crater_color, bowl * 0.7 * strength);
+      final_color = final_color.lerp(&rim_color, rim * 0.5 * strength);
+  }
+
   final_color * fragment.intensity
 }
 
@@ -427,10 +737,10 @@ fn ocean_planet_shader(fragment: &Fragment, uniforms: &Uniforms) -> Color {
     let time = uniforms.time as f32 * 0.01;
 
     //capas de color
-    let deep_ocean = Color::new(0, 51, 102);     
-    let shallow_water = Color::new(0, 153, 204); 
-    let coral_reef = Color::new(64, 224, 208);   
-    let surface_foam = Color::new(240, 255, 255);
+    let deep_ocean = uniforms.palette(0, 51, 102);     
+    let shallow_water = uniforms.palette(0, 153, 204); 
+    let coral_reef = uniforms.palette(64, 224, 208);   
+    let surface_foam = uniforms.palette(240, 255, 255);
 
     // Patrones de oleaje
     let waves = uniforms.noise.get_noise_3d(
@@ -465,111 +775,81 @@ fn ocean_planet_shader(fragment: &Fragment, uniforms: &Uniforms) -> Color {
         final_color = final_color.lerp(&surface_foam, (waves - 0.7) * 0.8);
     }
 
-    final_color * fragment.intensity
+    // El agua refleja más mirada de costado, solo donde le da el sol
+    let sheen = fresnel(fragment, uniforms, 0.02, 5) * smoothstep(-0.1, 0.3, sun_facing(fragment, uniforms));
+    final_color = final_color.lerp(&surface_foam, sheen * 0.4);
+
+    atmosphere_rim(fragment, uniforms, final_color * fragment.intensity)
 }
 fn nature_planet_shader(fragment: &Fragment, uniforms: &Uniforms) -> Color {
     let position = fragment.vertex_position;
-    let time = uniforms.time as f32 * 0.005;
-
-    let moss_green = Color::new(98, 185, 82);
-    let soil_brown = Color::new(121, 85, 61);
-    let deep_forest = Color::new(34, 93, 44);
-    let misty_fog = Color::new(180, 200, 195);
-    let mossy_neon = Color::new(128, 255, 170);
-    let rich_bark = Color::new(121, 85, 72);
-    let biolum_blue = Color::new(64, 224, 208);
-    let golden_pollen = Color::new(255, 223, 128);
-    let purple_fungi = Color::new(147, 112, 219);
-    let coral_accent = Color::new(255, 127, 80);
-
-    let veg_base = uniforms.noise.get_noise_3d(
-        position.x * 3.5 + time * 0.8,
-        position.y * 3.5,
-        position.z * 3.5
-    ).sin() * 0.5 + 0.5;
-
-    let veg_detail = uniforms.noise.get_noise_3d(
-        position.x * 8.0 + time * 0.4,
-        position.y * 8.0 + time * 0.3,
-        position.z * 8.0
-    ).sin() * 0.5 + 0.5;
-
-    let vegetation_pattern = veg_base * 0.7 + veg_detail * 0.3;
-
-    let latitude = position.y.asin();
-    let biome_mix = (latitude * 3.0).cos() * 0.5 + 0.5;
-
-    let terrain_spiral = ((position.x * 7.0 + time * 1.2).sin() * 
-                         (position.y * 7.0 + time).cos() * 
-                         (position.z * 7.0 + time * 0.8).sin()).abs();
-
-    let line_pattern1 = (position.x * 10.0 + position.z * 5.0 + time * 1.5).sin() * 0.5 + 0.5;
-    let line_pattern2 = (position.y * 15.0 + position.x * 7.0 + time * 1.2).cos() * 0.5 + 0.5;
 
-    let river_pattern = uniforms.noise.get_noise_3d(
-        position.x * 5.0 + time * 0.2,
-        position.y * 5.0,
-        position.z * 5.0
-    ).abs();
-
-    let mut final_color = moss_green.lerp(&deep_forest, vegetation_pattern);
-    final_color = final_color.lerp(&soil_brown, biome_mix * 0.4);
-
-    if terrain_spiral > 0.4 {
-        final_color = final_color.lerp(&rich_bark, (terrain_spiral - 0.4) * 0.8);
-    }
-
-    if line_pattern1 > 0.7 {
-        final_color = final_color.lerp(&purple_fungi, (line_pattern1 - 0.7) * 1.3);
-    }
-    if line_pattern2 > 0.6 {
-        final_color = final_color.lerp(&coral_accent, (line_pattern2 - 0.6) * 0.8);
-    }
-
-    let biolum_pattern = (position.magnitude() * 8.0 + time).sin().abs();
-    if biolum_pattern > 0.8 {
-        final_color = final_color.lerp(&biolum_blue, (biolum_pattern - 0.8) * 2.0);
-    }
-
-    let pollen = uniforms.noise.get_noise_3d(
-        position.x * 20.0 + time * 2.0,
-        position.y * 20.0 + time * 1.5,
-        position.z * 20.0
-    ).abs();
-    if pollen > 0.93 {
-        final_color = final_color.lerp(&golden_pollen, (pollen - 0.93) * 15.0);
-    }
+    let deep_ocean = uniforms.palette(20, 60, 130);
+    let shallow_ocean = uniforms.palette(40, 120, 180);
+    let beach = uniforms.palette(220, 205, 150);
+    let grassland = uniforms.palette(120, 170, 70);
+    let forest = uniforms.palette(34, 93, 44);
+    let jungle = uniforms.palette(20, 120, 60);
+    let desert = uniforms.palette(200, 170, 110);
+    let mountain = uniforms.palette(120, 105, 90);
+    let snow = uniforms.palette(245, 250, 255);
+
+    // Dos canales de ruido independientes: elevación y humedad
+    let elevation = fbm(uniforms.noise, position * 6.0, uniforms.material.noise.fractal) * 1.4;
+
+    let moisture = uniforms.noise.get_noise_3d(
+        position.x * 4.0 + 300.0,
+        position.y * 4.0 + 300.0,
+        position.z * 4.0
+    ) * 0.5 + 0.5;
+
+    let latitude = position.normalize().y.abs();
+
+    let sea_level = 0.0;
+    let mut final_color = if elevation < sea_level - 0.2 {
+        deep_ocean
+    } else if elevation < sea_level {
+        deep_ocean.lerp(&shallow_ocean, (elevation - sea_level + 0.2) * 5.0)
+    } else if elevation < sea_level + 0.05 {
+        beach
+    } else if elevation < 0.45 {
+        // Tierras bajas: el bioma lo decide la humedad
+        if moisture < 0.35 {
+            desert
+        } else if moisture < 0.55 {
+            grassland
+        } else if moisture < 0.75 {
+            forest
+        } else {
+            jungle
+        }
+    } else if elevation < 0.6 {
+        mountain
+    } else {
+        snow
+    };
 
-    if river_pattern < 0.1 {
-        let water_depth = (river_pattern * 10.0).max(0.0);
-        final_color = final_color.lerp(&biolum_blue, 1.0 - water_depth);
+    // Casquetes polares: más grandes sobre tierra que sobre el mar
+    let ice_line = if elevation > sea_level { 0.78 } else { 0.85 };
+    if latitude > ice_line {
+        final_color = final_color.lerp(&snow, (latitude - ice_line) * 12.0);
     }
 
-    let depth_effect = uniforms.noise.get_noise_3d(
-        position.x * 1.8 + time * 0.1,
-        position.y * 1.8,
-        position.z * 1.8
-    ).abs();
-    
-    let fog_intensity = (time * 0.5).sin() * 0.1 + 0.3;
-    final_color = final_color.lerp(&misty_fog, depth_effect * fog_intensity);
-
-    let height_intensity = (position.y * 2.0).sin() * 0.1 + 1.0;
-    final_color * fragment.intensity * height_intensity
+    atmosphere_rim(fragment, uniforms, final_color * fragment.intensity)
 }
 
 fn aurora_planet_shader(fragment: &Fragment, uniforms: &Uniforms) -> Color {
     let position = fragment.vertex_position;
     let time = uniforms.time as f32 * 0.01;
 
-    let pink_base = Color::new(255, 84, 180);
-    let purple_flow = Color::new(144, 97, 255);
-    let lavender_mist = Color::new(210, 158, 255);
-    let cyan_glow = Color::new(99, 231, 255);
-    let deep_blue = Color::new(2, 119, 188);
-    let neon_pink = Color::new(255, 20, 147);
-    let electric_blue = Color::new(45, 226, 230);
-    let golden_glow = Color::new(255, 215, 0);
+    let pink_base = uniforms.palette(255, 84, 180);
+    let purple_flow = uniforms.palette(144, 97, 255);
+    let lavender_mist = uniforms.palette(210, 158, 255);
+    let cyan_glow = uniforms.palette(99, 231, 255);
+    let deep_blue = uniforms.palette(2, 119, 188);
+    let neon_pink = uniforms.palette(255, 20, 147);
+    let electric_blue = uniforms.palette(45, 226, 230);
+    let golden_glow = uniforms.palette(255, 215, 0);
 
     let aurora_base = uniforms.noise.get_noise_3d(
         position.x * 3.5 + time * 0.6,
@@ -630,7 +910,69 @@ fn aurora_planet_shader(fragment: &Fragment, uniforms: &Uniforms) -> Color {
     
     final_color = final_color.lerp(&deep_blue, depth * 0.5);
 
-    final_color * fragment.intensity * 1.2
+    // Cortinas de aurora sobre los polos, visibles también en el lado oscuro
+    let (curtain_color, curtains) = aurora_curtains(position, uniforms);
+    final_color * fragment.intensity * 1.2 + curtain_color * (curtains * 0.8)
+}
+
+// Capa exterior de la aurora: sólo aporta luz, más intensa hacia el borde
+pub fn aurora_glow_shader(fragment: &Fragment, uniforms: &Uniforms) -> (Color, f32) {
+    let (curtain_color, curtains) = aurora_curtains(fragment.vertex_position, uniforms);
+
+    let to_camera = (uniforms.camera_position - world_position(fragment, uniforms)).normalize();
+    let limb = 1.0 - fragment.normal.dot(&to_camera).abs();
+
+    (curtain_color, curtains * (0.15 + limb * 0.5))
+}
+
+// Neblina atmosférica: casi transparente al centro y más densa hacia el borde
+pub fn atmosphere_haze_shader(fragment: &Fragment, uniforms: &Uniforms) -> (Color, f32) {
+    let haze_color = uniforms.palette(140, 190, 255);
+
+    let limb = fresnel(fragment, uniforms, 0.0, 2);
+    let lit = smoothstep(-0.2, 0.3, sun_facing(fragment, uniforms));
+    let backlight = backlit_rim(fragment, uniforms, 0.0, 3);
+
+    (haze_color, (limb * 0.6 * lit + backlight * 0.5).min(1.0))
+}
+
+// Color liso para el contorno del planeta seleccionado
+pub fn outline_shader(_fragment: &Fragment, uniforms: &Uniforms) -> (Color, f32) {
+    (uniforms.palette(255, 220, 120), 1.0)
+}
+
+// Cortinas animadas en bandas de latitud cerca de los polos, con ruido deformado
+fn aurora_curtains(position: Vec3, uniforms: &Uniforms) -> (Color, f32) {
+    let time = uniforms.time as f32 * 0.01;
+    let curtain_green = uniforms.palette(80, 255, 150);
+    let curtain_violet = uniforms.palette(190, 90, 255);
+
+    let direction = position.normalize();
+    let latitude = direction.y.asin().abs();
+    let longitude = direction.z.atan2(direction.x);
+
+    // Banda auroral entre ~55° y ~78° de latitud
+    let band = smoothstep(0.95, 1.1, latitude) * (1.0 - smoothstep(1.25, 1.36, latitude));
+    if band <= 0.0 {
+        return (Color::black(), 0.0);
+    }
+
+    let warped = domain_warp(
+        uniforms.noise,
+        Vec3::new(longitude * 3.0 + time * 0.7, latitude * 4.0, time * 0.3),
+        0.5,
+        FractalParams::new(2, 2.0, 0.5)
+    );
+    let curtain = uniforms.noise.get_noise_3d(
+        warped.x * 4.0,
+        warped.y * 0.5,
+        time * 1.2
+    ) * 0.5 + 0.5;
+    let folds = smoothstep(0.45, 0.85, curtain);
+
+    let height = (latitude - 0.95) / 0.41;
+    let color = curtain_green.lerp(&curtain_violet, height);
+    (color, folds * band)
 }
 
 fn gas_giant_shader(fragment: &Fragment, uniforms: &Uniforms) -> Color {
@@ -638,38 +980,41 @@ fn gas_giant_shader(fragment: &Fragment, uniforms: &Uniforms) -> Color {
     let time = uniforms.time as f32 * 0.005;
 
  
-    let band1_color = Color::new(255, 225, 190); 
-    let band2_color = Color::new(210, 160, 110); 
-    let band3_color = Color::new(180, 130, 90); 
+    let band1_color = uniforms.palette(255, 225, 190); 
+    let band2_color = uniforms.palette(210, 160, 110); 
+    let band3_color = uniforms.palette(180, 130, 90); 
 
-    let storm_core_color = Color::new(255, 100, 80); 
-    let storm_edge_color = Color::new(255, 140, 100); 
+    let storm_core_color = uniforms.palette(255, 100, 80); 
+    let storm_edge_color = uniforms.palette(255, 140, 100); 
 
 
-    let bands = uniforms.noise.get_noise_3d(
-        position.x * 50.0 + time,
-        position.y * 15.0 + time * 0.2,
-        position.z * 50.0,
+    // Bandas arremolinadas: se deforma el dominio antes de muestrear
+    let band_point = domain_warp(
+        uniforms.noise,
+        Vec3::new(position.x * 3.0, position.y * 3.0, position.z * 3.0),
+        0.4,
+        FractalParams::new(2, 2.0, 0.5)
     );
-
-    let secondary_bands = uniforms.noise.get_noise_3d(
-        position.x * 25.0 + time * 0.5,
-        position.y * 10.0 + time * 0.1,
-        position.z * 25.0,
+    // Las bandas cambian de forma con el tiempo en vez de girar congeladas
+    let bands = animated_noise(
+        uniforms.noise,
+        Vec3::new(band_point.x * 16.0 + time, band_point.y * 5.0, band_point.z * 16.0),
+        time * 0.3
     );
 
+    let secondary_bands = animated_fbm(
+        uniforms.noise,
+        Vec3::new(position.x * 25.0 + time * 0.5, position.y * 10.0, position.z * 25.0),
+        time * 0.2,
+        FractalParams::new(2, 2.0, 0.5)
+    );
 
-    let storm = uniforms.noise.get_noise_3d(
-        (position.x + 0.5) * 150.0,
-        (position.y + 0.5) * 150.0,
-        time,
-    ).abs();
 
-    let turbulence = uniforms.noise.get_noise_3d(
-        position.x * 100.0 + time * 2.0,
-        position.y * 100.0,
-        position.z * 100.0,
-    ).abs();
+    let churn = turbulence(
+        uniforms.noise,
+        Vec3::new(position.x * 100.0 + time * 2.0, position.y * 100.0, position.z * 100.0),
+        FractalParams::new(3, 2.0, 0.5)
+    );
 
 
     let base_band_color = if bands > 0.2 {
@@ -680,13 +1025,123 @@ fn gas_giant_shader(fragment: &Fragment, uniforms: &Uniforms) -> Color {
         band3_color
     };
 
-    let mut final_color = if storm > 0.5 && position.x > 0.0 && position.y > 0.0 {
-        storm_core_color.lerp(&storm_edge_color, (storm - 0.5) * 2.0)
-    } else {
-        base_band_color
-    };
+    let mut final_color = base_band_color.lerp(&band3_color, churn * 0.3);
+
+    // Gran mancha: vórtice anticiclónico que gira y deriva en longitud,
+    // donde lo ponga el material
+    if let Some(spot) = uniforms.material.spot {
+        let direction = position.normalize();
+        let latitude = direction.y.asin();
+        let longitude = direction.z.atan2(direction.x);
+
+        let center_longitude = spot.longitude + time * spot.drift;
+        let mut delta_longitude = (longitude - center_longitude) % (2.0 * f32::consts::PI);
+        if delta_longitude > f32::consts::PI {
+            delta_longitude -= 2.0 * f32::consts::PI;
+        } else if delta_longitude < -f32::consts::PI {
+            delta_longitude += 2.0 * f32::consts::PI;
+        }
 
-    final_color = final_color.lerp(&band3_color, turbulence * 0.3);
+        // Elipse más ancha en longitud que en latitud, como la de Júpiter
+        let dx = delta_longitude * latitude.cos() / 1.7;
+        let dy = latitude - spot.latitude;
+        let r = (dx * dx + dy * dy).sqrt() / spot.radius;
+
+        if r < 1.2 {
+            let swirl = dy.atan2(dx) + (1.0 - r) * 5.0 - time * 3.0;
+            let streaks = uniforms.noise.get_noise_2d(
+                swirl.cos() * r * 300.0,
+                swirl.sin() * r * 300.0
+            ) * 0.5 + 0.5;
+
+            let vortex_color = storm_core_color.lerp(&storm_edge_color, r * 0.8 + streaks * 0.3);
+            let mask = 1.0 - smoothstep(0.75, 1.2, r);
+            final_color = final_color.lerp(&vortex_color, mask);
+        }
+    }
 
     final_color * fragment.intensity
 }
+
+fn lava_planet_shader(fragment: &Fragment, uniforms: &Uniforms) -> Color {
+    let position = fragment.vertex_position;
+    let time = uniforms.time as f32 * 0.004;
+
+    let crust_color = uniforms.palette(35, 25, 25);
+    let cooled_color = uniforms.palette(70, 40, 30);
+    let lava_hot = uniforms.palette(255, 230, 120);
+    let lava_warm = uniforms.palette(255, 90, 10);
+
+    // Costra oscura con zonas de roca más fría
+    let crust = uniforms.noise.get_noise_3d(
+        position.x * 60.0,
+        position.y * 60.0,
+        position.z * 60.0
+    ).abs();
+
+    // Grietas: líneas donde el ruido cruza por cero, desplazadas con el tiempo
+    let flow = uniforms.noise.get_noise_3d(
+        position.x * 4.0 + time,
+        position.y * 4.0,
+        position.z * 4.0 - time * 0.5
+    );
+    let cracks = ridged(
+        uniforms.noise,
+        Vec3::new(position.x * 25.0 + flow * 2.0, position.y * 25.0 + time * 0.8, position.z * 25.0),
+        FractalParams::new(2, 2.3, 0.4)
+    );
+    let crack_glow = smoothstep(0.75, 0.95, cracks);
+
+    let pulse = (time * 8.0 + flow * 6.0).sin() * 0.15 + 0.85;
+
+    let rock = crust_color.lerp(&cooled_color, crust) * fragment.intensity;
+    let lava = lava_warm.lerp(&lava_hot, crack_glow * crack_glow) * (crack_glow * pulse);
+
+    // La lava emite luz propia; sólo la costra depende de la iluminación
+    rock + lava
+}
+
+fn desert_planet_shader(fragment: &Fragment, uniforms: &Uniforms) -> Color {
+    let position = fragment.vertex_position;
+    let time = uniforms.time as f32 * 0.002;
+
+    let sand_light = uniforms.palette(235, 200, 140);
+    let sand_dark = uniforms.palette(190, 140, 85);
+    let rock_color = uniforms.palette(140, 95, 60);
+    let storm_color = uniforms.palette(215, 170, 115);
+
+    // Dunas: bandas onduladas, deformadas por ruido para que no sean rectas
+    let dune_warp = uniforms.noise.get_noise_3d(
+        position.x * 8.0,
+        position.y * 8.0,
+        position.z * 8.0
+    );
+    let dunes = ((position.y * 60.0 + position.x * 20.0 + dune_warp * 6.0).sin() * 0.5 + 0.5).powf(1.5);
+
+    let rocks = uniforms.noise.get_noise_3d(
+        position.x * 40.0 + 500.0,
+        position.y * 40.0,
+        position.z * 40.0
+    );
+
+    let mut final_color = sand_dark.lerp(&sand_light, dunes);
+    if rocks > 0.45 {
+        final_color = final_color.lerp(&rock_color, (rocks - 0.45) * 3.0);
+    }
+
+    // Tormentas de polvo: manchas grandes que se desplazan despacio
+    let storm = uniforms.noise.get_noise_3d(
+        position.x * 3.0 + time * 2.0,
+        position.y * 3.0 + time * 0.5,
+        position.z * 3.0 - time
+    );
+    let storm_detail = uniforms.noise.get_noise_3d(
+        position.x * 15.0 + time * 6.0,
+        position.y * 15.0,
+        position.z * 15.0
+    ) * 0.5 + 0.5;
+    let storm_density = smoothstep(0.2, 0.6, storm) * (0.7 + storm_detail * 0.3);
+    final_color = final_color.lerp(&storm_color, storm_density);
+
+    atmosphere_rim(fragment, uniforms, final_color * fragment.intensity)
+}
@@ -0,0 +1,250 @@
+use nalgebra_glm::{Vec3, Mat3};
+use fastnoise_lite::FastNoiseLite;
+use std::f32::consts::PI;
+use crate::framebuffer::Framebuffer;
+use crate::color::Color;
+use crate::noise_utils::{fbm, ridged, FractalParams};
+use crate::Uniforms;
+
+// Imagen en memoria. Se guarda en sRGB y ya convertida a lineal, para no
+// hacer la conversión por píxel en cada cuadro.
+struct Texels {
+    width: usize,
+    height: usize,
+    srgb: Vec<Color>,
+    linear: Vec<Color>,
+}
+
+impl Texels {
+    fn new(width: usize, height: usize, srgb: Vec<Color>) -> Self {
+        let linear = srgb.iter().map(|texel| texel.to_linear()).collect();
+        Texels { width, height, srgb, linear }
+    }
+
+    fn load(filename: &str) -> Result<Self, String> {
+        let image = image::open(filename).map_err(|err| format!("{}: {}", filename, err))?.to_rgb8();
+        let (width, height) = image.dimensions();
+        let srgb = image.pixels().map(|pixel| Color::new(pixel[0], pixel[1], pixel[2])).collect();
+        Ok(Texels::new(width as usize, height as usize, srgb))
+    }
+
+    // Muestreo bilineal en píxeles. Con `wrap` la imagen da la vuelta en
+    // horizontal; si no, se recorta en los bordes.
+    fn bilinear(&self, u: f32, v: f32, wrap: bool, linear: bool) -> Color {
+        let texels = if linear { &self.linear } else { &self.srgb };
+        let x0 = u.floor();
+        let y0 = v.floor();
+        let fx = u - x0;
+        let fy = v - y0;
+
+        let column = |x: f32| {
+            if wrap {
+                (x as isize).rem_euclid(self.width as isize) as usize
+            } else {
+                (x.max(0.0) as usize).min(self.width - 1)
+            }
+        };
+        let row = |y: f32| (y.max(0.0) as usize).min(self.height - 1);
+        let texel = |x: f32, y: f32| texels[row(y) * self.width + column(x)];
+
+        let top = texel(x0, y0).lerp(&texel(x0 + 1.0, y0), fx);
+        let bottom = texel(x0, y0 + 1.0).lerp(&texel(x0 + 1.0, y0 + 1.0), fx);
+        top.lerp(&bottom, fy)
+    }
+}
+
+// Textura equirectangular: longitud en horizontal y latitud en vertical
+pub struct SkyTexture {
+    texels: Texels,
+}
+
+impl SkyTexture {
+    pub fn from_texels(width: usize, height: usize, texels: Vec<Color>) -> Self {
+        SkyTexture { texels: Texels::new(width, height, texels) }
+    }
+
+    pub fn load(filename: &str) -> Result<Self, String> {
+        Ok(SkyTexture { texels: Texels::load(filename)? })
+    }
+
+    // `direction` es unitaria
+    pub fn sample(&self, direction: Vec3, linear: bool) -> Color {
+        let u = (direction.z.atan2(direction.x) / (2.0 * PI) + 0.5) * self.texels.width as f32 - 0.5;
+        let v = (0.5 - direction.y.clamp(-1.0, 1.0).asin() / PI) * self.texels.height as f32 - 0.5;
+        self.texels.bilinear(u, v, true, linear)
+    }
+}
+
+// Seis caras en el orden +X, -X, +Y, -Y, +Z, -Z. Los lados siguen la
+// convención de OpenGL; el techo y el piso vienen girados 90° como los
+// exporta Spacescape (los cielos de assets/skybox).
+pub struct CubeMap {
+    faces: Vec<Texels>,
+}
+
+impl CubeMap {
+    // Carga `<prefijo>_rt`, `_lf`, `_up`, `_dn`, `_bk` y `_ft` con la extensión dada
+    pub fn load(prefix: &str, extension: &str) -> Result<Self, String> {
+        let faces = ["rt", "lf", "up", "dn", "bk", "ft"].iter()
+            .map(|face| Texels::load(&format!("{}_{}.{}", prefix, face, extension)))
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(CubeMap { faces })
+    }
+
+    pub fn sample(&self, direction: Vec3, linear: bool) -> Color {
+        let (x, y, z) = (direction.x, direction.y, direction.z);
+        let (ax, ay, az) = (x.abs(), y.abs(), z.abs());
+
+        // Cara según el eje dominante, y coordenadas dentro de la cara
+        let (face, sc, tc, major) = if ax >= ay && ax >= az {
+            if x > 0.0 { (0, -z, -y, ax) } else { (1, z, -y, ax) }
+        } else if ay >= az {
+            if y > 0.0 { (2, -z, x, ay) } else { (3, -z, -x, ay) }
+        } else if z > 0.0 {
+            (4, x, -y, az)
+        } else {
+            (5, -x, -y, az)
+        };
+
+        let texels = &self.faces[face];
+        let u = (sc / major + 1.0) * 0.5 * texels.width as f32 - 0.5;
+        let v = (tc / major + 1.0) * 0.5 * texels.height as f32 - 0.5;
+        texels.bilinear(u, v, false, linear)
+    }
+}
+
+// Fondo del cielo: la galaxia procedural o una imagen puesta por el usuario
+pub enum SkySource {
+    Equirect(SkyTexture),
+    Cubemap(CubeMap),
+}
+
+impl SkySource {
+    pub fn sample(&self, direction: Vec3, linear: bool) -> Color {
+        match self {
+            SkySource::Equirect(texture) => texture.sample(direction, linear),
+            SkySource::Cubemap(cubemap) => cubemap.sample(direction, linear),
+        }
+    }
+}
+
+// Busca fondos en `directory`: cubemaps (`<nombre>_rt.png`, etc.) e imágenes
+// equirectangulares (`<nombre>_equirect.png` o `.jpg`). Devuelve las rutas
+// que entiende `load_background`, ordenadas.
+pub fn find_backgrounds(directory: &str) -> Vec<String> {
+    let Ok(entries) = std::fs::read_dir(directory) else {
+        return Vec::new();
+    };
+
+    let mut found: Vec<String> = entries
+        .filter_map(|entry| entry.ok())
+        .filter_map(|entry| entry.file_name().into_string().ok())
+        .filter_map(|name| {
+            let (stem, extension) = name.rsplit_once('.')?;
+            if extension != "png" && extension != "jpg" {
+                return None;
+            }
+            if stem.ends_with("_equirect") {
+                Some(format!("{}/{}", directory, name))
+            } else {
+                stem.strip_suffix("_rt").map(|prefix| format!("{}/{}_*.{}", directory, prefix, extension))
+            }
+        })
+        .collect();
+    found.sort();
+    found
+}
+
+// `path` es una imagen equirectangular o `<prefijo>_*.<ext>` para un cubemap
+pub fn load_background(path: &str) -> Result<SkySource, String> {
+    if let Some((prefix, extension)) = path.split_once("_*.") {
+        Ok(SkySource::Cubemap(CubeMap::load(prefix, extension)?))
+    } else {
+        Ok(SkySource::Equirect(SkyTexture::load(path)?))
+    }
+}
+
+// Dirección de la texel (x, y) de una textura equirectangular
+fn texel_direction(x: usize, y: usize, width: usize, height: usize) -> Vec3 {
+    let longitude = ((x as f32 + 0.5) / width as f32 - 0.5) * 2.0 * PI;
+    let latitude = (0.5 - (y as f32 + 0.5) / height as f32) * PI;
+    Vec3::new(latitude.cos() * longitude.cos(), latitude.sin(), latitude.cos() * longitude.sin())
+}
+
+// Vía Láctea y nebulosas procedurales, horneadas una vez al arrancar
+pub fn bake_galaxy(width: usize, height: usize, noise: &FastNoiseLite) -> SkyTexture {
+    // El plano de la galaxia está inclinado respecto a la eclíptica
+    let galaxy_normal = Vec3::new(0.3, 0.85, 0.43).normalize();
+    let galactic_center = Vec3::new(0.9, -0.1, -0.42).normalize();
+    let core_color = Color::new(255, 220, 170);
+    let arm_color = Color::new(150, 170, 230);
+    let dust = FractalParams::new(4, 2.0, 0.5);
+
+    // (dirección, color, tamaño angular)
+    let nebulae = [
+        (Vec3::new(-0.5, 0.4, 0.76), Color::new(220, 60, 140), 0.35),
+        (Vec3::new(0.2, -0.6, -0.77), Color::new(60, 180, 200), 0.3),
+        (Vec3::new(-0.8, -0.2, -0.55), Color::new(200, 70, 50), 0.25),
+        (Vec3::new(0.6, 0.7, 0.38), Color::new(90, 90, 220), 0.28),
+    ];
+
+    let mut texels = Vec::with_capacity(width * height);
+    for y in 0..height {
+        for x in 0..width {
+            let direction = texel_direction(x, y, width, height);
+            let p = direction * 200.0;
+
+            // Banda: gaussiana alrededor del plano, más ancha y brillante hacia el centro
+            let core = direction.dot(&galactic_center).max(0.0).powi(3);
+            let spread = 0.12 + 0.1 * core;
+            let height_above = direction.dot(&galaxy_normal) / spread;
+            let band = (-height_above * height_above).exp();
+
+            let clouds = fbm(noise, p, dust) * 0.5 + 0.5;
+            let lanes = ridged(noise, p * 1.7 + Vec3::new(31.0, 7.0, 11.0), dust);
+            let dust_lanes = 1.0 - 0.7 * lanes * (-height_above * height_above * 4.0).exp();
+            let galaxy = band * (0.35 + 0.65 * clouds) * dust_lanes;
+
+            let mut color = arm_color.lerp(&core_color, core) * (galaxy * (0.18 + 0.25 * core));
+
+            for &(center, tint, size) in &nebulae {
+                let angle = direction.dot(&center.normalize()).clamp(-1.0, 1.0).acos();
+                if angle > size * 1.5 {
+                    continue;
+                }
+                let falloff = (1.0 - angle / (size * 1.5)).powi(2);
+                let wisps = fbm(noise, p * 2.5 + center * 100.0, dust) * 0.5 + 0.5;
+                color += tint * (falloff * wisps * wisps * 0.25);
+            }
+
+            texels.push(color);
+        }
+    }
+
+    SkyTexture::from_texels(width, height, texels)
+}
+
+// Pinta la textura detrás de todo, con el rayo de vista inverso de cada píxel.
+// Se suma al color de fondo; se espera la prueba de profundidad en Always.
+pub fn render_background(framebuffer: &mut Framebuffer, uniforms: &Uniforms, source: &SkySource) {
+    let view = uniforms.view_matrix;
+    // La rotación inversa de la vista lleva direcciones de cámara al mundo
+    let camera_to_world = Mat3::new(
+        view[(0, 0)], view[(0, 1)], view[(0, 2)],
+        view[(1, 0)], view[(1, 1)], view[(1, 2)],
+        view[(2, 0)], view[(2, 1)], view[(2, 2)],
+    ).transpose();
+    let tan_x = 1.0 / uniforms.projection_matrix[(0, 0)];
+    let tan_y = 1.0 / uniforms.projection_matrix[(1, 1)];
+
+    let viewport = framebuffer.viewport();
+    for y in viewport.y..viewport.y + viewport.height {
+        let ndc_y = 1.0 - ((y - viewport.y) as f32 + 0.5) / viewport.height as f32 * 2.0;
+        for x in viewport.x..viewport.x + viewport.width {
+            let ndc_x = ((x - viewport.x) as f32 + 0.5) / viewport.width as f32 * 2.0 - 1.0;
+            let ray = camera_to_world * Vec3::new(ndc_x * tan_x, ndc_y * tan_y, -1.0);
+            let color = source.sample(ray.normalize(), uniforms.linear_workflow);
+            framebuffer.point_additive(x, y, 1.0, color);
+        }
+    }
+}
@@ -0,0 +1,120 @@
+use std::collections::HashMap;
+use nalgebra_glm::{Mat4, Vec3, Vec4};
+
+// Esfera que encierra un objeto de la escena
+#[derive(Clone, Copy, Debug)]
+pub struct Bounds {
+    pub center: Vec3,
+    pub radius: f32,
+}
+
+// Grilla uniforme sobre las esferas de los cuerpos. Cada esfera se anota en
+// todas las celdas que toca, así una consulta solo revisa lo que está cerca
+// en vez de todos los objetos. Se rearma en cada cuadro porque todo se mueve.
+pub struct SpatialGrid {
+    cell_size: f32,
+    entries: Vec<Bounds>,
+    cells: HashMap<(i32, i32, i32), Vec<usize>>,
+}
+
+impl SpatialGrid {
+    pub fn new(cell_size: f32, entries: Vec<Bounds>) -> Self {
+        let mut grid = SpatialGrid { cell_size, entries, cells: HashMap::new() };
+        for index in 0..grid.entries.len() {
+            let bounds = grid.entries[index];
+            let extent = Vec3::new(bounds.radius, bounds.radius, bounds.radius);
+            let (low, high) = (grid.cell(bounds.center - extent), grid.cell(bounds.center + extent));
+            for x in low.0..=high.0 {
+                for y in low.1..=high.1 {
+                    for z in low.2..=high.2 {
+                        grid.cells.entry((x, y, z)).or_default().push(index);
+                    }
+                }
+            }
+        }
+        grid
+    }
+
+    pub fn entries(&self) -> &[Bounds] {
+        &self.entries
+    }
+
+    fn cell(&self, point: Vec3) -> (i32, i32, i32) {
+        (
+            (point.x / self.cell_size).floor() as i32,
+            (point.y / self.cell_size).floor() as i32,
+            (point.z / self.cell_size).floor() as i32,
+        )
+    }
+
+    // Índices de las esferas cuyas celdas tocan la caja [min, max], sin repetir.
+    // Es una fase amplia: quien llama hace la prueba exacta.
+    pub fn query_box(&self, min: Vec3, max: Vec3) -> Vec<usize> {
+        let (low, high) = (self.cell(min), self.cell(max));
+        let mut found = Vec::new();
+        for x in low.0..=high.0 {
+            for y in low.1..=high.1 {
+                for z in low.2..=high.2 {
+                    if let Some(indices) = self.cells.get(&(x, y, z)) {
+                        found.extend_from_slice(indices);
+                    }
+                }
+            }
+        }
+        found.sort_unstable();
+        found.dedup();
+        found
+    }
+
+    // Candidatos a tocar una esfera que recorre el tramo `start` → `end`
+    pub fn query_segment(&self, start: Vec3, end: Vec3, radius: f32) -> Vec<usize> {
+        let extent = Vec3::new(radius, radius, radius);
+        self.query_box(start.inf(&end) - extent, start.sup(&end) + extent)
+    }
+
+    // Marca qué esferas quedan al menos en parte dentro del frustum. Se
+    // descartan primero las celdas enteras que caen fuera.
+    pub fn query_frustum(&self, frustum: &Frustum) -> Vec<bool> {
+        let mut visible = vec![false; self.entries.len()];
+        let half = self.cell_size * 0.5;
+        let cell_radius = half * 3.0f32.sqrt();
+
+        for (&(x, y, z), indices) in &self.cells {
+            let center = Vec3::new(x as f32, y as f32, z as f32) * self.cell_size + Vec3::new(half, half, half);
+            if !frustum.intersects_sphere(center, cell_radius) {
+                continue;
+            }
+            for &index in indices {
+                if !visible[index] {
+                    let bounds = self.entries[index];
+                    visible[index] = frustum.intersects_sphere(bounds.center, bounds.radius);
+                }
+            }
+        }
+        visible
+    }
+}
+
+// Los seis planos de la pirámide de visión, con la normal hacia adentro
+pub struct Frustum {
+    planes: [Vec4; 6],
+}
+
+impl Frustum {
+    // A partir de proyección · vista (método de Gribb y Hartmann)
+    pub fn from_matrix(view_projection: &Mat4) -> Self {
+        let row = |i: usize| -> Vec4 { view_projection.row(i).transpose() };
+        let (r0, r1, r2, r3) = (row(0), row(1), row(2), row(3));
+        let planes = [r3 + r0, r3 - r0, r3 + r1, r3 - r1, r3 + r2, r3 - r2].map(|plane| {
+            let length = Vec3::new(plane.x, plane.y, plane.z).magnitude();
+            if length > 0.0 { plane / length } else { plane }
+        });
+        Frustum { planes }
+    }
+
+    pub fn intersects_sphere(&self, center: Vec3, radius: f32) -> bool {
+        self.planes.iter().all(|plane| {
+            plane.x * center.x + plane.y * center.y + plane.z * center.z + plane.w >= -radius
+        })
+    }
+}
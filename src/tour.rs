@@ -0,0 +1,92 @@
+use nalgebra_glm::Vec3;
+use crate::camera::Camera;
+use crate::tween::{CameraPose, CameraTween, Easing};
+
+const TRAVEL_FRAMES: u32 = 180;
+const ORBIT_FRAMES: u32 = 300;
+const ORBIT_SPEED: f32 = 0.006;
+
+// Planeta a visitar en este cuadro: posición y radio en el mundo
+#[derive(Clone, Copy, Debug)]
+pub struct TourTarget {
+    pub position: Vec3,
+    pub radius: f32,
+}
+
+enum Stage {
+    Travel { stop: usize, tween: CameraTween },
+    Orbit { stop: usize, frame: u32 },
+    Return { tween: CameraTween },
+}
+
+// Recorrido automático: vuela a cada planeta, lo rodea despacio y sigue
+// al siguiente; al final vuelve a la pose en la que estaba la cámara.
+pub struct Tour {
+    // Índices de los objetivos, en el orden de la visita
+    route: Vec<usize>,
+    stage: Stage,
+    start: CameraPose,
+}
+
+impl Tour {
+    pub fn new(camera: &Camera, route: Vec<usize>) -> Self {
+        let start = CameraPose::from_camera(camera);
+        let stage = if route.is_empty() {
+            Stage::Return { tween: CameraTween::new(start, 1, Easing::Linear) }
+        } else {
+            Stage::Travel { stop: 0, tween: CameraTween::new(start, TRAVEL_FRAMES, Easing::EaseInOut) }
+        };
+        Tour { route, stage, start }
+    }
+
+    // Mueve la cámara un cuadro. Devuelve false cuando el recorrido terminó.
+    pub fn update(&mut self, camera: &mut Camera, targets: &[TourTarget]) -> bool {
+        let current = CameraPose::from_camera(camera);
+
+        match &mut self.stage {
+            Stage::Travel { stop, tween } => {
+                let target = targets[self.route[*stop]];
+                tween.step(&viewpoint(target, 0.0)).apply(camera);
+                if tween.is_finished() {
+                    self.stage = Stage::Orbit { stop: *stop, frame: 0 };
+                }
+            }
+            Stage::Orbit { stop, frame } => {
+                let target = targets[self.route[*stop]];
+                *frame += 1;
+                viewpoint(target, *frame as f32 * ORBIT_SPEED).apply(camera);
+
+                if *frame >= ORBIT_FRAMES {
+                    let next = *stop + 1;
+                    let tween = CameraTween::new(current, TRAVEL_FRAMES, Easing::EaseInOut);
+                    self.stage = if next < self.route.len() {
+                        Stage::Travel { stop: next, tween }
+                    } else {
+                        Stage::Return { tween }
+                    };
+                }
+            }
+            Stage::Return { tween } => {
+                tween.step(&self.start).apply(camera);
+                return !tween.is_finished();
+            }
+        }
+        true
+    }
+}
+
+// Pose mirando al planeta desde afuera (del lado contrario al sol) y un poco
+// arriba, girada `angle` radianes alrededor de él
+fn viewpoint(target: TourTarget, angle: f32) -> CameraPose {
+    let outward = Vec3::new(target.position.x, 0.0, target.position.z);
+    let outward = if outward.magnitude() > 0.001 { outward.normalize() } else { Vec3::new(0.0, 0.0, 1.0) };
+    let base = (outward + Vec3::new(0.0, 0.45, 0.0)).normalize();
+    let direction = nalgebra_glm::rotate_vec3(&base, angle, &Vec3::new(0.0, 1.0, 0.0));
+
+    let distance = target.radius * 3.0 + 2.0;
+    CameraPose {
+        eye: target.position + direction * distance,
+        center: target.position,
+        up: Vec3::new(0.0, 1.0, 0.0),
+    }
+}
@@ -0,0 +1,112 @@
+use nalgebra_glm::{Vec3, Vec4};
+use crate::framebuffer::Framebuffer;
+use crate::color::Color;
+use crate::shaders::encode_depth;
+use crate::Uniforms;
+
+// Posiciones recientes de un cuerpo en un buffer circular. Se guarda una
+// cada `spacing` cuadros para que la estela cubra más recorrido.
+pub struct Trail {
+    points: Vec<Vec3>,
+    capacity: usize,
+    // Dónde va el próximo punto cuando el buffer ya está lleno
+    next: usize,
+    spacing: u32,
+    counter: u32,
+}
+
+impl Trail {
+    pub fn new(capacity: usize, spacing: u32) -> Self {
+        Trail { points: Vec::with_capacity(capacity), capacity: capacity.max(2), next: 0, spacing: spacing.max(1), counter: 0 }
+    }
+
+    pub fn record(&mut self, position: Vec3) {
+        self.counter += 1;
+        if self.counter < self.spacing {
+            return;
+        }
+        self.counter = 0;
+
+        if self.points.len() < self.capacity {
+            self.points.push(position);
+        } else {
+            self.points[self.next] = position;
+            self.next = (self.next + 1) % self.capacity;
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.points.clear();
+        self.next = 0;
+    }
+
+    // Del punto más viejo al más nuevo
+    fn ordered(&self) -> impl Iterator<Item = &Vec3> {
+        let (newer, older) = self.points.split_at(self.next);
+        older.iter().chain(newer.iter())
+    }
+
+    // Polilínea que se apaga hacia el extremo más viejo; `head` es la
+    // posición actual del cuerpo
+    pub fn render(&self, framebuffer: &mut Framebuffer, uniforms: &Uniforms, head: Vec3, color: Color) {
+        self.render_around(framebuffer, uniforms, Vec3::new(0.0, 0.0, 0.0), head, color);
+    }
+
+    // Igual, pero con los puntos guardados relativos a `origin`: la estela
+    // acompaña a lo que se mueva en ese marco
+    pub fn render_around(&self, framebuffer: &mut Framebuffer, uniforms: &Uniforms, origin: Vec3, head: Vec3, color: Color) {
+        if self.points.len() < 2 {
+            return;
+        }
+        let points: Vec<Vec3> = self.ordered().map(|point| point + origin).chain(std::iter::once(head)).collect();
+        render_polyline(framebuffer, uniforms, &points, |t| color * t);
+    }
+}
+
+// Polilínea aditiva en el mundo. `color_at` recibe la posición a lo largo de
+// la línea, de 0 (primer punto) a 1 (último). Pasa la prueba de profundidad
+// pero no la escribe.
+pub fn render_polyline(framebuffer: &mut Framebuffer, uniforms: &Uniforms, points: &[Vec3], color_at: impl Fn(f32) -> Color) {
+    let count = points.len().saturating_sub(1).max(1) as f32;
+
+    let mut previous: Option<(f32, f32, f32)> = None;
+    for (i, point) in points.iter().enumerate() {
+        let current = project(uniforms, *point);
+        if let (Some(start), Some(end)) = (previous, current) {
+            let color0 = color_at((i - 1) as f32 / count);
+            let color1 = color_at(i as f32 / count);
+            segment(framebuffer, start, end, color0, color1);
+        }
+        previous = current;
+    }
+}
+
+fn project(uniforms: &Uniforms, position: Vec3) -> Option<(f32, f32, f32)> {
+    let clip = uniforms.projection_matrix * uniforms.view_matrix * Vec4::new(position.x, position.y, position.z, 1.0);
+    if clip.w <= 0.0 {
+        return None;
+    }
+
+    let ndc = clip / clip.w;
+    if ndc.x.abs() > 2.0 || ndc.y.abs() > 2.0 {
+        return None;
+    }
+    let (screen_x, screen_y) = uniforms.ndc_to_screen(ndc.x, ndc.y);
+    Some((screen_x, screen_y, encode_depth(&clip, uniforms.depth_mode)))
+}
+
+fn segment(framebuffer: &mut Framebuffer, start: (f32, f32, f32), end: (f32, f32, f32), start_color: Color, end_color: Color) {
+    let steps = (end.0 - start.0).abs().max((end.1 - start.1).abs()).ceil().max(1.0) as usize;
+
+    // Sin el último píxel, que es el primero del tramo siguiente
+    for step in 0..steps {
+        let t = step as f32 / steps as f32;
+        let x = start.0 + (end.0 - start.0) * t;
+        let y = start.1 + (end.1 - start.1) * t;
+        if x < 0.0 || y < 0.0 {
+            continue;
+        }
+        let depth = start.2 + (end.2 - start.2) * t;
+        framebuffer.point_additive(x as usize, y as usize, depth, start_color.lerp(&end_color, t));
+    }
+}
@@ -1,77 +1,145 @@
 use nalgebra_glm::{Vec3, dot};
 use crate::fragment::Fragment;
 use crate::vertex::Vertex;
-use crate::color::Color;
-
-pub fn triangle(v1: &Vertex, v2: &Vertex, v3: &Vertex) -> Vec<Fragment> {
-  let mut fragments = Vec::new();
+use crate::framebuffer::Viewport;
+
+// Bits de precisión por debajo del píxel: los vértices se redondean a 1/256
+// de píxel, así un planeta que gira despacio mueve sus bordes de a poco y no
+// salta de un píxel al siguiente. El recorrido se hace con enteros exactos.
+const SUBPIXEL_BITS: u32 = 8;
+const SUBPIXEL_ONE: i64 = 1 << SUBPIXEL_BITS;
+// Lo que queda más lejos se aplasta a este borde para que los productos
+// de las funciones de borde no se desborden
+const MAX_COORDINATE: f32 = 65536.0;
+
+// Entrega cada fragmento del triángulo a `emit` apenas se calcula, sin
+// juntarlos en una lista. Solo se recorren los píxeles dentro de `clip`.
+pub fn triangle(v1: &Vertex, v2: &Vertex, v3: &Vertex, clip: &Viewport, mut emit: impl FnMut(Fragment)) {
+  // Con los vértices en el otro sentido el área sale negativa: se dan vuelta
+  // para que adentro las tres funciones de borde sean positivas
+  let (fa, fb, fc) = (to_fixed(&v1.transformed_position), to_fixed(&v2.transformed_position), to_fixed(&v3.transformed_position));
+  let (v2, v3, fb, fc) = if edge_function(fa, fb, fc) < 0 {
+    (v3, v2, fc, fb)
+  } else {
+    (v2, v3, fb, fc)
+  };
   let (a, b, c) = (v1.transformed_position, v2.transformed_position, v3.transformed_position);
 
-  let (min_x, min_y, max_x, max_y) = calculate_bounding_box(&a, &b, &c);
+  let triangle_area = edge_function(fa, fb, fc);
+  if triangle_area <= 0 {
+    return;
+  }
+  let triangle_area = triangle_area as f32;
+
+  let Some((min_x, min_y, max_x, max_y)) = calculate_bounding_box(&a, &b, &c, clip) else {
+    return;
+  };
 
   let light_dir = Vec3::new(0.0, 0.0, 1.0);
 
-  let triangle_area = edge_function(&a, &b, &c);
+  // Cada borde da el peso del vértice opuesto; se evalúan una vez en el
+  // centro del primer píxel de la caja y después solo se suman los pasos
+  let edges = [Edge::new(fb, fc), Edge::new(fc, fa), Edge::new(fa, fb)];
+  let start = (min_x as i64 * SUBPIXEL_ONE + SUBPIXEL_ONE / 2, min_y as i64 * SUBPIXEL_ONE + SUBPIXEL_ONE / 2);
+  let mut row = [edges[0].at(start), edges[1].at(start), edges[2].at(start)];
 
   for y in min_y..=max_y {
+    let mut weights = row;
     for x in min_x..=max_x {
-      let point = Vec3::new(x as f32 + 0.5, y as f32 + 0.5, 0.0);
-
-      let (w1, w2, w3) = barycentric_coordinates(&point, &a, &b, &c, triangle_area);
-
-      if w1 >= 0.0 && w1 <= 1.0 && 
-         w2 >= 0.0 && w2 <= 1.0 &&
-         w3 >= 0.0 && w3 <= 1.0 {
+      if edges.iter().zip(&weights).all(|(edge, &value)| edge.covers(value)) {
+        let w1 = weights[0] as f32 / triangle_area;
+        let w2 = weights[1] as f32 / triangle_area;
+        let w3 = weights[2] as f32 / triangle_area;
 
         let normal = v1.transformed_normal * w1 + v2.transformed_normal * w2 + v3.transformed_normal * w3;
         let normal = normal.normalize();
 
         let intensity = dot(&normal, &light_dir).max(0.0);
 
-        let base_color = Color::new(100, 100, 100);
-        let lit_color = base_color * intensity;
+        let color = v1.color * w1 + v2.color * w2 + v3.color * w3;
 
         let depth = a.z * w1 + b.z * w2 + c.z * w3;
 
         let vertex_position = v1.position * w1 + v2.position * w2 + v3.position * w3;
+        let tex_coords = v1.tex_coords * w1 + v2.tex_coords * w2 + v3.tex_coords * w3;
 
-        fragments.push(
-            Fragment::new(
+        emit(Fragment {
+            tex_coords,
+            barycentric: Vec3::new(w1, w2, w3),
+            ..Fragment::new(
                 x as f32,
                 y as f32,
-                lit_color,
+                color,
                 depth,
                 normal,
                 intensity,
                 vertex_position,
             )
-        );
+        });
       }
+      for (value, edge) in weights.iter_mut().zip(&edges) {
+        *value += edge.step_x;
+      }
+    }
+    for (value, edge) in row.iter_mut().zip(&edges) {
+      *value += edge.step_y;
     }
   }
-
-  fragments
 }
 
-fn calculate_bounding_box(v1: &Vec3, v2: &Vec3, v3: &Vec3) -> (i32, i32, i32, i32) {
-    let min_x = v1.x.min(v2.x).min(v3.x).floor() as i32;
-    let min_y = v1.y.min(v2.y).min(v3.y).floor() as i32;
-    let max_x = v1.x.max(v2.x).max(v3.x).ceil() as i32;
-    let max_y = v1.y.max(v2.y).max(v3.y).ceil() as i32;
+// Posición en pantalla en punto fijo
+type FixedPoint = (i64, i64);
 
-    (min_x, min_y, max_x, max_y)
+fn to_fixed(p: &Vec3) -> FixedPoint {
+    let fixed = |value: f32| (value.clamp(-MAX_COORDINATE, MAX_COORDINATE) * SUBPIXEL_ONE as f32).round() as i64;
+    (fixed(p.x), fixed(p.y))
 }
 
-fn barycentric_coordinates(p: &Vec3, a: &Vec3, b: &Vec3, c: &Vec3, area: f32) -> (f32, f32, f32) {
-    let w1 = edge_function(b, c, p) / area;
-    let w2 = edge_function(c, a, p) / area;
-    let w3 = edge_function(a, b, p) / area;
-
-    (w1, w2, w3)
+// Función de borde de `from` a `to` con lo que cambia al avanzar un píxel
+struct Edge {
+    from: FixedPoint,
+    to: FixedPoint,
+    step_x: i64,
+    step_y: i64,
+    // Regla de arriba a la izquierda: un píxel justo sobre el borde que
+    // comparten dos triángulos es solo del que lo tiene arriba o a la izquierda
+    top_left: bool,
 }
 
-fn edge_function(a: &Vec3, b: &Vec3, c: &Vec3) -> f32 {
-    (c.x - a.x) * (b.y - a.y) - (c.y - a.y) * (b.x - a.x)
+impl Edge {
+    fn new(from: FixedPoint, to: FixedPoint) -> Self {
+        let (dx, dy) = (to.0 - from.0, to.1 - from.1);
+        Edge {
+            from,
+            to,
+            step_x: dy * SUBPIXEL_ONE,
+            step_y: -dx * SUBPIXEL_ONE,
+            top_left: dy > 0 || (dy == 0 && dx < 0),
+        }
+    }
+
+    fn at(&self, p: FixedPoint) -> i64 {
+        edge_function(self.from, self.to, p)
+    }
+
+    fn covers(&self, value: i64) -> bool {
+        value > 0 || (value == 0 && self.top_left)
+    }
 }
 
+// Caja del triángulo recortada a `clip`; None si no quedan píxeles
+fn calculate_bounding_box(v1: &Vec3, v2: &Vec3, v3: &Vec3, clip: &Viewport) -> Option<(i32, i32, i32, i32)> {
+    if clip.width == 0 || clip.height == 0 {
+        return None;
+    }
+    let min_x = (v1.x.min(v2.x).min(v3.x).floor() as i32).max(clip.x as i32);
+    let min_y = (v1.y.min(v2.y).min(v3.y).floor() as i32).max(clip.y as i32);
+    let max_x = (v1.x.max(v2.x).max(v3.x).ceil() as i32).min((clip.x + clip.width - 1) as i32);
+    let max_y = (v1.y.max(v2.y).max(v3.y).ceil() as i32).min((clip.y + clip.height - 1) as i32);
+
+    (min_x <= max_x && min_y <= max_y).then_some((min_x, min_y, max_x, max_y))
+}
 
+fn edge_function(a: FixedPoint, b: FixedPoint, c: FixedPoint) -> i64 {
+    (c.0 - a.0) * (b.1 - a.1) - (c.1 - a.1) * (b.0 - a.0)
+}
@@ -0,0 +1,82 @@
+use nalgebra_glm::Vec3;
+use crate::camera::Camera;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Easing {
+    Linear,
+    EaseInOut,
+    EaseOut,
+}
+
+impl Easing {
+    pub fn apply(self, t: f32) -> f32 {
+        let t = t.clamp(0.0, 1.0);
+        match self {
+            Easing::Linear => t,
+            // Cúbica: arranca y frena suave
+            Easing::EaseInOut => {
+                if t < 0.5 {
+                    4.0 * t * t * t
+                } else {
+                    1.0 - (-2.0 * t + 2.0).powi(3) / 2.0
+                }
+            }
+            Easing::EaseOut => 1.0 - (1.0 - t).powi(3),
+        }
+    }
+}
+
+// Lo necesario para reconstruir la vista de la cámara
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct CameraPose {
+    pub eye: Vec3,
+    pub center: Vec3,
+    pub up: Vec3,
+}
+
+impl CameraPose {
+    pub fn from_camera(camera: &Camera) -> Self {
+        CameraPose { eye: camera.eye, center: camera.center, up: camera.up }
+    }
+
+    pub fn apply(&self, camera: &mut Camera) {
+        camera.eye = self.eye;
+        camera.center = self.center;
+        camera.up = self.up;
+        camera.has_changed = true;
+    }
+
+    pub fn lerp(&self, other: &CameraPose, t: f32) -> CameraPose {
+        CameraPose {
+            eye: self.eye + (other.eye - self.eye) * t,
+            center: self.center + (other.center - self.center) * t,
+            up: (self.up + (other.up - self.up) * t).normalize(),
+        }
+    }
+}
+
+// Transición de la cámara desde una pose fija hasta un destino. El destino
+// se pasa en cada cuadro, así puede seguir a un planeta que se mueve.
+pub struct CameraTween {
+    from: CameraPose,
+    duration: u32,
+    elapsed: u32,
+    easing: Easing,
+}
+
+impl CameraTween {
+    pub fn new(from: CameraPose, duration: u32, easing: Easing) -> Self {
+        CameraTween { from, duration: duration.max(1), elapsed: 0, easing }
+    }
+
+    // Avanza un cuadro y devuelve la pose interpolada hacia `to`
+    pub fn step(&mut self, to: &CameraPose) -> CameraPose {
+        self.elapsed = (self.elapsed + 1).min(self.duration);
+        let t = self.easing.apply(self.elapsed as f32 / self.duration as f32);
+        self.from.lerp(to, t)
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.elapsed >= self.duration
+    }
+}
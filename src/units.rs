@@ -0,0 +1,49 @@
+use crate::ephemeris::{ScaleMode, KM_PER_AU};
+
+const LIGHT_SPEED_KM_S: f64 = 299_792.458;
+// Cuadros por segundo con la pausa de 16 ms del bucle principal
+const FRAMES_PER_SECOND: f64 = 60.0;
+
+// Paso de unidades del mundo a km y UA. La escena no es lineal (las
+// distancias están comprimidas), así que se calibra con la órbita de la
+// Tierra: ahí una unidad vale lo que dice esta tabla.
+#[derive(Clone, Copy, Debug)]
+pub struct Units {
+    au_per_unit: f64,
+}
+
+impl Units {
+    // Sistema estilizado: la Tierra orbita a 9 unidades del sol
+    pub fn stylized() -> Self {
+        Units { au_per_unit: 1.0 / 9.0 }
+    }
+
+    // Sistema real con la escala `mode`
+    pub fn for_scale(mode: ScaleMode) -> Self {
+        Units { au_per_unit: 1.0 / mode.distance(1.0) as f64 }
+    }
+
+    pub fn km(&self, units: f32) -> f64 {
+        units as f64 * self.au_per_unit * KM_PER_AU as f64
+    }
+
+    // En km de cerca y en UA de lejos
+    pub fn format_distance(&self, units: f32) -> String {
+        let km = self.km(units);
+        if km < 1.0e6 {
+            format!("{:.0} km", km)
+        } else {
+            format!("{:.2} UA", km / KM_PER_AU as f64)
+        }
+    }
+
+    // En km/s, o en múltiplos de la velocidad de la luz si la pasa de lejos
+    pub fn format_speed(&self, units_per_frame: f32) -> String {
+        let km_per_second = self.km(units_per_frame) * FRAMES_PER_SECOND;
+        if km_per_second < LIGHT_SPEED_KM_S * 0.1 {
+            format!("{:.1} km/s", km_per_second)
+        } else {
+            format!("{:.2} c", km_per_second / LIGHT_SPEED_KM_S)
+        }
+    }
+}
@@ -6,6 +6,7 @@ pub struct Vertex {
   pub position: Vec3,
   pub normal: Vec3,
   pub tex_coords: Vec2,
+  // Multiplica lo que sale del shader; en blanco no cambia nada
   pub color: Color,
   pub transformed_position: Vec3,
   pub transformed_normal: Vec3,
@@ -17,7 +18,7 @@ impl Vertex {
       position,
       normal,
       tex_coords,
-      color: Color::black(),
+      color: Color::white(),
       transformed_position: position,
       transformed_normal: normal,
     }
@@ -46,7 +47,7 @@ impl Default for Vertex {
       position: Vec3::new(0.0, 0.0, 0.0),
       normal: Vec3::new(0.0, 1.0, 0.0),
       tex_coords: Vec2::new(0.0, 0.0),
-      color: Color::black(),
+      color: Color::white(),
       transformed_position: Vec3::new(0.0, 0.0, 0.0),
       transformed_normal: Vec3::new(0.0, 1.0, 0.0),
     }
@@ -0,0 +1,92 @@
+// Imágenes de referencia del cuadro sin ventana. Cada prueba dibuja una
+// escena fija y la compara con su PNG en tests/golden. Después de un cambio
+// que mueve la imagen a propósito se regeneran con
+// `ACTUALIZAR_REFERENCIAS=1 cargo test --test golden`.
+
+use std::path::PathBuf;
+use sistema_solar::headless::{self, FrameOptions};
+use sistema_solar::obj::Obj;
+use sistema_solar::shaders::DebugView;
+use sistema_solar::vertex::Vertex;
+
+const WIDTH: usize = 160;
+const HEIGHT: usize = 120;
+// Diferencia por canal que todavía cuenta como el mismo color
+const CHANNEL_TOLERANCE: u8 = 6;
+// Parte de los píxeles que puede pasarse de la tolerancia (bordes que caen
+// de otro lado con otra precisión de punto flotante)
+const MAX_DIFFERENT: f32 = 0.002;
+
+fn sphere() -> Vec<Vertex> {
+    Obj::load(concat!(env!("CARGO_MANIFEST_DIR"), "/assets/sphere.obj"))
+        .expect("no se pudo cargar la esfera")
+        .get_vertex_array()
+}
+
+fn golden_path(name: &str) -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/golden").join(format!("{}.png", name))
+}
+
+// Parte de los píxeles en que las dos imágenes se pasan de la tolerancia
+fn different_share(actual: &image::RgbImage, expected: &image::RgbImage) -> f32 {
+    let different = actual.pixels().zip(expected.pixels())
+        .filter(|(a, b)| a.0.iter().zip(b.0.iter()).any(|(a, b)| a.abs_diff(*b) > CHANNEL_TOLERANCE))
+        .count();
+    different as f32 / (actual.width() * actual.height()) as f32
+}
+
+fn render(options: FrameOptions) -> image::RgbImage {
+    headless::to_image(&headless::render_frame(&sphere(), &options))
+}
+
+fn assert_matches(name: &str, actual: &image::RgbImage, expected: &image::RgbImage) {
+    assert_eq!(actual.dimensions(), expected.dimensions(), "{}: otro tamaño", name);
+    let share = different_share(actual, expected);
+    if share > MAX_DIFFERENT {
+        // Lo que salió queda en el directorio temporal de target para mirarlo
+        let output = PathBuf::from(env!("CARGO_TARGET_TMPDIR")).join(format!("{}.png", name));
+        actual.save(&output).expect("no se pudo guardar la imagen obtenida");
+        panic!("{}: {:.2}% de los píxeles no coincide; la imagen obtenida está en {}", name, share * 100.0, output.display());
+    }
+}
+
+fn check(name: &str, options: FrameOptions) {
+    let actual = render(options);
+    let path = golden_path(name);
+
+    if std::env::var_os("ACTUALIZAR_REFERENCIAS").is_some() {
+        actual.save(&path).expect("no se pudo guardar la referencia");
+        return;
+    }
+
+    let expected = image::open(&path)
+        .unwrap_or_else(|err| panic!("{}: {} (se genera con ACTUALIZAR_REFERENCIAS=1)", path.display(), err))
+        .to_rgb8();
+    assert_matches(name, &actual, &expected);
+}
+
+#[test]
+fn system_at_start() {
+    check("sistema", FrameOptions::new(WIDTH, HEIGHT, 0));
+}
+
+#[test]
+fn system_animated() {
+    check("sistema_t300", FrameOptions::new(WIDTH, HEIGHT, 300));
+}
+
+#[test]
+fn normals_view() {
+    check("normales", FrameOptions { debug_view: DebugView::Normals, ..FrameOptions::new(WIDTH, HEIGHT, 0) });
+}
+
+// La pre-pasada y el camino diferido solo cambian cuánto se sombrea, no la
+// imagen: se comparan contra el camino de siempre, sin referencia guardada
+#[test]
+fn prepass_and_deferred_match_forward() {
+    let forward = render(FrameOptions::new(WIDTH, HEIGHT, 0));
+    let prepass = render(FrameOptions { depth_prepass: true, ..FrameOptions::new(WIDTH, HEIGHT, 0) });
+    let deferred = render(FrameOptions { deferred: true, ..FrameOptions::new(WIDTH, HEIGHT, 0) });
+    assert_matches("prepasada", &prepass, &forward);
+    assert_matches("diferido", &deferred, &forward);
+}